@@ -8,7 +8,7 @@ use stm32f7xx_hal::{pac, prelude::*};
 pub const IDLE_STACK_SIZE: usize = 256;
 pub const TASK_STACK_SIZE: usize = 2048;
 
-defmt::timestamp!("{=u64:us}", rucos::get_current_tick());
+defmt::timestamp!("{=u64:us}", rucos::get_current_tick() as u64);
 
 pub struct KernelResources {
     pub scb: SCB,
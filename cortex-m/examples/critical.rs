@@ -0,0 +1,70 @@
+//! A supervisor task pausing a worker task only while it is asleep:
+//! - Task 1 does a little work and then sleeps, over and over
+//! - Task 0 checks the state of Task 1 and suspends it only if it is sleeping,
+//!   inside `critical` so the tick cannot wake it between the check and the
+//!   suspend
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+use rucos_cortex_m::TaskState;
+
+/// Worker task ID
+const WORKER: usize = 1;
+
+fn worker_task(_: u32) -> ! {
+    loop {
+        info!("Task {} working", rucos::get_current_task());
+        rucos::sleep(rucos::TICK_RATE_HZ / 3);
+    }
+}
+
+fn supervisor_task(_: u32) -> ! {
+    loop {
+        rucos::sleep(rucos::TICK_RATE_HZ);
+
+        // Read the state, decide, and act on it as one step
+        let paused = rucos::critical(|_| {
+            let asleep = matches!(rucos::get_task_state(WORKER), Some(TaskState::Pending));
+            if asleep {
+                rucos::suspend(Some(WORKER));
+            }
+
+            asleep
+        });
+
+        if paused {
+            info!("Task {} paused the worker", rucos::get_current_task());
+            rucos::sleep(rucos::TICK_RATE_HZ);
+            rucos::resume(WORKER);
+        }
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, supervisor_task, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(WORKER, 1, &mut task1_stack, worker_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
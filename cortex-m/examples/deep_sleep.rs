@@ -0,0 +1,135 @@
+//! The idle task enters STOP mode for long idle periods:
+//! - Task 0 sleeps for 5 seconds at a time, so the kernel is idle for long
+//!   periods
+//! - The deep sleep hook enters STOP mode and resynchronizes the kernel tick
+//!   on wake up
+//!
+//! The low power timer is a stub: the user button (PC13) wakes the core, and
+//! the stub reports the requested sleep as elapsed, so Task 0 runs on each
+//! press. An application would use a timer that keeps running in STOP mode to
+//! wake the core and measure the sleep, e.g. the RTC wake up timer or LPTIM1
+//! clocked by the LSE.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::ptr::addr_of_mut;
+use cortex_m::peripheral::NVIC;
+use defmt::info;
+use rucos_cortex_m as rucos;
+use rucos_cortex_m::TickResync;
+use stm32f7xx_hal::pac::{self, interrupt};
+
+/// Count rate of the low power timer
+const LOW_POWER_HZ: u64 = 32_768;
+
+/// Shortest idle period worth entering STOP mode for, in ticks
+const MIN_DEEP_SLEEP_TICKS: rucos::Tick = 100;
+
+/// User button pin number on GPIOC
+const BUTTON_PIN: u32 = 13;
+
+/// Converter from low power timer counts to kernel ticks, only used by the hook
+static mut RESYNC: TickResync = TickResync::new(LOW_POWER_HZ, rucos::TICK_RATE_HZ as u64);
+
+/// Counts programmed into the low power timer
+static mut WAKEUP_COUNTS: u64 = 0;
+
+/// Program the low power timer to wake up the core (stub)
+fn wakeup_timer_start(counts: u64) {
+    unsafe { WAKEUP_COUNTS = counts };
+}
+
+/// Read the counts elapsed since `wakeup_timer_start` (stub)
+fn wakeup_timer_elapsed() -> u64 {
+    unsafe { WAKEUP_COUNTS }
+}
+
+fn button_setup() {
+    let rcc = unsafe { &*pac::RCC::ptr() };
+    let syscfg = unsafe { &*pac::SYSCFG::ptr() };
+    let exti = unsafe { &*pac::EXTI::ptr() };
+
+    // Enable the GPIOC and SYSCFG clocks, and the PWR clock for STOP mode
+    rcc.ahb1enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 2)) });
+    rcc.apb1enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 28)) });
+    rcc.apb2enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 14)) });
+
+    // Route PC13 to EXTI13 and interrupt on the rising edge, which also wakes
+    // the core from STOP mode
+    syscfg
+        .exticr4
+        .modify(|r, w| unsafe { w.bits((r.bits() & !(0xF << 4)) | (0x2 << 4)) });
+    exti.rtsr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << BUTTON_PIN)) });
+    exti.imr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << BUTTON_PIN)) });
+
+    let mut nvic = unsafe { cortex_m::Peripherals::steal() }.NVIC;
+    rucos::configure_irq(
+        &mut nvic,
+        pac::Interrupt::EXTI15_10,
+        rucos::KERNEL_INTERRUPT_PRIORITY,
+        true,
+    );
+    unsafe { NVIC::unmask(pac::Interrupt::EXTI15_10) };
+}
+
+#[interrupt]
+fn EXTI15_10() {
+    let exti = unsafe { &*pac::EXTI::ptr() };
+    exti.pr.write(|w| unsafe { w.bits(1 << BUTTON_PIN) });
+}
+
+fn deep_sleep(sleep_ticks: rucos::Tick) -> rucos::Tick {
+    let resync = unsafe { &mut *addr_of_mut!(RESYNC) };
+    wakeup_timer_start(resync.counts(u64::from(sleep_ticks)));
+
+    // Enter STOP mode with the voltage regulator in low power mode
+    let pwr = unsafe { &*pac::PWR::ptr() };
+    pwr.cr1
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 0)) });
+    let mut scb = unsafe { cortex_m::Peripherals::steal() }.SCB;
+    scb.set_sleepdeep();
+    cortex_m::asm::wfi();
+    scb.clear_sleepdeep();
+
+    // The core wakes up on the 16 MHz HSI, which `common::setup` already runs
+    // from, an application using the PLL would reconfigure it here
+    let elapsed = resync.ticks(wakeup_timer_elapsed());
+    rucos::Tick::try_from(elapsed).unwrap_or(rucos::Tick::MAX)
+}
+
+fn sleepy_task(_: u32) -> ! {
+    loop {
+        info!("Task {} awake", rucos::get_current_task());
+        rucos::sleep(5 * rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+    rucos::set_deep_sleep_hook(Some(deep_sleep), MIN_DEEP_SLEEP_TICKS);
+    button_setup();
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, sleepy_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
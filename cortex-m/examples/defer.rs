@@ -0,0 +1,118 @@
+//! A button interrupt defers its work to a task:
+//! - The EXTI handler for the user button (PC13) only defers a work item
+//! - Task 0 (high priority server) toggles the blue LED (PB7) for each item
+//! - Task 1 prints a heartbeat every second
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use cortex_m::peripheral::NVIC;
+use defmt::info;
+use rucos_cortex_m as rucos;
+use stm32f7xx_hal::pac::{self, interrupt};
+
+/// Deferred work item to toggle the LED
+const WORK_TOGGLE_LED: usize = 0;
+
+/// User button pin number on GPIOC
+const BUTTON_PIN: u32 = 13;
+
+/// Blue LED pin number on GPIOB
+const LED_PIN: u32 = 7;
+
+fn gpio_setup() {
+    let rcc = unsafe { &*pac::RCC::ptr() };
+    let gpiob = unsafe { &*pac::GPIOB::ptr() };
+    let syscfg = unsafe { &*pac::SYSCFG::ptr() };
+    let exti = unsafe { &*pac::EXTI::ptr() };
+
+    // Enable GPIOB, GPIOC, and SYSCFG clocks
+    rcc.ahb1enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 1) | (1 << 2)) });
+    rcc.apb2enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 14)) });
+
+    // LED pin as output
+    gpiob.moder.modify(|r, w| unsafe {
+        w.bits((r.bits() & !(0b11 << (LED_PIN * 2))) | (0b01 << (LED_PIN * 2)))
+    });
+
+    // Route PC13 to EXTI13 and interrupt on the rising edge
+    syscfg
+        .exticr4
+        .modify(|r, w| unsafe { w.bits((r.bits() & !(0xF << 4)) | (0x2 << 4)) });
+    exti.rtsr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << BUTTON_PIN)) });
+    exti.imr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << BUTTON_PIN)) });
+
+    // Kernel APIs may only be called from interrupts at or below the kernel
+    // interrupt priority, which `configure_irq` checks, the NVIC is not
+    // otherwise used after `common::setup`
+    let mut nvic = unsafe { cortex_m::Peripherals::steal() }.NVIC;
+    rucos::configure_irq(
+        &mut nvic,
+        pac::Interrupt::EXTI15_10,
+        rucos::KERNEL_INTERRUPT_PRIORITY,
+        true,
+    );
+    unsafe { NVIC::unmask(pac::Interrupt::EXTI15_10) };
+}
+
+#[interrupt]
+fn EXTI15_10() {
+    let exti = unsafe { &*pac::EXTI::ptr() };
+    exti.pr.write(|w| unsafe { w.bits(1 << BUTTON_PIN) });
+
+    rucos::defer(WORK_TOGGLE_LED);
+}
+
+fn server(_: u32) -> ! {
+    let gpiob = unsafe { &*pac::GPIOB::ptr() };
+
+    loop {
+        match rucos::work_wait() {
+            WORK_TOGGLE_LED => {
+                info!("Task {} toggling the LED", rucos::get_current_task());
+                gpiob
+                    .odr
+                    .modify(|r, w| unsafe { w.bits(r.bits() ^ (1 << LED_PIN)) });
+            }
+            work_id => info!("Unknown work item {}", work_id),
+        }
+    }
+}
+
+fn heartbeat(_: u32) -> ! {
+    loop {
+        info!("Task {} is alive", rucos::get_current_task());
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+    gpio_setup();
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, server, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(1, 1, &mut task1_stack, heartbeat, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -0,0 +1,68 @@
+//! An integer-only task and a floating point task running side by side. Only
+//! the floating point task gets the floating point unit, on its first floating
+//! point instruction, and has its floating point registers saved on context
+//! switches. Both tasks check their results survive the switches.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::hint::black_box;
+use defmt::{assert_eq, info};
+use rucos_cortex_m as rucos;
+
+fn integer_task(_: u32) -> ! {
+    let mut sum: u32 = 0;
+    let mut count: u32 = 0;
+
+    loop {
+        sum = black_box(sum + count);
+        count += 1;
+        assert_eq!(sum, count * (count - 1) / 2);
+        info!("Integer task sum {}", sum);
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+fn float_task(_: u32) -> ! {
+    let mut value: f32 = 0.0;
+    let mut count: u32 = 0;
+
+    loop {
+        // Exact in single precision for the first 2^24 iterations
+        value = black_box(value + 1.0);
+        count += 1;
+        assert_eq!(value, count as f32);
+
+        // The integer task has run and been switched out by now
+        assert_eq!(rucos::task_uses_fpu(0), Some(false));
+        assert_eq!(rucos::task_uses_fpu(1), Some(true));
+        info!("Float task value {}", value);
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, integer_task, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(1, 1, &mut task1_stack, float_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -0,0 +1,57 @@
+//! The idle hook kicks the independent watchdog, so the device resets if the
+//! tasks ever keep the CPU busy long enough to starve the idle task.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+use stm32f7xx_hal::pac;
+
+/// Watchdog timeout in milliseconds, using the 32 kHz LSI divided by 32
+const WATCHDOG_TIMEOUT_MS: u32 = 1000;
+
+fn watchdog_start() {
+    let iwdg = unsafe { &*pac::IWDG::ptr() };
+
+    iwdg.kr.write(|w| unsafe { w.bits(0xCCCC) }); // Start
+    iwdg.kr.write(|w| unsafe { w.bits(0x5555) }); // Unlock PR and RLR
+    iwdg.pr.write(|w| unsafe { w.bits(0b011) }); // Divide by 32
+    iwdg.rlr.write(|w| unsafe { w.bits(WATCHDOG_TIMEOUT_MS) });
+}
+
+fn watchdog_kick() {
+    let iwdg = unsafe { &*pac::IWDG::ptr() };
+    iwdg.kr.write(|w| unsafe { w.bits(0xAAAA) });
+}
+
+fn task(_: u32) -> ! {
+    loop {
+        info!("Hello from Task {}", rucos::get_current_task());
+        rucos::sleep(rucos::TICK_RATE_HZ / 2);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+    rucos::set_idle_hook(Some(watchdog_kick));
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, task, None);
+
+    info!("Starting");
+    watchdog_start();
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
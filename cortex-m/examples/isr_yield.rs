@@ -0,0 +1,142 @@
+//! A button interrupt calls several kernel APIs and pends a single switch:
+//! - The EXTI handler for the user button (PC13) gives a semaphore, sets an
+//!   event flag, and posts the press count to a mailbox
+//! - Task 0 waits on the semaphore and toggles the blue LED (PB7)
+//! - Task 1 waits on the event flag and the mailbox and prints the press count
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use cortex_m::peripheral::NVIC;
+use defmt::info;
+use rucos_cortex_m as rucos;
+use stm32f7xx_hal::pac::{self, interrupt};
+
+/// Semaphore given on each button press
+const SEM_PRESS: usize = 0;
+
+/// Event group set on each button press
+const EVENT_PRESS: usize = 0;
+
+/// Event flag for a button press
+const FLAG_PRESS: u32 = 1 << 0;
+
+/// Mailbox holding the number of button presses
+const MAILBOX_COUNT: usize = 0;
+
+/// User button pin number on GPIOC
+const BUTTON_PIN: u32 = 13;
+
+/// Blue LED pin number on GPIOB
+const LED_PIN: u32 = 7;
+
+/// Number of button presses
+static PRESSES: AtomicUsize = AtomicUsize::new(0);
+
+fn gpio_setup() {
+    let rcc = unsafe { &*pac::RCC::ptr() };
+    let gpiob = unsafe { &*pac::GPIOB::ptr() };
+    let syscfg = unsafe { &*pac::SYSCFG::ptr() };
+    let exti = unsafe { &*pac::EXTI::ptr() };
+
+    // Enable GPIOB, GPIOC, and SYSCFG clocks
+    rcc.ahb1enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 1) | (1 << 2)) });
+    rcc.apb2enr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << 14)) });
+
+    // LED pin as output
+    gpiob.moder.modify(|r, w| unsafe {
+        w.bits((r.bits() & !(0b11 << (LED_PIN * 2))) | (0b01 << (LED_PIN * 2)))
+    });
+
+    // Route PC13 to EXTI13 and interrupt on the rising edge
+    syscfg
+        .exticr4
+        .modify(|r, w| unsafe { w.bits((r.bits() & !(0xF << 4)) | (0x2 << 4)) });
+    exti.rtsr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << BUTTON_PIN)) });
+    exti.imr
+        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << BUTTON_PIN)) });
+
+    // Kernel APIs may only be called from interrupts at or below the kernel
+    // interrupt priority, which `configure_irq` checks, the NVIC is not
+    // otherwise used after `common::setup`
+    let mut nvic = unsafe { cortex_m::Peripherals::steal() }.NVIC;
+    rucos::configure_irq(
+        &mut nvic,
+        pac::Interrupt::EXTI15_10,
+        rucos::KERNEL_INTERRUPT_PRIORITY,
+        true,
+    );
+    unsafe { NVIC::unmask(pac::Interrupt::EXTI15_10) };
+}
+
+#[interrupt]
+fn EXTI15_10() {
+    let exti = unsafe { &*pac::EXTI::ptr() };
+    exti.pr.write(|w| unsafe { w.bits(1 << BUTTON_PIN) });
+
+    let presses = PRESSES.fetch_add(1, Ordering::Relaxed) + 1;
+
+    // Each call may wake a task, pend the context switch once at the end
+    let mut switch = rucos::sem_give_from_isr(SEM_PRESS);
+    switch |= rucos::event_set_from_isr(EVENT_PRESS, FLAG_PRESS);
+    switch |= rucos::mailbox_post_from_isr(MAILBOX_COUNT, presses);
+    rucos::isr_yield_if_needed(switch);
+}
+
+fn led_task(_: u32) -> ! {
+    let gpiob = unsafe { &*pac::GPIOB::ptr() };
+
+    loop {
+        rucos::sem_take(SEM_PRESS, None);
+        info!("Task {} toggling the LED", rucos::get_current_task());
+        gpiob
+            .odr
+            .modify(|r, w| unsafe { w.bits(r.bits() ^ (1 << LED_PIN)) });
+    }
+}
+
+fn count_task(_: u32) -> ! {
+    loop {
+        rucos::event_wait(EVENT_PRESS, FLAG_PRESS, false, None);
+        rucos::event_clear(EVENT_PRESS, FLAG_PRESS);
+
+        // The handler posts the count before this task runs
+        if let Some(presses) = rucos::mailbox_fetch(MAILBOX_COUNT, None) {
+            info!("Task {} saw {} presses", rucos::get_current_task(), presses);
+        }
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+    rucos::sem_create(SEM_PRESS);
+    rucos::event_create(EVENT_PRESS);
+    rucos::mailbox_create(MAILBOX_COUNT);
+    gpio_setup();
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, led_task, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(1, 1, &mut task1_stack, count_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -0,0 +1,51 @@
+//! A kernel sized for 16 tasks, built with the `max-tasks-16` feature:
+//! - Tasks 0 - 14 print and sleep, the idle task takes the last slot
+//! - Creating a 16th task would exceed `MAX_NUM_TASKS`
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+const NUM_TASKS: usize = rucos::MAX_NUM_TASKS - 1;
+
+/// Smaller stacks, to fit all tasks in RAM
+const STACK_SIZE: usize = 1024;
+
+fn task(arg: u32) -> ! {
+    loop {
+        info!("Hello from Task {}", rucos::get_current_task());
+        rucos::sleep(rucos::TICK_RATE_HZ + arg as rucos::Tick);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing a kernel for {} tasks", rucos::MAX_NUM_TASKS);
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    let mut stacks: [[u8; STACK_SIZE]; NUM_TASKS] = [[0; STACK_SIZE]; NUM_TASKS];
+    for (id, stack) in stacks.iter_mut().enumerate() {
+        info!("Creating Task {}", id);
+        rucos::create(id, id, stack, task, Some(id as u32));
+    }
+
+    let mut extra_stack: [u8; STACK_SIZE] = [0; STACK_SIZE];
+    assert_eq!(
+        rucos::try_create(NUM_TASKS, 0, &mut extra_stack, task, None),
+        Err(rucos::CreateError::CapacityExceeded)
+    );
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
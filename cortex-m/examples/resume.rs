@@ -0,0 +1,72 @@
+//! Resuming a higher priority task preempts the caller right away:
+//! - Task 0 suspends itself and waits to be resumed
+//! - Task 1 resumes Task 0, which runs before `resume` returns, so its log
+//!   comes first and shows the same tick as the resume
+//! - Task 1 then logs how many ticks passed, which is zero unless a tick fell
+//!   in between
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+/// Number of times Task 0 was resumed
+static RESUMED: AtomicU32 = AtomicU32::new(0);
+
+fn waiting_task(_: u32) -> ! {
+    loop {
+        rucos::suspend(None);
+        RESUMED.fetch_add(1, Ordering::Relaxed);
+        info!(
+            "Task {} resumed on tick {}",
+            rucos::get_current_task(),
+            rucos::get_current_tick()
+        );
+    }
+}
+
+fn resuming_task(_: u32) -> ! {
+    loop {
+        rucos::sleep(rucos::TICK_RATE_HZ);
+
+        let before = rucos::get_current_tick();
+        let count = RESUMED.load(Ordering::Relaxed);
+        rucos::resume(0);
+
+        // Task 0 already ran and suspended itself again
+        assert_eq!(RESUMED.load(Ordering::Relaxed), count + 1);
+        info!(
+            "Task {} back after {} ticks",
+            rucos::get_current_task(),
+            rucos::get_current_tick() - before
+        );
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, waiting_task, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(1, 1, &mut task1_stack, resuming_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -0,0 +1,53 @@
+//! Two tasks synchronized with a semaphore:
+//! - Task 0 (consumer) blocks on the semaphore
+//! - Task 1 (producer) gives the semaphore every second
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+const SEM_ID: usize = 0;
+
+fn consumer(_: u32) -> ! {
+    loop {
+        rucos::sem_take(SEM_ID, None);
+        info!("Task {} took the semaphore", rucos::get_current_task());
+    }
+}
+
+fn producer(_: u32) -> ! {
+    loop {
+        rucos::sleep(rucos::TICK_RATE_HZ);
+        info!("Task {} giving the semaphore", rucos::get_current_task());
+        rucos::sem_give(SEM_ID);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+    rucos::sem_create(SEM_ID);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, consumer, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(1, 1, &mut task1_stack, producer, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
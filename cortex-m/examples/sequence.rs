@@ -0,0 +1,76 @@
+//! An ordered startup sequence of three steps, then the normal task set:
+//! - Task 0 runs the steps with `sequence`, each in its own short-lived task
+//! - The steps take different amounts of time, and run at a lower priority than
+//!   task 0, yet always finish in order
+//! - Once the last step is done, task 0 creates the application task and
+//!   returns, which deletes it
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::ptr::addr_of_mut;
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+/// Task ID shared by the steps, one step exists at a time
+const STEP_ID: usize = 1;
+
+/// Stack shared by the steps, outliving each of them
+static mut STEP_STACK: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+
+/// Stack of the application task created after the sequence
+static mut APP_STACK: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+
+fn init_clocks(step: u32) {
+    info!("Step {}: Clocks", step);
+    rucos::sleep(rucos::TICK_RATE_HZ / 2);
+}
+
+fn init_bus(step: u32) {
+    info!("Step {}: Bus", step);
+    rucos::sleep(rucos::TICK_RATE_HZ / 8);
+}
+
+fn init_sensors(step: u32) {
+    // Only runs once the bus is up
+    info!("Step {}: Sensors", step);
+}
+
+fn app_task(_: u32) -> ! {
+    loop {
+        info!("Task {} running", rucos::get_current_task());
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+fn startup_task(_: u32) {
+    // Only one step uses the stack at a time, see `sequence`
+    let stack = unsafe { &mut *addr_of_mut!(STEP_STACK) };
+    rucos::sequence(STEP_ID, 2, stack, &[init_clocks, init_bus, init_sensors]);
+    info!("Startup sequence done");
+
+    let stack = unsafe { &mut *addr_of_mut!(APP_STACK) };
+    rucos::create(2, 1, stack, app_task, None);
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create_oneshot(0, 0, &mut task0_stack, startup_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
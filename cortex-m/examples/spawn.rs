@@ -0,0 +1,69 @@
+//! A running task spawns worker tasks on demand:
+//! - Task 0 creates a higher priority worker, which runs as soon as it is
+//!   created, then waits for the worker to finish before creating the next one
+//! - Each worker runs to completion and is deleted, so its ID and stack are
+//!   reused by the next worker
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::ptr::addr_of_mut;
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+/// Task ID shared by the workers, one worker exists at a time
+const WORKER_ID: usize = 1;
+
+/// Stack shared by the workers, outliving each of them
+static mut WORKER_STACK: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+
+fn worker_task(job: u32) {
+    info!("Worker {} running job {}", rucos::get_current_task(), job);
+    rucos::sleep(rucos::TICK_RATE_HZ / 4);
+    info!("Worker {} finished job {}", rucos::get_current_task(), job);
+}
+
+fn parent_task(_: u32) -> ! {
+    let mut job = 0;
+
+    loop {
+        info!("Task {} spawning job {}", rucos::get_current_task(), job);
+
+        // Only one worker uses the stack at a time, see `join` below
+        let stack = unsafe { &mut *addr_of_mut!(WORKER_STACK) };
+        let worker = rucos::create_oneshot(WORKER_ID, 0, stack, worker_task, Some(job));
+
+        // The worker has a higher priority, so it already ran until it slept
+        info!(
+            "Task {} back after spawning job {}",
+            rucos::get_current_task(),
+            job
+        );
+        worker.join();
+
+        job += 1;
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 1, &mut task0_stack, parent_task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -0,0 +1,62 @@
+//! A task that reports its own peak stack usage, growing its stack a little
+//! more on every iteration. Useful for tuning `TASK_STACK_SIZE`.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::hint::black_box;
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+#[inline(never)]
+fn use_stack(depth: u32) -> u32 {
+    let buffer = black_box([depth; 8]);
+    if depth == 0 {
+        buffer[0]
+    } else {
+        use_stack(depth - 1) + buffer[7]
+    }
+}
+
+fn task(_: u32) -> ! {
+    let mut depth = 0;
+
+    loop {
+        let _ = use_stack(depth);
+
+        let id = rucos::get_current_task();
+        let high_water = rucos::stack_high_water(id).unwrap();
+        info!(
+            "Task {} used {} of {} stack bytes at depth {}",
+            id,
+            high_water,
+            common::TASK_STACK_SIZE,
+            depth
+        );
+
+        depth = (depth + 1) % 16;
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
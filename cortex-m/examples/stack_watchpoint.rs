@@ -0,0 +1,69 @@
+//! A task that grows its stack until it overflows, caught by a data watchpoint
+//! on its stack limit. Run without a debugger halting the core, otherwise the
+//! watchpoint halts it instead of raising the DebugMonitor exception.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use core::hint::black_box;
+use cortex_m_rt::exception;
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+#[inline(never)]
+fn use_stack(depth: u32) -> u32 {
+    let buffer = black_box([depth; 8]);
+    if depth == 0 {
+        buffer[0]
+    } else {
+        use_stack(depth - 1) + buffer[7]
+    }
+}
+
+fn task(_: u32) -> ! {
+    rucos::arm_stack_watchpoint(rucos::get_current_task());
+
+    let mut depth = 0;
+    loop {
+        info!("Task 0 at depth {}", depth);
+        let _ = use_stack(depth);
+
+        depth += 16;
+        rucos::sleep(1);
+    }
+}
+
+#[exception]
+fn DebugMonitor() {
+    info!(
+        "Task {} overflowed its stack, canary intact: {}",
+        rucos::get_current_task(),
+        rucos::check_stack(rucos::get_current_task())
+    );
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, task, None);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -11,6 +11,7 @@ mod common;
 
 use defmt::info;
 use rucos_cortex_m as rucos;
+use rucos_cortex_m::TaskBuilder;
 
 fn task0(_: u32) -> ! {
     let mut counter = 0;
@@ -62,11 +63,20 @@ fn main() -> ! {
 
     info!("Creating Task 0");
     let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
-    rucos::create(0, 0, &mut task0_stack, task0, None);
+    TaskBuilder::new(task0)
+        .id(0)
+        .priority(0)
+        .stack(&mut task0_stack)
+        .spawn();
 
     info!("Creating Task 1");
     let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
-    rucos::create(1, 1, &mut task1_stack, task1, None);
+    TaskBuilder::new(task1)
+        .id(1)
+        .priority(1)
+        .stack(&mut task1_stack)
+        .name("Task 1")
+        .spawn();
 
     info!("Starting");
     rucos::start(
@@ -0,0 +1,61 @@
+//! Two tasks share a function, each configured by its own argument:
+//! - Task 0 prints every second
+//! - Task 1 prints every half second
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+struct TaskConfig {
+    name: &'static str,
+    period: rucos::Tick,
+}
+
+static FAST: TaskConfig = TaskConfig {
+    name: "fast",
+    period: rucos::TICK_RATE_HZ / 2,
+};
+
+static SLOW: TaskConfig = TaskConfig {
+    name: "slow",
+    period: rucos::TICK_RATE_HZ,
+};
+
+fn task(config: &'static TaskConfig) -> ! {
+    loop {
+        info!(
+            "Hello from Task {} ({})",
+            rucos::get_current_task(),
+            config.name
+        );
+        rucos::sleep(config.period);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create_with_arg(0, 0, &mut task0_stack, task, &SLOW);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create_with_arg(1, 1, &mut task1_stack, task, &FAST);
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
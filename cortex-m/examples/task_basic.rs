@@ -10,7 +10,7 @@ use defmt::info;
 use rucos_cortex_m as rucos;
 
 fn task_template(arg: u32) -> ! {
-    let delay: u64 = arg as u64;
+    let delay = arg as rucos::Tick;
     assert!(delay > 0);
 
     loop {
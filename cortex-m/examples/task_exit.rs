@@ -0,0 +1,54 @@
+//! A task that runs to completion and is deleted, while a periodic task keeps
+//! running afterward.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+
+fn oneshot_task(count: u32) {
+    for i in 0..count {
+        info!("Task {} iteration {}", rucos::get_current_task(), i);
+        rucos::sleep(rucos::TICK_RATE_HZ / 2);
+    }
+
+    info!("Task {} done", rucos::get_current_task());
+}
+
+fn periodic_task(_: u32) -> ! {
+    loop {
+        info!(
+            "Task {} alive, Task 1 state {}",
+            rucos::get_current_task(),
+            defmt::Debug2Format(&rucos::get_task_state(1))
+        );
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating Task 0");
+    let mut task0_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(0, 0, &mut task0_stack, periodic_task, None);
+
+    info!("Creating Task 1");
+    let mut task1_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create_oneshot(1, 1, &mut task1_stack, oneshot_task, Some(3));
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -0,0 +1,67 @@
+//! A supervisor task controlling a worker task through its handle.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+use defmt::info;
+use rucos_cortex_m as rucos;
+use rucos_cortex_m::TaskHandle;
+
+fn worker_task(_: u32) -> ! {
+    loop {
+        info!("Worker {} running", TaskHandle::current().id());
+        rucos::sleep(rucos::TICK_RATE_HZ / 4);
+    }
+}
+
+fn supervisor_task(worker_id: u32) -> ! {
+    let worker = TaskHandle::from_id(worker_id as usize);
+
+    for _ in 0..3 {
+        worker.suspend();
+        info!("Worker state {}", defmt::Debug2Format(&worker.state()));
+        rucos::sleep(rucos::TICK_RATE_HZ);
+
+        worker.resume();
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+
+    worker.delete();
+    info!("Worker state {}", defmt::Debug2Format(&worker.state()));
+
+    loop {
+        rucos::sleep(rucos::TICK_RATE_HZ);
+    }
+}
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut resources = common::setup();
+
+    info!("Initializing");
+    let mut idle_stack: [u8; common::IDLE_STACK_SIZE] = [0; common::IDLE_STACK_SIZE];
+    rucos::init(&mut idle_stack, None);
+
+    info!("Creating worker");
+    let mut worker_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    let worker = rucos::create(1, 1, &mut worker_stack, worker_task, None);
+
+    info!("Creating supervisor");
+    let mut supervisor_stack: [u8; common::TASK_STACK_SIZE] = [0; common::TASK_STACK_SIZE];
+    rucos::create(
+        0,
+        0,
+        &mut supervisor_stack,
+        supervisor_task,
+        Some(worker.id() as u32),
+    );
+
+    info!("Starting");
+    rucos::start(
+        &mut resources.scb,
+        &mut resources.systick,
+        resources.clocks.hclk().to_Hz(),
+    );
+}
@@ -4,21 +4,154 @@
 #![feature(naked_functions)]
 
 use core::arch::asm;
+use core::cell::UnsafeCell;
 use core::mem::MaybeUninit;
-use core::ptr::write_volatile;
-use cortex_m::interrupt::free;
-use cortex_m::peripheral::{scb, syst::SystClkSource, SCB, SYST};
-use rucos::Kernel;
+use core::ptr::{read_volatile, write_volatile};
+use cortex_m::interrupt::{free, CriticalSection, InterruptNumber};
+use cortex_m::peripheral::{scb, NVIC, SCB, SYST};
+use rucos::{AtomicTick, Kernel, MemPool, Tick as _};
+
+pub use cortex_m::peripheral::syst::SystClkSource;
+#[cfg(feature = "stats")]
+pub use rucos::Jitter;
+pub use rucos::{
+    CreateError, MutexError, NotifyAction, PendReason, PriorityQueue, ResetError, SchedPolicy,
+    SpscRing, TaskError, TaskInfo, TaskState, TickResync, TieBreak, WaitResult, WakeOrder,
+    WakeReason, IDLE_TASK_ID, IDLE_TASK_PRIORITY, MAX_READ_LOCKS, TLS_SLOTS,
+};
 
 const _TICK_RATE_HZ: u32 = 1000;
 
+/// Kernel tick data type
+///
+/// Defaults to `u64`, which never wraps in practice. The `tick-u32` feature
+/// selects `u32` to save RAM, which wraps around after about 49.7 days at the
+/// default `TICK_RATE_HZ`. The kernel compares ticks allowing for the wrap, so
+/// delays and timeouts must then be less than half that, about 24.8 days.
+#[cfg(not(feature = "tick-u32"))]
+pub type Tick = u64;
+
+/// Kernel tick data type, see the `tick-u32` feature
+#[cfg(feature = "tick-u32")]
+pub type Tick = u32;
+
 /// Kernel tick rate in hertz
-pub const TICK_RATE_HZ: u64 = _TICK_RATE_HZ as u64;
+pub const TICK_RATE_HZ: Tick = _TICK_RATE_HZ as Tick;
 
 /// Maximum number of kernel tasks
-pub const MAX_NUM_TASKS: usize = 256;
+///
+/// Defaults to 256, a smaller kernel is selected with one of the `max-tasks-8`,
+/// `max-tasks-16`, `max-tasks-32`, `max-tasks-64`, or `max-tasks-128` features.
+/// If more than one is enabled, the largest wins. The capacity sizes every
+/// kernel object list, so it dominates the RAM used by the kernel.
+pub const MAX_NUM_TASKS: usize = if cfg!(feature = "max-tasks-128") {
+    128
+} else if cfg!(feature = "max-tasks-64") {
+    64
+} else if cfg!(feature = "max-tasks-32") {
+    32
+} else if cfg!(feature = "max-tasks-16") {
+    16
+} else if cfg!(feature = "max-tasks-8") {
+    8
+} else {
+    256
+};
+
+/// Interrupt priority boundary of the kernel
+///
+/// The context switch masks interrupts with this priority value or higher (less
+/// urgent) using `BASEPRI`, so interrupts with a lower priority value still
+/// preempt it. Those interrupts must not call kernel APIs, so interrupt handlers
+/// calling the kernel must have this priority value or higher. SysTick and
+/// PendSV are both set to the lowest priority when the kernel starts, so they
+/// never delay an application interrupt and never preempt each other.
+///
+/// The value is level 1 with 4 or more implemented priority bits (the STM32F7
+/// implements 4). With fewer bits it would be truncated to level 0 and mask no
+/// interrupts, so `start` checks it against the bits the NVIC implements.
+pub const KERNEL_INTERRUPT_PRIORITY: u8 = 0x10;
+
+/// Smallest task stack size in bytes, which fits the canary and the initial task
+/// context wherever the stack is placed
+///
+/// The top of the stack is aligned down to 8 bytes, which can lose up to 7 bytes.
+/// A task needs more than this to run, the check only prevents the initial
+/// context from being written below the stack.
+pub const MIN_STACK_SIZE: usize = rucos::stack_min_size(INITIAL_CONTEXT_SIZE, STACK_ALIGN);
+
+/// Size of the initial task context, 17 registers
+const INITIAL_CONTEXT_SIZE: usize = 17 * 4;
+
+/// Alignment of the top of a task stack
+///
+/// The AAPCS requires the stack pointer to be 8-byte aligned at public
+/// interfaces, and the exception return pops the frame of R0 - R3, R12, LR, PC
+/// and xPSR (plus S0 - S15 and FPSCR for a floating point frame) from an 8-byte
+/// aligned address. The initial context ends at the aligned top, so the frame
+/// popped on the first switch to the task is aligned.
+const STACK_ALIGN: usize = 8;
+
+static mut KERNEL: MaybeUninit<Kernel<u32, Tick, MAX_NUM_TASKS>> = MaybeUninit::uninit();
+
+/// Copy of the kernel tick, updated with it and read without a critical section
+static TICK_COUNTER: AtomicTick = AtomicTick::new();
+
+/// Largest value of the 24-bit SysTick reload register
+const SYSTICK_MAX_RELOAD: u32 = 0x00FF_FFFF;
 
-static mut KERNEL: MaybeUninit<Kernel<u32, u64, MAX_NUM_TASKS>> = MaybeUninit::uninit();
+/// CPACR bits giving full access to the floating point unit (CP10 and CP11)
+const CPACR_FPU: u32 = 0xF << 20;
+
+/// CFSR bit set by a UsageFault from a disabled coprocessor
+const CFSR_NOCP: u32 = 1 << 19;
+
+/// ICSR bit set if the active exception returns to thread mode
+const ICSR_RETTOBASE: u32 = 1 << 11;
+
+/// DEMCR bit enabling the DebugMonitor exception
+#[cfg(feature = "stack-watchpoint")]
+const DEMCR_MON_EN: u32 = 1 << 16;
+
+/// DWT comparator function generating a debug event on a data write
+#[cfg(feature = "stack-watchpoint")]
+const DWT_FUNCTION_WRITE: u32 = 0b0110;
+
+/// SysTick reload value for one kernel tick, set when the kernel starts
+static mut TICK_RELOAD: u32 = 0;
+
+/// Suppress tick interrupts while the idle task runs
+static mut TICKLESS: bool = false;
+
+/// Function called by the default idle task once per loop iteration
+static mut IDLE_HOOK: Option<fn()> = None;
+
+/// Function called by the default idle task to deep sleep, and the minimum
+/// number of idle ticks to call it for
+static mut DEEP_SLEEP: Option<(fn(Tick) -> Tick, Tick)> = None;
+
+/// SysTick was reprogrammed after tickless idle and must be restored on the next
+/// tick interrupt
+static mut TICKLESS_RESTORE: bool = false;
+
+/// Worst-case cycles spent in the context switch
+#[cfg(feature = "switch-timing")]
+static mut SWITCH_CYCLES: rucos::CycleStats = rucos::CycleStats::new();
+
+/// Cycle counter of the Data Watchpoint and Trace unit, enabled by `start`
+#[cfg(feature = "switch-timing")]
+struct Dwt;
+
+#[cfg(feature = "switch-timing")]
+impl rucos::CycleCounter for Dwt {
+    fn now(&self) -> u32 {
+        cortex_m::peripheral::DWT::cycle_count()
+    }
+}
+
+/// Task restarted while it was running, with the argument to pass to its entry
+/// function, whose stack is re-initialized on the next context switch
+static mut PENDING_RESTART: Option<(usize, Option<u32>)> = None;
 
 /// Initialize the kernel and create the idle task
 ///
@@ -30,213 +163,3159 @@ static mut KERNEL: MaybeUninit<Kernel<u32, u64, MAX_NUM_TASKS>> = MaybeUninit::u
 /// # Note
 ///
 /// The idle task is the lowest priority task and is always ready to run, it
-/// must not block or call any kernel APIs (e.g. `sleep`)
+/// must not block or call any kernel APIs (e.g. `sleep`). The default idle task
+/// executes `wfi` to save power, a user idle task is run as provided.
+///
+/// Kernel APIs that can block the current task (e.g. `sleep`) panic if called
+/// from an interrupt handler.
 pub fn init(idle_stack: &mut [u8], user_idle_task: Option<fn(u32) -> !>) {
+    init_with_tie_break(idle_stack, user_idle_task, TieBreak::default());
+}
+
+/// Initialize the kernel with a scheduler tie-break policy and create the idle
+/// task
+///
+/// # Arguments
+///
+/// * `idle_stack`: Idle task stack
+/// * `user_idle_task`: Optional idle task function
+/// * `tie_break`: Policy used to select among runnable tasks with the same
+///   priority
+///
+/// # Note
+///
+/// See `init` for idle task requirements
+pub fn init_with_tie_break(
+    idle_stack: &mut [u8],
+    user_idle_task: Option<fn(u32) -> !>,
+    tie_break: TieBreak,
+) {
     unsafe {
-        KERNEL = MaybeUninit::new(Kernel::new());
+        KERNEL = MaybeUninit::new(Kernel::new_with_tie_break(tie_break));
+    }
+
+    let entry = user_idle_task.unwrap_or(idle_task) as *const () as u32;
+    let stack_ptr = init_stack(idle_stack, entry, None);
+
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        let _ = kernel.create_idle(stack_ptr);
+        kernel.set_isr_check(Some(in_isr));
+        kernel.set_stack_check(Some(stack_intact));
+        kernel.set_stack_region(IDLE_TASK_ID, idle_stack.as_ptr() as usize, idle_stack.len());
+        kernel.set_task_entry(IDLE_TASK_ID, entry as usize);
+    });
+}
+
+/// Reset the kernel, removing all tasks and kernel objects
+///
+/// # Returns
+///
+/// `Ok(())` on success, or `Err(ResetError::Running)` if the kernel is running
+///
+/// # Note
+///
+/// Intended for on-target test harnesses, where each test case calls `init`,
+/// creates its tasks and kernel objects, and resets the kernel before the next
+/// test case. Must be called after `init`. The idle task is removed too, so
+/// `init` must be called again before creating tasks. Hooks set on the port
+/// (e.g. `set_idle_hook`) are cleared as well. Since `start` never returns,
+/// only a kernel that was never started can be reset.
+pub fn reset() -> Result<(), ResetError> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.reset()?;
+
+        unsafe {
+            TICK_RELOAD = 0;
+            TICKLESS = false;
+            IDLE_HOOK = None;
+            DEEP_SLEEP = None;
+            TICKLESS_RESTORE = false;
+            PENDING_RESTART = None;
+            #[cfg(feature = "switch-timing")]
+            {
+                SWITCH_CYCLES = rucos::CycleStats::new();
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Set the scheduling policy
+///
+/// # Arguments
+///
+/// * `policy`: Scheduling policy, the default is `SchedPolicy::Priority`
+///
+/// # Panics
+///
+/// If called after `start`
+///
+/// # Note
+///
+/// Called after `init`. Under `SchedPolicy::Mlfq`, only tasks created with a
+/// time slice (see `create_with_time_slice`) are demoted for using it up.
+pub fn set_sched_policy(policy: SchedPolicy) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_sched_policy(policy);
+    });
+}
+
+/// Set the maximum priority number a task may be given
+///
+/// # Arguments
+///
+/// * `max_priority`: Highest allowed priority number, i.e. the lowest
+///   priority, or `None` to allow any priority (the default)
+///
+/// # Panics
+///
+/// The `max_priority` is not below `IDLE_TASK_PRIORITY`
+///
+/// # Note
+///
+/// Called after `init` and before creating tasks. Creating a task with a
+/// priority number above the maximum, or changing a task priority to one, then
+/// panics, and `try_create` returns `CreateError::PriorityOutOfRange`.
+pub fn set_max_priority(max_priority: Option<usize>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_max_priority(max_priority);
+    });
+}
+
+/// Handle to a task, returned when the task is created
+///
+/// # Note
+///
+/// The handle is a copy of the task ID, so it stays valid to pass around after
+/// the task is deleted, in which case the APIs behave as with an unknown ID
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TaskHandle(usize);
+
+impl TaskHandle {
+    /// Get a handle to a task from its ID
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task ID
+    ///
+    /// # Returns
+    ///
+    /// Handle to the task
+    pub const fn from_id(id: usize) -> Self {
+        Self(id)
+    }
+
+    /// Get a handle to the current task
+    ///
+    /// # Returns
+    ///
+    /// Handle to the current task
+    pub fn current() -> Self {
+        Self(get_current_task())
+    }
+
+    /// Get the ID of the task
+    ///
+    /// # Returns
+    ///
+    /// Task ID
+    pub const fn id(self) -> usize {
+        self.0
+    }
+
+    /// Get the state of the task, see `get_task_state`
+    ///
+    /// # Returns
+    ///
+    /// State of the task, or `None` if the task does not exist
+    pub fn state(self) -> Option<TaskState> {
+        get_task_state(self.0)
+    }
+
+    /// Delete the task, see `delete`
+    pub fn delete(self) {
+        delete(Some(self.0));
+    }
+
+    /// Suspend the task, see `suspend`
+    pub fn suspend(self) {
+        suspend(Some(self.0));
+    }
+
+    /// Resume the task, see `resume`
+    pub fn resume(self) {
+        resume(self.0);
+    }
+
+    /// Block the current task until the task is deleted, see `join`
+    pub fn join(self) {
+        join(self.0);
+    }
+
+    /// Change the priority of the task, see `set_priority`
+    ///
+    /// # Arguments
+    ///
+    /// * `priority`: New task priority
+    pub fn set_priority(self, priority: usize) {
+        set_priority(self.0, priority);
+    }
+}
+
+/// Create a task
+///
+/// # Arguments
+///
+/// * `id`: Task ID
+/// * `priority`: Task priority, with a lower number meaning higher priority
+/// * `stack`: Task stack memory
+/// * `entry`: Task function
+/// * `arg`: An optional argument to pass to `entry`
+///
+/// # Returns
+///
+/// Handle to the task
+///
+/// # Panics
+///
+/// The `stack` is too small for the initial task context, see `MIN_STACK_SIZE`
+///
+/// # Note
+///
+/// A context switch may occur after calling this API, if the kernel is running.
+/// A running task may call this API to spawn a child task. If the child has a
+/// higher priority than the caller, the child runs before this API returns to
+/// the caller, so the caller is preempted. The child's initial context does not
+/// depend on the caller, and `stack` must stay valid until the child is
+/// deleted. The lowest word of `stack` is used as a canary, see `check_stack`,
+/// and the rest is filled with a pattern, see `stack_high_water`. The
+/// `IDLE_TASK_ID` is reserved for the idle task and cannot be used.
+pub fn create(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: fn(u32) -> !,
+    arg: Option<u32>,
+) -> TaskHandle {
+    create_with_time_slice(id, priority, stack, entry, arg, None)
+}
+
+/// Create a task with a time slice
+///
+/// # Arguments
+///
+/// * `id`: Task ID
+/// * `priority`: Task priority, with a lower number meaning higher priority
+/// * `stack`: Task stack memory
+/// * `entry`: Task function
+/// * `arg`: An optional argument to pass to `entry`
+/// * `time_slice`: Number of ticks the task runs before being switched out for
+///   a ready task of the same priority, or `None` to run until blocked
+///
+/// # Returns
+///
+/// Handle to the task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API, if the kernel is running
+pub fn create_with_time_slice(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: fn(u32) -> !,
+    arg: Option<u32>,
+    time_slice: Option<Tick>,
+) -> TaskHandle {
+    create_task(
+        id,
+        priority,
+        stack,
+        entry as *const () as u32,
+        arg,
+        time_slice,
+        None,
+    )
+}
+
+/// Create a task with a name
+///
+/// # Arguments
+///
+/// * `id`: Task ID
+/// * `priority`: Task priority, with a lower number meaning higher priority
+/// * `stack`: Task stack memory
+/// * `entry`: Task function
+/// * `arg`: An optional argument to pass to `entry`
+/// * `name`: Task name for debugging, see `get_task_name`
+///
+/// # Returns
+///
+/// Handle to the task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API, if the kernel is running
+pub fn create_named(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: fn(u32) -> !,
+    arg: Option<u32>,
+    name: &'static str,
+) -> TaskHandle {
+    create_task(
+        id,
+        priority,
+        stack,
+        entry as *const () as u32,
+        arg,
+        None,
+        Some(name),
+    )
+}
+
+/// Create a task with a reference argument of any type
+///
+/// # Arguments
+///
+/// * `id`: Task ID
+/// * `priority`: Task priority, with a lower number meaning higher priority
+/// * `stack`: Task stack memory
+/// * `entry`: Task function, taking the argument by reference
+/// * `arg`: Argument to pass to `entry`
+///
+/// # Returns
+///
+/// Handle to the task
+///
+/// # Note
+///
+/// The argument must be `'static` since the task may run for as long as the
+/// program does, and `Sync` since other tasks may share it. It is passed as a
+/// pointer in R0, so it is not copied. A context switch may occur after calling
+/// this API, if the kernel is running.
+pub fn create_with_arg<T: Sync>(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: fn(&'static T) -> !,
+    arg: &'static T,
+) -> TaskHandle {
+    create_task(
+        id,
+        priority,
+        stack,
+        entry as *const () as u32,
+        Some(arg as *const T as u32),
+        None,
+        None,
+    )
+}
+
+/// Create a task that runs to completion
+///
+/// # Arguments
+///
+/// * `id`: Task ID
+/// * `priority`: Task priority, with a lower number meaning higher priority
+/// * `stack`: Task stack memory
+/// * `entry`: Task function, the task is deleted when it returns
+/// * `arg`: An optional argument to pass to `entry`
+///
+/// # Returns
+///
+/// Handle to the task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API, if the kernel is running.
+/// Once the task is deleted, its ID and stack may be reused to create another
+/// task.
+pub fn create_oneshot(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: fn(u32),
+    arg: Option<u32>,
+) -> TaskHandle {
+    create_task(
+        id,
+        priority,
+        stack,
+        entry as *const () as u32,
+        arg,
+        None,
+        None,
+    )
+}
+
+/// Create a task with the address of its function
+fn create_task(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: u32,
+    arg: Option<u32>,
+    time_slice: Option<Tick>,
+    name: Option<&'static str>,
+) -> TaskHandle {
+    // When called from a task, the pended switch only happens after the critical
+    // section, so the child is fully set up before it runs
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+
+        // Do not overwrite the stack of a task that already has this ID
+        assert!(
+            id != IDLE_TASK_ID,
+            "The task ID is reserved for the idle task"
+        );
+        assert!(
+            kernel.get_task_state(id).is_none(),
+            "The task ID is not unique"
+        );
+
+        let stack_ptr = init_stack(stack, entry, arg);
+        if kernel.create_with_time_slice(id, priority, stack_ptr, time_slice) {
+            SCB::set_pendsv();
+        }
+
+        kernel.set_stack_region(id, stack.as_ptr() as usize, stack.len());
+        kernel.set_task_entry(id, entry as usize);
+        if let Some(name) = name {
+            kernel.set_task_name(id, name);
+        }
+    });
+
+    TaskHandle(id)
+}
+
+/// Builder for creating a task with optional settings
+///
+/// # Note
+///
+/// Settings that are not given default to the behavior of `create`, with an ID
+/// and priority of `0`. A stack must be given before calling `spawn`.
+pub struct TaskBuilder<'a> {
+    entry: fn(u32) -> !,
+    id: usize,
+    priority: usize,
+    stack: Option<&'a mut [u8]>,
+    arg: Option<u32>,
+    name: Option<&'static str>,
+    time_slice: Option<Tick>,
+    deadline: Option<Tick>,
+    watchdog: Option<Tick>,
+}
+
+impl<'a> TaskBuilder<'a> {
+    /// Start building a task
+    ///
+    /// # Arguments
+    ///
+    /// * `entry`: Task function
+    pub fn new(entry: fn(u32) -> !) -> Self {
+        Self {
+            entry,
+            id: 0,
+            priority: 0,
+            stack: None,
+            arg: None,
+            name: None,
+            time_slice: None,
+            deadline: None,
+            watchdog: None,
+        }
+    }
+
+    /// Set the task ID
+    pub fn id(mut self, id: usize) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Set the task priority, with a lower number meaning higher priority
+    pub fn priority(mut self, priority: usize) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the task stack memory
+    pub fn stack(mut self, stack: &'a mut [u8]) -> Self {
+        self.stack = Some(stack);
+        self
+    }
+
+    /// Set the argument to pass to the task function
+    pub fn arg(mut self, arg: u32) -> Self {
+        self.arg = Some(arg);
+        self
+    }
+
+    /// Set the task name, see `get_task_name`
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
     }
 
-    match user_idle_task {
-        Some(entry) => create(usize::MAX, usize::MAX, idle_stack, entry, None),
-        None => create(usize::MAX, usize::MAX, idle_stack, idle_task, None),
+    /// Set the task time slice, see `create_with_time_slice`
+    pub fn time_slice(mut self, time_slice: Tick) -> Self {
+        self.time_slice = Some(time_slice);
+        self
     }
+
+    /// Set the task deadline, see `set_deadline`
+    pub fn deadline(mut self, deadline: Tick) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the task watchdog timeout, see `set_watchdog`
+    pub fn watchdog(mut self, timeout: Tick) -> Self {
+        self.watchdog = Some(timeout);
+        self
+    }
+
+    /// Create the task
+    ///
+    /// # Returns
+    ///
+    /// Handle to the task
+    ///
+    /// # Panics
+    ///
+    /// * No stack was given
+    /// * See `create`
+    ///
+    /// # Note
+    ///
+    /// A context switch may occur after calling this API, if the kernel is
+    /// running
+    pub fn spawn(self) -> TaskHandle {
+        let stack = self.stack.expect("Task stack not set");
+        let handle = create_task(
+            self.id,
+            self.priority,
+            stack,
+            self.entry as *const () as u32,
+            self.arg,
+            self.time_slice,
+            self.name,
+        );
+
+        if self.deadline.is_some() {
+            set_deadline(handle.id(), self.deadline);
+        }
+
+        if self.watchdog.is_some() {
+            set_watchdog(handle.id(), self.watchdog);
+        }
+
+        handle
+    }
+}
+
+/// Create a task, returning an error instead of panicking
+///
+/// # Arguments
+///
+/// * `id`: Task ID
+/// * `priority`: Task priority, with a lower number meaning higher priority
+/// * `stack`: Task stack memory
+/// * `entry`: Task function
+/// * `arg`: An optional argument to pass to `entry`
+///
+/// # Returns
+///
+/// Handle to the task if it was created, or the reason it could not be created
+///
+/// # Note
+///
+/// A context switch may occur after calling this API, if the kernel is running
+pub fn try_create(
+    id: usize,
+    priority: usize,
+    stack: &mut [u8],
+    entry: fn(u32) -> !,
+    arg: Option<u32>,
+) -> Result<TaskHandle, CreateError> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+
+        if id == IDLE_TASK_ID {
+            return Err(CreateError::ReservedId);
+        }
+
+        // Do not overwrite the stack of a task that already has this ID
+        if kernel.get_task_state(id).is_some() {
+            return Err(CreateError::DuplicateId);
+        }
+
+        let (bottom, size) = (stack.as_ptr() as usize, stack.len());
+        if !rucos::stack_fits(bottom, size, INITIAL_CONTEXT_SIZE, STACK_ALIGN) {
+            return Err(CreateError::StackTooSmall);
+        }
+
+        let stack_ptr = init_stack(stack, entry as *const () as u32, arg);
+        if kernel.try_create(id, priority, stack_ptr)? {
+            SCB::set_pendsv();
+        }
+
+        kernel.set_stack_region(id, bottom, size);
+        kernel.set_task_entry(id, entry as *const () as usize);
+        Ok(TaskHandle(id))
+    })
+}
+
+/// Delete a task
+///
+/// # Arguments
+///
+/// * `id`: Task to delete or `None` to delete the current task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn delete(id: Option<usize>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.delete(id) {
+            SCB::set_pendsv();
+        }
+    });
+
+    run_delete_hooks();
+}
+
+/// Delete a task, returning an error instead of panicking
+///
+/// # Arguments
+///
+/// * `id`: Task to delete or `None` to delete the current task
+///
+/// # Returns
+///
+/// `Ok(())` on success, `Err(TaskError::NotFound)` if the `id` does not
+/// correspond to a task, or `Err(TaskError::IdleTask)` if the task is the idle
+/// task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn try_delete(id: Option<usize>) -> Result<(), TaskError> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.try_delete(id)? {
+            SCB::set_pendsv();
+        }
+
+        Ok(())
+    })?;
+
+    run_delete_hooks();
+    Ok(())
+}
+
+/// Restart a task from its entry function with a fresh stack
+///
+/// # Arguments
+///
+/// * `id`: Task to restart
+/// * `arg`: An optional argument to pass to the task function
+///
+/// # Panics
+///
+/// * The `id` provided does not correspond to a task
+/// * The task owns a mutex
+/// * If called before the kernel is running
+///
+/// # Note
+///
+/// The task becomes ready with its base priority. A task can restart itself,
+/// in which case its stack is re-initialized once the context switch away from
+/// it has happened. A context switch may occur after calling this API.
+pub fn restart(id: usize, arg: Option<u32>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        let (bottom, size) = kernel.get_stack_region(id).expect("Task does not exist");
+        let entry = kernel.get_task_entry(id).expect("Task does not exist") as u32;
+        let stack = unsafe { core::slice::from_raw_parts_mut(bottom as *mut u8, size) };
+
+        let is_current = kernel.get_current_task() == id;
+
+        // The kernel checks the task can be restarted before its stack is
+        // overwritten, the context switch only happens after the critical
+        // section
+        if kernel.restart(id, initial_stack_ptr(stack)) {
+            SCB::set_pendsv();
+        }
+
+        // The current task is still running on its stack, so it cannot be
+        // overwritten until the context switch
+        if is_current {
+            unsafe { PENDING_RESTART = Some((id, arg)) };
+        } else {
+            let _ = init_stack(stack, entry, arg);
+        }
+    });
+}
+
+/// Check a task stack for overflow
+///
+/// # Arguments
+///
+/// * `id`: Task to check
+///
+/// # Returns
+///
+/// `true` if the canary at the bottom of the task stack is intact, `false` if
+/// it has been overwritten
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+///
+/// # Note
+///
+/// Only detects an overflow that reaches the bottom word of the stack, a task
+/// can still corrupt memory below its stack without being detected
+pub fn check_stack(id: usize) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        let (bottom, size) = kernel.get_stack_region(id).expect("Task does not exist");
+        stack_intact(bottom, size)
+    })
+}
+
+/// Check the canary of a task stack region recorded by `create`
+fn stack_intact(bottom: usize, size: usize) -> bool {
+    // The stack was provided by `create` and is never deallocated
+    let stack = unsafe { core::slice::from_raw_parts(bottom as *const u8, size) };
+    rucos::stack_canary_check(stack)
+}
+
+/// Check if a task has used the floating point unit
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// `true` if the task has used the floating point unit, `false` if not, or
+/// `None` if the `id` does not correspond to a task
+///
+/// # Note
+///
+/// Floating point registers are only saved for tasks with an active floating
+/// point context, so integer-only tasks never need stack space for them. A task
+/// that uses the floating point unit needs an extra 136 bytes of stack for each
+/// saved context: S0 - S15 and FPSCR stacked by hardware, plus S16 - S31 stacked
+/// by the context switch. Detected on the first floating point instruction of
+/// the task, see `UsageFault`, and cleared when the task is restarted.
+pub fn task_uses_fpu(id: usize) -> Option<bool> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.task_uses_fpu(id)
+    })
+}
+
+/// Watch the stack limit of a task with a data watchpoint
+///
+/// # Arguments
+///
+/// * `id`: Task to watch
+///
+/// # Panics
+///
+/// * The `id` provided does not correspond to a task
+/// * The core has no DWT comparators
+///
+/// # Note
+///
+/// Requires the `stack-watchpoint` feature. Programs the last DWT comparator to
+/// match writes to the canary word at the bottom of the task stack, so a debug
+/// event fires within a few instructions of the write that overflows the stack
+/// rather than on the next canary check. Only the current task is watched, the comparator is
+/// reprogrammed on every context switch and disabled while a task that is not
+/// watched runs. Enables the DebugMonitor exception, which is raised when no
+/// debugger has enabled halting debug, otherwise the debugger halts the core.
+///
+/// The comparator must not be used by anything else: a debugger setting data
+/// watchpoints may pick the same one, and any other use is overwritten on the
+/// next context switch.
+#[cfg(feature = "stack-watchpoint")]
+pub fn arm_stack_watchpoint(id: usize) {
+    free(|_| {
+        assert!(
+            cortex_m::peripheral::DWT::num_comp() > 0,
+            "No DWT comparators"
+        );
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_stack_watch(id, true);
+
+        // Trace must be enabled for the DWT to work, and debug events go to the
+        // DebugMonitor exception
+        unsafe {
+            let mut peripherals = cortex_m::Peripherals::steal();
+            peripherals.DCB.enable_trace();
+            peripherals.DCB.demcr.modify(|w| w | DEMCR_MON_EN);
+        }
+
+        if kernel.is_current_task(id) {
+            stack_watchpoint_update(kernel);
+        }
+    });
+}
+
+/// Program the stack watchpoint comparator for the current task, see
+/// `arm_stack_watchpoint`
+#[cfg(feature = "stack-watchpoint")]
+fn stack_watchpoint_update(kernel: &Kernel<u32, Tick, MAX_NUM_TASKS>) {
+    let id = kernel.get_current_task();
+    let region = match kernel.task_stack_watch(id) {
+        Some(true) => kernel.get_stack_region(id),
+        _ => None,
+    };
+
+    let dwt = unsafe { &*cortex_m::peripheral::DWT::PTR };
+    let comparator = &dwt.c[usize::from(cortex_m::peripheral::DWT::num_comp()) - 1];
+    unsafe {
+        comparator.function.write(0);
+        if let Some((bottom, _)) = region {
+            comparator.comp.write((bottom & !0x3) as u32);
+            comparator.mask.write(2);
+            comparator.function.write(DWT_FUNCTION_WRITE);
+        }
+    }
+}
+
+/// Disable the stack watchpoint comparator, see `arm_stack_watchpoint`
+#[cfg(feature = "stack-watchpoint")]
+fn stack_watchpoint_disable() {
+    if cortex_m::peripheral::DWT::num_comp() > 0 {
+        let dwt = unsafe { &*cortex_m::peripheral::DWT::PTR };
+        let comparator = &dwt.c[usize::from(cortex_m::peripheral::DWT::num_comp()) - 1];
+        unsafe { comparator.function.write(0) };
+    }
+}
+
+/// Measure the peak stack usage of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to measure, `IDLE_TASK_ID` for the idle task
+///
+/// # Returns
+///
+/// Maximum number of bytes of the task stack ever used, or `None` if the `id`
+/// does not correspond to a task
+///
+/// # Note
+///
+/// Intended for sizing task stacks during development, the stack is scanned on
+/// every call
+pub fn stack_high_water(id: usize) -> Option<usize> {
+    free(|_| {
+        let kernel = unsafe { &*KERNEL.as_ptr() };
+        task_stack_high_water(kernel, id)
+    })
+}
+
+/// Measure the peak stack usage of a task, see `stack_high_water`
+fn task_stack_high_water(kernel: &Kernel<u32, Tick, MAX_NUM_TASKS>, id: usize) -> Option<usize> {
+    let (bottom, size) = kernel.get_stack_region(id)?;
+
+    // The stack was provided by `create` and is never deallocated
+    let stack = unsafe { core::slice::from_raw_parts(bottom as *const u8, size) };
+    Some(rucos::stack_high_water(stack))
+}
+
+/// Format the state of every task for a post-mortem
+///
+/// # Arguments
+///
+/// * `w`: Writer to format the task state into, e.g. over RTT or UART
+///
+/// # Returns
+///
+/// Result of writing to `w`
+///
+/// # Note
+///
+/// Intended to be called from a panic handler. Each task's ID, priority, state,
+/// pend reason and peak stack usage is written on its own line. The kernel is
+/// read without a critical section, so this is a best-effort snapshot that is
+/// safe to take even if the panic occurred inside the kernel.
+pub fn dump_state(w: &mut impl core::fmt::Write) -> core::fmt::Result {
+    let kernel = unsafe { &*KERNEL.as_ptr() };
+    kernel.dump_state(w, |id| task_stack_high_water(kernel, id))
+}
+
+/// Set the priority of an interrupt after validating it
+///
+/// # Arguments
+///
+/// * `nvic`: Nested vectored interrupt controller (from the `cortex-m` crate)
+/// * `irq`: Interrupt number
+/// * `priority`: Priority field value, lower values are more urgent
+/// * `calls_kernel`: The interrupt handler calls kernel APIs
+///
+/// # Panics
+///
+/// * The `priority` is truncated by the priority bits the NVIC implements
+/// * The interrupt handler calls kernel APIs and the `priority` is more urgent
+///   (numerically lower) than `KERNEL_INTERRUPT_PRIORITY`
+///
+/// # Note
+///
+/// The context switch masks interrupts with a priority value of
+/// `KERNEL_INTERRUPT_PRIORITY` or higher, so an interrupt calling the kernel at
+/// a more urgent priority could preempt it and corrupt the kernel state. An
+/// interrupt not calling the kernel may use any priority and is never delayed by
+/// the kernel. The implemented priority bits are found by writing the priority
+/// of `irq` and reading it back. Does not unmask the interrupt.
+pub fn configure_irq<I: InterruptNumber>(
+    nvic: &mut NVIC,
+    irq: I,
+    priority: u8,
+    calls_kernel: bool,
+) {
+    unsafe { nvic.set_priority(irq, 0xFF) };
+    let prio_bits = rucos::irq_priority_bits(NVIC::get_priority(irq));
+    assert!(
+        rucos::irq_priority_implemented(priority, prio_bits),
+        "Interrupt priority is truncated by the implemented priority bits"
+    );
+    assert!(
+        rucos::irq_priority_allowed(priority, KERNEL_INTERRUPT_PRIORITY, calls_kernel, prio_bits),
+        "Interrupt calling the kernel is more urgent than KERNEL_INTERRUPT_PRIORITY"
+    );
+    unsafe { nvic.set_priority(irq, priority) };
+}
+
+/// Start the kernel
+///
+/// # Arguments
+///
+/// * `scb`: System control block (from the `cortex-m` crate)
+/// * `systick`: System tick  (from the `cortex-m` crate)
+/// * `clock_freq_hz`: Core clock frequency in hertz
+///
+/// # Panics
+///
+/// No tasks have been created other than the idle task, see
+/// `start_with_clock_source`
+///
+/// # Note
+///
+/// Does not return: Program execution continues from tasks or interrupt
+/// handlers after calling this API
+pub fn start(scb: &mut SCB, systick: &mut SYST, clock_freq_hz: u32) -> ! {
+    start_with_clock_source(scb, systick, SystClkSource::Core, clock_freq_hz);
+}
+
+/// Start the kernel with a SysTick clock source
+///
+/// # Arguments
+///
+/// * `scb`: System control block (from the `cortex-m` crate)
+/// * `systick`: System tick  (from the `cortex-m` crate)
+/// * `clock_source`: SysTick clock source, the core clock or the external
+///   reference clock
+/// * `clock_freq_hz`: Frequency of `clock_source` in hertz
+///
+/// # Panics
+///
+/// * No tasks have been created other than the idle task
+/// * The SysTick reload value for `clock_freq_hz` does not fit, see
+///   `systick_reload`
+/// * The `KERNEL_INTERRUPT_PRIORITY` is truncated by the priority bits the NVIC
+///   implements
+///
+/// # Note
+///
+/// The reference clock frequency is device specific, often the core clock
+/// divided by 8. It may keep running while the core clock is gated in sleep.
+/// The implemented priority bits are found by writing the PendSV priority and
+/// reading it back. All panics happen before SysTick is configured. Does not
+/// return: Program execution continues from tasks or interrupt handlers after
+/// calling this API.
+pub fn start_with_clock_source(
+    scb: &mut SCB,
+    systick: &mut SYST,
+    clock_source: SystClkSource,
+    clock_freq_hz: u32,
+) -> ! {
+    // Validate everything before starting the tick, PendSV is set to the
+    // lowest priority below anyway
+    let tick_reload = systick_reload(clock_freq_hz);
+    unsafe { scb.set_priority(scb::SystemHandler::PendSV, 0xFF) };
+    let prio_bits = rucos::irq_priority_bits(SCB::get_priority(scb::SystemHandler::PendSV));
+    assert!(
+        rucos::irq_priority_implemented(KERNEL_INTERRUPT_PRIORITY, prio_bits),
+        "KERNEL_INTERRUPT_PRIORITY is truncated by the implemented priority bits"
+    );
+    let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+    let first_task_stack_ptr = kernel.start();
+
+    // Tasks get the floating point unit on first use, see `UsageFault`
+    scb.enable(scb::Exception::UsageFault);
+    fpu_enable(kernel.task_uses_fpu(kernel.get_current_task()) == Some(true));
+
+    #[cfg(feature = "stack-watchpoint")]
+    stack_watchpoint_update(kernel);
+
+    unsafe { TICK_RELOAD = tick_reload };
+
+    systick.set_reload(tick_reload);
+    systick.clear_current();
+    systick.set_clock_source(clock_source);
+    systick.enable_interrupt();
+    systick.enable_counter();
+
+    unsafe {
+        // Context switch should only happen once all interrupts have been serviced,
+        // and the tick shares its priority so neither preempts the other
+        let lowest = rucos::irq_priority_lowest(prio_bits);
+        scb.set_priority(scb::SystemHandler::PendSV, lowest);
+        scb.set_priority(scb::SystemHandler::SysTick, lowest);
+
+        // Start the cycle counter used to time the context switch
+        #[cfg(feature = "switch-timing")]
+        {
+            let mut peripherals = cortex_m::Peripherals::steal();
+            peripherals.DCB.enable_trace();
+            cortex_m::peripheral::DWT::unlock();
+            peripherals.DWT.enable_cycle_counter();
+        }
+
+        asm!(
+            "cpsid  i",                    // Disable interrupts
+            "mov    r0, {tmp}",            // Get first task stack pointer
+            "msr    psp, r0",              // Write PSP
+            "mrs    r1, control",          // Read CONTROL
+            "orr    r1, r1, #2",           // Set SP = PSP
+            "bic    r1, r1, #4",           // Clear FPCA (reset FPU)
+            "msr    control, r1",          // Write CONTROL
+            "isb",                         // Sync instructions
+            "ldmia  sp!, {{r4-r11, r14}}", // Restore R4 - R11, LR
+            "ldmia  sp!, {{r0-r3}}",       // Restore R0 - R3
+            "ldmia  sp!, {{r12, r14}}",    // Load R12 and LR
+            "ldmia  sp!, {{r1, r2}}",      // Load PC and discard xPSR
+            "cpsie  i",                    // Enable interrupts
+            "bx     r1",                   // Branch to first task
+            tmp = in(reg) first_task_stack_ptr,
+            options(noreturn),
+        )
+    };
+}
+
+/// Compute the SysTick reload value for one kernel tick
+///
+/// # Arguments
+///
+/// * `clock_freq_hz`: Frequency of the SysTick clock source in hertz
+///
+/// # Returns
+///
+/// SysTick reload value, such that SysTick fires at `TICK_RATE_HZ`
+///
+/// # Panics
+///
+/// The reload value does not fit in the 24-bit SysTick reload register, or the
+/// clock is too slow to tick at `TICK_RATE_HZ`
+pub fn systick_reload(clock_freq_hz: u32) -> u32 {
+    let cycles_per_tick = clock_freq_hz / _TICK_RATE_HZ;
+    assert!(
+        cycles_per_tick > 1,
+        "SysTick clock too slow for TICK_RATE_HZ"
+    );
+    assert!(
+        cycles_per_tick - 1 <= SYSTICK_MAX_RELOAD,
+        "SysTick reload exceeds 24 bits"
+    );
+
+    cycles_per_tick - 1
+}
+
+/// Get the ID of the current task
+///
+/// # Returns
+///
+/// ID of the current task
+pub fn get_current_task() -> usize {
+    let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+
+    // Does not modify the kernel
+    kernel.get_current_task()
+}
+
+/// Get the ID of the idle task
+///
+/// # Returns
+///
+/// `IDLE_TASK_ID`, which is reserved for the idle task
+///
+/// # Note
+///
+/// The idle task can be queried like any other task, e.g. the idle percentage
+/// from `cpu_usage` or the peak stack usage from `stack_high_water`
+pub const fn idle_task_id() -> usize {
+    IDLE_TASK_ID
+}
+
+/// Get the current value of the kernel tick
+///
+/// # Returns
+///
+/// Current value of the kernel tick
+///
+/// # Note
+///
+/// Ticks correspond to system time based on `TICK_RATE_HZ`. May be called from
+/// any context without a critical section, e.g. a `defmt` timestamp. The tick
+/// is read from a copy kept in an `AtomicTick`, which the kernel updates in a
+/// critical section on every tick, so a read never sees a partly written tick.
+pub fn get_current_tick() -> Tick {
+    TICK_COUNTER.load() as Tick
+}
+
+/// Get the state of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// State of the task, or `None` if the `id` does not correspond to a task
+pub fn get_task_state(id: usize) -> Option<TaskState> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.get_task_state(id)
+    })
+}
+
+/// Get the reason a task is pending
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// Pend reason of the task, or `None` if the `id` does not correspond to a task
+pub fn get_pend_reason(id: usize) -> Option<PendReason> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.get_pend_reason(id)
+    })
+}
+
+/// Get the number of ticks left before a sleeping task wakes up
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// Ticks until the task wakes up, `0` if it is about to, or `None` if the task
+/// is not sleeping or the `id` does not correspond to a task
+pub fn sleep_remaining(id: usize) -> Option<u64> {
+    free(|_| {
+        let kernel = unsafe { &*KERNEL.as_ptr() };
+        kernel.sleep_remaining(id)
+    })
+}
+
+/// Get the number of runnable tasks
+///
+/// # Returns
+///
+/// Number of tasks that are ready or running, not including the idle task
+///
+/// # Note
+///
+/// A monitor task can sample this periodically to gauge contention
+pub fn ready_count() -> usize {
+    free(|_| {
+        let kernel = unsafe { &*KERNEL.as_ptr() };
+        kernel.ready_count()
+    })
+}
+
+/// Get the CPU usage of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to query, `IDLE_TASK_ID` for the idle task
+///
+/// # Returns
+///
+/// Number of ticks that elapsed while the task was running and the total number
+/// of ticks since the kernel started, or `None` if the `id` does not correspond
+/// to a task
+///
+/// # Note
+///
+/// The resolution is one kernel tick, see `TICK_RATE_HZ`. Tasks that run in
+/// bursts much shorter than a tick are under-reported.
+pub fn cpu_usage(id: usize) -> Option<(Tick, Tick)> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.cpu_usage(id)
+    })
+}
+
+/// Report a snapshot of every task
+///
+/// # Arguments
+///
+/// * `f`: Function called with the snapshot of each task, including the idle
+///   task
+///
+/// # Note
+///
+/// The whole walk runs in a critical section so tasks cannot be created or
+/// deleted part way through, keep `f` short (e.g. format into a buffer)
+pub fn task_report(f: impl FnMut(&TaskInfo)) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.for_each_task(f);
+    });
+}
+
+/// Get the name of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// Name of the task, or `None` if the `id` does not correspond to a task or
+/// the task was created without a name
+pub fn get_task_name(id: usize) -> Option<&'static str> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.get_task_name(id)
+    })
+}
+
+/// Serialize the kernel state into a versioned binary snapshot
+///
+/// # Arguments
+///
+/// * `buf`: Buffer to write the snapshot into
+///
+/// # Returns
+///
+/// Number of bytes written, or `0` if `buf` is too small
+///
+/// # Note
+///
+/// See `rucos::Kernel::serialize_state` for the format
+pub fn serialize_state(buf: &mut [u8]) -> usize {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.serialize_state(buf)
+    })
+}
+
+/// Set a task-local storage slot of the current task
+///
+/// # Arguments
+///
+/// * `index`: Slot to set, lower than `TLS_SLOTS`
+/// * `value`: Pointer to store in the slot
+///
+/// # Panics
+///
+/// * The `index` is not lower than `TLS_SLOTS`
+/// * If called before `start`
+///
+/// # Note
+///
+/// Only called from a task, an interrupt handler would set the slot of the
+/// task it interrupted. The kernel does not access the pointer.
+pub fn tls_set(index: usize, value: *mut ()) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.tls_set(index, value);
+    })
+}
+
+/// Get a task-local storage slot of the current task
+///
+/// # Arguments
+///
+/// * `index`: Slot to get, lower than `TLS_SLOTS`
+///
+/// # Returns
+///
+/// Pointer stored in the slot, null if it was never set
+///
+/// # Panics
+///
+/// * The `index` is not lower than `TLS_SLOTS`
+/// * If called before `start`
+///
+/// # Note
+///
+/// Only called from a task, see `tls_set`
+pub fn tls_get(index: usize) -> *mut () {
+    free(|_| {
+        let kernel = unsafe { &*KERNEL.as_ptr() };
+        kernel.tls_get(index)
+    })
+}
+
+/// Check if the caller is executing in interrupt context
+///
+/// # Returns
+///
+/// `true` if called from an exception or interrupt handler, `false` if called
+/// from a task
+///
+/// # Note
+///
+/// Reads the exception number from `IPSR`, which is zero in thread mode
+pub fn in_isr() -> bool {
+    let ipsr: u32;
+
+    unsafe {
+        asm!(
+            "mrs {}, ipsr",
+            out(reg) ipsr,
+            options(nomem, nostack, preserves_flags),
+        )
+    };
+
+    (ipsr & 0x1FF) != 0
+}
+
+/// Request a context switch on exit from an interrupt handler, if needed
+///
+/// # Arguments
+///
+/// * `switch`: `true` if a context switch is needed, usually the OR of the
+///   values returned by the `_from_isr` APIs called by the handler
+///
+/// # Note
+///
+/// An interrupt handler calling several kernel APIs can use their `_from_isr`
+/// variants, OR the returned values together, and call this once at the end.
+/// Requesting PendSV several times before it runs results in a single context
+/// switch anyway, so this does not change behavior compared to the APIs that
+/// request it themselves, but it makes the intent of the handler clear.
+pub fn isr_yield_if_needed(switch: bool) {
+    if switch {
+        SCB::set_pendsv();
+    }
+}
+
+/// Run a closure with the kernel locked
+///
+/// # Arguments
+///
+/// * `f`: Closure to run, given the critical section token for use with
+///   `cortex_m::interrupt::Mutex`
+///
+/// # Returns
+///
+/// Value returned by `f`
+///
+/// # Note
+///
+/// Runs `f` with interrupts disabled, so a sequence of kernel API calls inside
+/// it (e.g. checking the state of a task and then suspending it) is atomic with
+/// respect to the tick and interrupt handlers. Kernel APIs nest their own
+/// critical sections inside it, and no reference to the kernel is held while
+/// `f` runs, so each call borrows the kernel on its own. A context switch
+/// requested inside `f` happens once it returns, so `f` must not call APIs that
+/// block the current task (e.g. `sleep`), and it should be short since it delays
+/// every interrupt.
+pub fn critical<R>(f: impl FnOnce(&CriticalSection) -> R) -> R {
+    free(f)
+}
+
+/// Convert milliseconds to kernel ticks
+///
+/// # Arguments
+///
+/// * `ms`: Duration in milliseconds
+///
+/// # Returns
+///
+/// Number of ticks, rounded up so that a non-zero duration is at least one tick
+pub fn ms_to_ticks(ms: u64) -> Tick {
+    to_tick(rucos::ms_to_ticks(ms, TICK_RATE_HZ as u64))
+}
+
+/// Convert microseconds to kernel ticks
+///
+/// # Arguments
+///
+/// * `us`: Duration in microseconds
+///
+/// # Returns
+///
+/// Number of ticks, rounded up so that a non-zero duration is at least one tick
+pub fn us_to_ticks(us: u64) -> Tick {
+    to_tick(rucos::us_to_ticks(us, TICK_RATE_HZ as u64))
+}
+
+/// Convert kernel ticks to milliseconds
+///
+/// # Arguments
+///
+/// * `ticks`: Number of ticks
+///
+/// # Returns
+///
+/// Duration in milliseconds, rounded down
+pub fn ticks_to_ms(ticks: Tick) -> u64 {
+    rucos::ticks_to_ms(ticks as u64, TICK_RATE_HZ as u64)
+}
+
+/// Convert kernel ticks to microseconds
+///
+/// # Arguments
+///
+/// * `ticks`: Number of ticks
+///
+/// # Returns
+///
+/// Duration in microseconds, rounded down
+pub fn ticks_to_us(ticks: Tick) -> u64 {
+    rucos::ticks_to_us(ticks as u64, TICK_RATE_HZ as u64)
+}
+
+/// Convert a number of ticks to the kernel tick type, saturating at its maximum
+fn to_tick(ticks: u64) -> Tick {
+    ticks.min(Tick::MAX as u64) as Tick
+}
+
+/// Sleep the current task
+///
+/// # Arguments
+///
+/// * `delay`: Number of ticks to sleep
+///
+/// # Note
+///
+/// Ticks correspond to system time based on `TICK_RATE_HZ`. A `delay` of `0`
+/// yields to other ready tasks of the same priority instead of sleeping, and a
+/// `delay` of `Tick::MAX` sleeps until woken up, see `sleep_forever`.
+pub fn sleep(delay: Tick) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.sleep(delay) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Sleep the current task until another task or an interrupt handler calls
+/// `wake`
+///
+/// # Note
+///
+/// The tick never wakes the task. Use this to wait for a signal, and leave
+/// `suspend` and `resume` for controlling the task from outside.
+pub fn sleep_forever() {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.sleep_forever() {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Sleep the current task until an absolute tick count
+///
+/// # Arguments
+///
+/// * `wakeup_tick`: Value of the global tick counter to wake up at
+///
+/// # Note
+///
+/// Yields instead of sleeping if `wakeup_tick` has already been reached. A
+/// periodic task can avoid drift by advancing `wakeup_tick` by its period each
+/// loop.
+pub fn sleep_until(wakeup_tick: Tick) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.sleep_until(wakeup_tick) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Set the deadline of a periodic task
+///
+/// # Arguments
+///
+/// * `id`: Task to update
+/// * `deadline`: Number of ticks after each wake up by which the task must
+///   sleep again, or `None` to stop checking
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+///
+/// # Note
+///
+/// Deadlines are checked when the task calls `sleep` or `sleep_until`, see
+/// `missed_deadlines` and `set_deadline_hook`
+pub fn set_deadline(id: usize, deadline: Option<Tick>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_deadline(id, deadline);
+    });
+}
+
+/// Get the number of deadlines a task has missed
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// Number of times the task slept again after its deadline
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+pub fn missed_deadlines(id: usize) -> u64 {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.missed_deadlines(id).expect("Task does not exist")
+    })
+}
+
+/// Get the jitter of a periodic task
+///
+/// # Arguments
+///
+/// * `id`: Task to query
+///
+/// # Returns
+///
+/// Smallest, largest and latest number of ticks between the tick the task
+/// asked to wake up at and the tick it started running again, or `None` if
+/// nothing was measured yet
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+///
+/// # Note
+///
+/// Requires the `stats` feature, see `rucos::Kernel::periodic_jitter`
+#[cfg(feature = "stats")]
+pub fn periodic_jitter(id: usize) -> Option<Jitter<Tick>> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        assert!(kernel.get_task_state(id).is_some(), "Task does not exist");
+        kernel.periodic_jitter(id)
+    })
+}
+
+/// Set the software watchdog of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to update
+/// * `timeout`: Number of ticks within which the task must call `task_kick`,
+///   or `None` to stop checking
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+///
+/// # Note
+///
+/// Watchdogs are checked on every tick, see `set_watchdog_hook`
+pub fn set_watchdog(id: usize, timeout: Option<Tick>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_watchdog(id, timeout);
+    });
+}
+
+/// Kick the software watchdog of a task, see `set_watchdog`
+///
+/// # Arguments
+///
+/// * `id`: Task checking in
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+pub fn task_kick(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.task_kick(id);
+    });
+}
+
+/// Yield the CPU to another ready task of the same priority
+///
+/// # Note
+///
+/// The current task stays ready. If no other task of the same priority is
+/// ready, this does nothing.
+pub fn yield_now() {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.yield_task() {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Retry an operation with exponential backoff
+///
+/// # Arguments
+///
+/// * `try_fn`: Operation to attempt, returning `true` on success
+/// * `base_ticks`: Number of ticks to sleep after the first failed attempt
+/// * `max_ticks`: Upper bound on the number of ticks to sleep between attempts
+/// * `max_attempts`: Upper bound on the number of times `try_fn` is called
+///
+/// # Returns
+///
+/// `true` if `try_fn` eventually succeeded, `false` if all attempts failed
+///
+/// # Note
+///
+/// The current task sleeps between attempts, doubling the delay each time up
+/// to `max_ticks`, so other tasks can run while the resource is contended
+pub fn retry_with_backoff(
+    try_fn: impl FnMut() -> bool,
+    base_ticks: Tick,
+    max_ticks: Tick,
+    max_attempts: usize,
+) -> bool {
+    rucos::retry_with_backoff(try_fn, sleep, base_ticks, max_ticks, max_attempts)
+}
+
+/// Suspend a task
+///
+/// # Arguments
+///
+/// * `id`: Task to suspend or `None` to suspend the current task
+///
+/// # Note
+///
+/// A task blocked on a kernel object stops waiting until it is resumed. A
+/// context switch may occur after calling this API.
+pub fn suspend(id: Option<usize>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.suspend(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Suspend a task, returning an error instead of panicking
+///
+/// # Arguments
+///
+/// * `id`: Task to suspend or `None` to suspend the current task
+///
+/// # Returns
+///
+/// `Ok(())` on success, `Err(TaskError::NotFound)` if the `id` does not
+/// correspond to a task, or `Err(TaskError::IdleTask)` if the task is the idle
+/// task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn try_suspend(id: Option<usize>) -> Result<(), TaskError> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.try_suspend(id)? {
+            SCB::set_pendsv();
+        }
+
+        Ok(())
+    })
+}
+
+/// Resume a task
+///
+/// # Arguments
+///
+/// * `id`: Task to resume
+///
+/// # Note
+///
+/// A task that was blocked on a kernel object goes back to waiting on it, see
+/// `rucos::Kernel::resume`. Does nothing if the task is not suspended.
+///
+/// A context switch may occur after calling this API. Resuming a task with a
+/// higher priority than the caller preempts the caller before this API
+/// returns, PendSV is taken as soon as the critical section ends rather than on
+/// the next tick.
+pub fn resume(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.resume(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Resume a task, returning an error instead of panicking
+///
+/// # Arguments
+///
+/// * `id`: Task to resume
+///
+/// # Returns
+///
+/// `Ok(())` on success, or `Err(TaskError::NotFound)` if the `id` does not
+/// correspond to a task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn try_resume(id: usize) -> Result<(), TaskError> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.try_resume(id)? {
+            SCB::set_pendsv();
+        }
+
+        Ok(())
+    })
+}
+
+/// Wake up a sleeping task before its wake up tick
+///
+/// # Arguments
+///
+/// * `id`: Task to wake up
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, `false` if not, including when the
+/// task is not sleeping
+///
+/// # Note
+///
+/// A context switch may occur after calling this API. Use `resume` for a
+/// suspended task.
+pub fn wake(id: usize) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        let switch = kernel.wake(id);
+        if switch {
+            SCB::set_pendsv();
+        }
+
+        switch
+    })
+}
+
+/// Suspend every task except one task and the idle task
+///
+/// # Arguments
+///
+/// * `id`: Task to keep running, usually `get_current_task()`
+///
+/// # Note
+///
+/// Intended for a coordinated pause of the system, e.g. entering a firmware
+/// update mode. The suspended tasks are put back the way they were with
+/// `resume_all`. A context switch may occur after calling this API.
+pub fn suspend_all_except(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.suspend_all_except(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Resume every task suspended by `suspend_all_except`
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn resume_all() {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.resume_all() {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Change the time slice of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to update
+/// * `time_slice`: Number of ticks the task runs before being switched out for
+///   another ready task with the same priority, or `None` to run until blocked
+/// * `restart`: `true` for the new time slice to take effect now, `false` for
+///   it to take effect once the current time slice runs out
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+///
+/// # Note
+///
+/// Intended for tuning the time slice of a task while it runs, without deleting
+/// and creating it again, see `rucos::Kernel::set_time_slice`
+pub fn set_time_slice(id: usize, time_slice: Option<Tick>, restart: bool) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_time_slice(id, time_slice, restart);
+    });
+}
+
+/// Change the priority of a task
+///
+/// # Arguments
+///
+/// * `id`: Task to update
+/// * `priority`: New task priority, with a lower number meaning higher priority
+///
+/// # Panics
+///
+/// * The `id` provided does not correspond to a task
+/// * The task is the idle task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn set_priority(id: usize, priority: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.set_priority(id, priority) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Assign task priorities by rate monotonic scheduling
+///
+/// # Arguments
+///
+/// * `tasks`: Task IDs and their periods in ticks
+///
+/// # Panics
+///
+/// * An `id` provided does not correspond to a task
+/// * A task is the idle task
+///
+/// # Note
+///
+/// Shorter periods get higher priorities, starting from priority `0`, with ties
+/// broken by the lower ID. A context switch may occur after calling this API.
+pub fn assign_rate_monotonic(tasks: &[(usize, Tick)]) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.assign_rate_monotonic(tasks) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Block the current task until another task is deleted
+///
+/// # Arguments
+///
+/// * `id`: Task to wait for
+///
+/// # Note
+///
+/// Covers both an explicit `delete` and a task created with `create_oneshot`
+/// returning. Returns immediately if the `id` does not correspond to a task. A
+/// context switch may occur after calling this API.
+pub fn join(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.join(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Run an ordered startup sequence, one step at a time
+///
+/// # Arguments
+///
+/// * `id`: Task ID used by every step
+/// * `priority`: Priority of the step tasks
+/// * `stack`: Stack memory used by every step
+/// * `steps`: Init functions in the order they run, each passed its index
+///
+/// # Panics
+///
+/// * If called before the kernel is running, or from an interrupt handler
+/// * The `id` is already used by a task, or the `stack` is too small, see
+///   `create`
+///
+/// # Note
+///
+/// Each step runs in its own short-lived task created with `create_oneshot`,
+/// and is done once its function returns (or the step task is deleted). The
+/// calling task blocks in `join` until then, so step N+1 is only created after
+/// step N is done and the order holds whatever the priority of the steps and of
+/// the calling task. Returns once the last step is done, so the caller can then
+/// create or resume the normal set of tasks. A context switch may occur after
+/// calling this API.
+pub fn sequence(id: usize, priority: usize, stack: &mut [u8], steps: &[fn(u32)]) {
+    for (index, step) in steps.iter().enumerate() {
+        // The previous step was deleted, so its ID and stack are free again
+        create_oneshot(id, priority, stack, *step, Some(index as u32));
+        join(id);
+    }
+}
+
+/// Create a binary semaphore
+///
+/// # Arguments
+///
+/// * `id`: Semaphore ID
+///
+/// # Note
+///
+/// The semaphore is created unavailable, so the first `sem_take` blocks until a
+/// `sem_give`
+pub fn sem_create(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.sem_create(id);
+    });
+}
+
+/// Create a counting semaphore
+///
+/// # Arguments
+///
+/// * `id`: Semaphore ID
+/// * `count`: Initial count
+/// * `max_count`: Upper bound on the count, `sem_give` saturates at this value
+pub fn sem_create_counting(id: usize, count: usize, max_count: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.sem_create_counting(id, count, max_count);
+    });
+}
+
+/// Create a counting semaphore with an order to wake up waiters in
+///
+/// # Arguments
+///
+/// * `id`: Semaphore ID
+/// * `count`: Initial count
+/// * `max_count`: Upper bound on the count, `sem_give` saturates at this value
+/// * `wake_order`: Order in which `sem_give` wakes up waiting tasks, e.g.
+///   `WakeOrder::Fifo` to serve requests in the order they were made
+pub fn sem_create_with_order(id: usize, count: usize, max_count: usize, wake_order: WakeOrder) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.sem_create_with_order(id, count, max_count, wake_order);
+    });
+}
+
+/// Get the count of a semaphore
+///
+/// # Arguments
+///
+/// * `id`: Semaphore to query
+///
+/// # Returns
+///
+/// Number of times the semaphore can be taken without blocking
+pub fn sem_get_count(id: usize) -> usize {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.sem_get_count(id)
+    })
+}
+
+/// Take a semaphore, blocking the current task if it is unavailable
+///
+/// # Arguments
+///
+/// * `id`: Semaphore to take
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// `WaitResult::Acquired` once the semaphore is taken, or
+/// `WaitResult::TimedOut` if the timeout expired
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn sem_take(id: usize, timeout: Option<Tick>) -> WaitResult {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.sem_take(id, timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    wait_result()
+}
+
+/// Give a semaphore, waking the highest priority task waiting on it
+///
+/// # Arguments
+///
+/// * `id`: Semaphore to give
+///
+/// # Note
+///
+/// May be called from a task or an interrupt handler. A context switch may
+/// occur after calling this API.
+pub fn sem_give(id: usize) {
+    isr_yield_if_needed(sem_give_from_isr(id));
+}
+
+/// Give a semaphore from an interrupt handler, without requesting a context
+/// switch
+///
+/// # Arguments
+///
+/// * `id`: Semaphore to give
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, see `isr_yield_if_needed`
+pub fn sem_give_from_isr(id: usize) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.sem_give(id)
+    })
+}
+
+/// Create a mutex
+///
+/// # Arguments
+///
+/// * `id`: Mutex ID
+///
+/// # Note
+///
+/// The mutex uses priority inheritance
+pub fn mutex_create(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.mutex_create(id);
+    });
+}
+
+/// Create a mutex using the immediate priority ceiling protocol
+///
+/// # Arguments
+///
+/// * `id`: Mutex ID
+/// * `ceiling`: Priority of the highest priority task that locks the mutex
+///
+/// # Note
+///
+/// A task locking the mutex runs at the `ceiling` priority until it unlocks it
+pub fn mutex_create_with_ceiling(id: usize, ceiling: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.mutex_create_with_ceiling(id, ceiling);
+    });
+}
+
+/// Lock a mutex, blocking the current task if another task holds it
+///
+/// # Arguments
+///
+/// * `id`: Mutex to lock
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// `WaitResult::Acquired` once the mutex is locked, or `WaitResult::TimedOut`
+/// if the timeout expired
+///
+/// # Note
+///
+/// The mutex is recursive, it must be unlocked as many times as it was locked.
+/// While blocked, the task holding the mutex inherits the priority of the
+/// current task if it is higher. A context switch may occur after calling this
+/// API.
+pub fn mutex_lock(id: usize, timeout: Option<Tick>) -> WaitResult {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.mutex_lock(id, timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    wait_result()
+}
+
+/// Lock a mutex, returning an error instead of blocking forever
+///
+/// # Arguments
+///
+/// * `id`: Mutex to lock
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// `Ok(WaitResult::Acquired)` once the mutex is locked,
+/// `Ok(WaitResult::TimedOut)` if the timeout expired, or
+/// `Err(MutexError::Deadlock)` if the task holding the mutex is waiting on a
+/// mutex held by the current task
+///
+/// # Note
+///
+/// Requires the `deadlock-detection` feature, which also makes `mutex_lock`
+/// panic on a deadlock. A context switch may occur after calling this API.
+#[cfg(feature = "deadlock-detection")]
+pub fn try_mutex_lock(id: usize, timeout: Option<Tick>) -> Result<WaitResult, MutexError> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.try_mutex_lock(id, timeout)? {
+            SCB::set_pendsv();
+        }
+
+        Ok(())
+    })?;
+
+    // If the task blocked, it runs again here once it was woken up
+    Ok(wait_result())
+}
+
+/// Get the outcome of the last blocking call of the current task, see
+/// `rucos::Kernel::get_wait_result`
+///
+/// # Note
+///
+/// The outcome is recorded by the context switch in `PendSV` that resumes the
+/// task, so an interrupt handler running between the resume and this call does
+/// not change it.
+fn wait_result() -> WaitResult {
+    free(|_| {
+        let kernel = unsafe { &*KERNEL.as_ptr() };
+        kernel.get_wait_result()
+    })
+}
+
+/// Unlock a mutex held by the current task
+///
+/// # Arguments
+///
+/// * `id`: Mutex to unlock
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn mutex_unlock(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.mutex_unlock(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Create a read-write lock
+///
+/// # Arguments
+///
+/// * `id`: Read-write lock ID
+pub fn rwlock_create(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.rwlock_create(id);
+    });
+}
+
+/// Lock a read-write lock for reading, blocking the current task while a task
+/// holds it for writing or is waiting to
+///
+/// # Arguments
+///
+/// * `id`: Read-write lock to lock
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// `WaitResult::Acquired` once the lock is held for reading, or
+/// `WaitResult::TimedOut` if the timeout expired
+///
+/// # Panics
+///
+/// The current task already holds `MAX_READ_LOCKS` read locks
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn rwlock_read_lock(id: usize, timeout: Option<Tick>) -> WaitResult {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.rwlock_read_lock(id, timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    wait_result()
+}
+
+/// Unlock a read-write lock held for reading
+///
+/// # Arguments
+///
+/// * `id`: Read-write lock to unlock
+///
+/// # Panics
+///
+/// The read-write lock is not held for reading by the current task
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn rwlock_read_unlock(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.rwlock_read_unlock(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Lock a read-write lock for writing, blocking the current task while any task
+/// holds it
+///
+/// # Arguments
+///
+/// * `id`: Read-write lock to lock
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// `WaitResult::Acquired` once the lock is held for writing, or
+/// `WaitResult::TimedOut` if the timeout expired
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn rwlock_write_lock(id: usize, timeout: Option<Tick>) -> WaitResult {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.rwlock_write_lock(id, timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    wait_result()
+}
+
+/// Unlock a read-write lock held for writing by the current task
+///
+/// # Arguments
+///
+/// * `id`: Read-write lock to unlock
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn rwlock_write_unlock(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.rwlock_write_unlock(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Create a barrier
+///
+/// # Arguments
+///
+/// * `id`: Barrier ID
+/// * `count`: Number of tasks that must arrive to release the barrier
+pub fn barrier_create(id: usize, count: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.barrier_create(id, count);
+    });
+}
+
+/// Wait at a barrier, blocking the current task until enough tasks arrive
+///
+/// # Arguments
+///
+/// * `id`: Barrier to wait at
+///
+/// # Note
+///
+/// The barrier resets once released, so it can be reused for the next round. A
+/// context switch may occur after calling this API.
+pub fn barrier_wait(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.barrier_wait(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Create a condition variable
+///
+/// # Arguments
+///
+/// * `id`: Condition variable ID
+pub fn cond_create(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.cond_create(id);
+    });
+}
+
+/// Unlock a mutex and wait on a condition variable, blocking the current task
+/// until it is notified and has locked the mutex again
+///
+/// # Arguments
+///
+/// * `id`: Condition variable to wait on
+/// * `mutex_id`: Mutex held by the current task, protecting the condition
+///
+/// # Note
+///
+/// Notifications are not remembered, so check the condition with the mutex
+/// locked before waiting, and again after waking up. A context switch may occur
+/// after calling this API.
+pub fn cond_wait(id: usize, mutex_id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.cond_wait(id, mutex_id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Notify the highest priority task waiting on a condition variable
+///
+/// # Arguments
+///
+/// * `id`: Condition variable to notify
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn cond_notify_one(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.cond_notify_one(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Notify all tasks waiting on a condition variable
+///
+/// # Arguments
+///
+/// * `id`: Condition variable to notify
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn cond_notify_all(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.cond_notify_all(id) {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Pool of fixed size memory blocks that can be shared with interrupt handlers
+///
+/// # Generics
+///
+/// * `BLOCK`: Size of each block in bytes
+/// * `N`: Number of blocks in the pool
+///
+/// # Note
+///
+/// Intended to be placed in a `static`, e.g. to allocate task stacks at
+/// runtime. Allocating and freeing are O(1) and run in a critical section.
+pub struct StaticMemPool<const BLOCK: usize, const N: usize> {
+    /// Free block list, without block memory
+    pool: UnsafeCell<MemPool<0, N>>,
+    /// Block memory, only accessed through pointers to single blocks since
+    /// allocated blocks are borrowed outside the pool
+    blocks: UnsafeCell<[[u8; BLOCK]; N]>,
+}
+
+// The pool is only accessed in critical sections
+unsafe impl<const BLOCK: usize, const N: usize> Sync for StaticMemPool<BLOCK, N> {}
+
+impl<const BLOCK: usize, const N: usize> StaticMemPool<BLOCK, N> {
+    /// Create a pool with all blocks free
+    pub const fn new() -> Self {
+        Self {
+            pool: UnsafeCell::new(MemPool::new()),
+            blocks: UnsafeCell::new([[0; BLOCK]; N]),
+        }
+    }
+
+    /// Allocate a block
+    ///
+    /// # Returns
+    ///
+    /// Memory of the allocated block, or `None` if all blocks are allocated
+    pub fn alloc(&'static self) -> Option<&'static mut [u8; BLOCK]> {
+        free(|_| {
+            let pool = unsafe { &mut *self.pool.get() };
+            let idx = pool.alloc()?;
+            let block = unsafe { self.blocks.get().cast::<[u8; BLOCK]>().add(idx) };
+
+            // The block is not handed out again until it is freed
+            Some(unsafe { &mut *block })
+        })
+    }
+
+    /// Free a block
+    ///
+    /// # Arguments
+    ///
+    /// * `block`: Memory of a block allocated from this pool
+    ///
+    /// # Panics
+    ///
+    /// * The `block` was not allocated from this pool
+    /// * The `block` is not allocated, e.g. it was already freed
+    ///
+    /// # Note
+    ///
+    /// A block used as a task stack must not be freed while the task exists
+    pub fn free(&'static self, block: &'static mut [u8; BLOCK]) {
+        let offset = (block.as_ptr() as usize).checked_sub(self.blocks.get() as usize);
+        let idx = match offset {
+            Some(offset) if BLOCK != 0 && offset % BLOCK == 0 && offset / BLOCK < N => {
+                offset / BLOCK
+            }
+            _ => panic!("Block not allocated from this pool"),
+        };
+
+        free(|_| {
+            let pool = unsafe { &mut *self.pool.get() };
+            pool.free(idx);
+        });
+    }
+
+    /// Get the number of free blocks
+    ///
+    /// # Returns
+    ///
+    /// Number of blocks that can be allocated
+    pub fn available(&'static self) -> usize {
+        free(|_| {
+            let pool = unsafe { &*self.pool.get() };
+            pool.available()
+        })
+    }
+}
+
+impl<const BLOCK: usize, const N: usize> Default for StaticMemPool<BLOCK, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create an event group
+///
+/// # Arguments
+///
+/// * `id`: Event group ID
+pub fn event_create(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.event_create(id);
+    });
+}
+
+/// Wait on a combination of flags in an event group, blocking the current
+/// task until they are set
+///
+/// # Arguments
+///
+/// * `id`: Event group to wait on
+/// * `mask`: Event flags to wait on
+/// * `wait_all`: `true` to wait on all flags in `mask`, `false` to wait on any
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// `WaitResult::Acquired` once the flags are set, or `WaitResult::TimedOut` if
+/// the timeout expired
+///
+/// # Note
+///
+/// Waiting does not clear any flags. A context switch may occur after calling
+/// this API.
+pub fn event_wait(id: usize, mask: u32, wait_all: bool, timeout: Option<Tick>) -> WaitResult {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.event_wait(id, mask, wait_all, timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    wait_result()
+}
+
+/// Set flags in an event group, waking all tasks waiting on them
+///
+/// # Arguments
+///
+/// * `id`: Event group to update
+/// * `bits`: Event flags to set
+///
+/// # Note
+///
+/// May be called from a task or an interrupt handler. A context switch may
+/// occur after calling this API.
+pub fn event_set(id: usize, bits: u32) {
+    isr_yield_if_needed(event_set_from_isr(id, bits));
+}
+
+/// Set flags in an event group from an interrupt handler, without requesting a
+/// context switch
+///
+/// # Arguments
+///
+/// * `id`: Event group to update
+/// * `bits`: Event flags to set
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, see `isr_yield_if_needed`
+pub fn event_set_from_isr(id: usize, bits: u32) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.event_set(id, bits)
+    })
+}
+
+/// Clear flags in an event group
+///
+/// # Arguments
+///
+/// * `id`: Event group to update
+/// * `bits`: Event flags to clear
+pub fn event_clear(id: usize, bits: u32) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.event_clear(id, bits);
+    });
+}
+
+/// Get the flags of an event group
+///
+/// # Arguments
+///
+/// * `id`: Event group to query
+///
+/// # Returns
+///
+/// Current event flags
+pub fn event_get(id: usize) -> u32 {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.event_get(id)
+    })
+}
+
+/// Create a mailbox
+///
+/// # Arguments
+///
+/// * `id`: Mailbox ID
+///
+/// # Note
+///
+/// The mailbox holds a single word, which can be a value or the address of
+/// data that outlives the mailbox. It is created empty.
+pub fn mailbox_create(id: usize) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.mailbox_create(id);
+    });
+}
+
+/// Post an item to a mailbox, replacing any item not yet fetched
+///
+/// # Arguments
+///
+/// * `id`: Mailbox to post to
+/// * `item`: Item to post
+///
+/// # Note
+///
+/// May be called from a task or an interrupt handler. A context switch may
+/// occur after calling this API.
+pub fn mailbox_post(id: usize, item: usize) {
+    isr_yield_if_needed(mailbox_post_from_isr(id, item));
+}
+
+/// Post an item to a mailbox from an interrupt handler, without requesting a
+/// context switch
+///
+/// # Arguments
+///
+/// * `id`: Mailbox to post to
+/// * `item`: Item to post
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, see `isr_yield_if_needed`
+pub fn mailbox_post_from_isr(id: usize, item: usize) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.mailbox_post(id, item)
+    })
+}
+
+/// Fetch the item in a mailbox, blocking the current task if it is empty
+///
+/// # Arguments
+///
+/// * `id`: Mailbox to fetch from
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// Item fetched from the mailbox, or `None` if the timeout expired
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn mailbox_fetch(id: usize, timeout: Option<Tick>) -> Option<usize> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.mailbox_fetch(id, timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.take_message()
+    })
+}
+
+/// Arm a one-shot alarm, without blocking the current task
+///
+/// # Arguments
+///
+/// * `id`: Alarm ID
+/// * `delay`: Number of ticks until the alarm fires
+/// * `callback`: Function called with the alarm ID when the alarm fires
+///
+/// # Note
+///
+/// The callback runs once from the SysTick handler, or from the idle task after
+/// tickless idle or deep sleep, once the kernel critical section has ended. It
+/// must be short and must not block, but may call kernel APIs that are allowed
+/// from interrupt handlers (e.g. `event_set` or `sem_give`) or re-arm the
+/// alarm. Arming an alarm that is already armed restarts it with the new
+/// `delay` and `callback`.
+pub fn alarm(id: usize, delay: Tick, callback: fn(usize)) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.alarm_set(id, delay, callback);
+    });
+}
+
+/// Cancel an alarm before it fires
+///
+/// # Arguments
+///
+/// * `id`: Alarm to cancel
+///
+/// # Returns
+///
+/// `true` if the alarm was armed, `false` if not
+pub fn alarm_cancel(id: usize) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.alarm_cancel(id)
+    })
+}
+
+/// Defer work to a task waiting in `work_wait`
+///
+/// # Arguments
+///
+/// * `work_id`: Token identifying the work to do
+///
+/// # Note
+///
+/// Intended to be called from an interrupt handler, so the handler stays short
+/// and the rest of the work runs in a task. A context switch may occur after
+/// calling this API.
+pub fn defer(work_id: usize) {
+    isr_yield_if_needed(defer_from_isr(work_id));
+}
+
+/// Defer work to a task waiting in `work_wait`, without requesting a context
+/// switch
+///
+/// # Arguments
+///
+/// * `work_id`: Token identifying the work to do
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, see `isr_yield_if_needed`
+pub fn defer_from_isr(work_id: usize) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.defer(work_id)
+    })
+}
+
+/// Wait for work deferred with `defer`, blocking the current task until there
+/// is some
+///
+/// # Returns
+///
+/// Token of the oldest deferred work item
+///
+/// # Note
+///
+/// Usually called in a loop by a dedicated high priority server task. A
+/// context switch may occur after calling this API.
+pub fn work_wait() -> usize {
+    loop {
+        let work_id = free(|_| {
+            let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+            let work_id = kernel.take_work();
+            if work_id.is_none() && kernel.work_wait() {
+                SCB::set_pendsv();
+            }
+
+            work_id
+        });
+
+        // If the task blocked, it runs again here once work was deferred
+        if let Some(work_id) = work_id {
+            return work_id;
+        }
+    }
+}
+
+/// Set how notifications sent to a task are combined
+///
+/// # Arguments
+///
+/// * `id`: Task to update
+/// * `action`: How a new notification is combined with one the task has not
+///   taken yet, the default is `NotifyAction::Overwrite`
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+pub fn set_notify_action(id: usize, action: NotifyAction) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_notify_action(id, action);
+    });
+}
+
+/// Send a notification value to a task, waking it if it is in `notify_wait`
+///
+/// # Arguments
+///
+/// * `id`: Task to notify
+/// * `value`: Notification value
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+///
+/// # Note
+///
+/// A lighter alternative to a mailbox for signalling a single task. May be
+/// called from a task or an interrupt handler. A context switch may occur
+/// after calling this API.
+pub fn notify(id: usize, value: u32) {
+    isr_yield_if_needed(notify_from_isr(id, value));
+}
+
+/// Send a notification value to a task from an interrupt handler, without
+/// requesting a context switch
+///
+/// # Arguments
+///
+/// * `id`: Task to notify
+/// * `value`: Notification value
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, see `isr_yield_if_needed`
+///
+/// # Panics
+///
+/// The `id` provided does not correspond to a task
+pub fn notify_from_isr(id: usize, value: u32) -> bool {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.notify(id, value)
+    })
+}
+
+/// Wait for a notification sent to the current task with `notify`
+///
+/// # Arguments
+///
+/// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+///
+/// # Returns
+///
+/// Notification value, which is cleared, or `None` if the timeout expired
+///
+/// # Note
+///
+/// A context switch may occur after calling this API
+pub fn notify_wait(timeout: Option<Tick>) -> Option<u32> {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.notify_wait(timeout) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.take_notification()
+    })
+}
+
+/// Wait until an absolute tick count or a notification sent to the current task
+/// with `notify`
+///
+/// # Arguments
+///
+/// * `wakeup_tick`: Value of the kernel tick at which the wait ends
+/// * `max_value`: Largest notification value to report, a larger value is
+///   reported as `max_value`
+///
+/// # Returns
+///
+/// `WakeReason::Signalled` with the notification value, which is cleared, or
+/// `WakeReason::Deadline` if `wakeup_tick` was reached first
+///
+/// # Note
+///
+/// Unlike `notify_wait`, the end of the wait does not depend on when this is
+/// called, so successive waits can advance `wakeup_tick` by a period without
+/// drift. A context switch may occur after calling this API.
+pub fn wait_until_or_signal(wakeup_tick: Tick, max_value: u32) -> WakeReason {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.wait_until_or_signal(wakeup_tick) {
+            SCB::set_pendsv();
+        }
+    });
+
+    // If the task blocked, it runs again here once it was woken up
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.take_wake_reason(max_value)
+    })
+}
+
+/// Lock the scheduler, deferring context switches away from the current task
+///
+/// # Note
+///
+/// Provides mutual exclusion against other tasks without disabling interrupts,
+/// so interrupt latency is unaffected. Calls may be nested. Blocking APIs
+/// should not be called while the scheduler is locked.
+pub fn scheduler_lock() {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.scheduler_lock();
+    });
+}
+
+/// Unlock the scheduler
+///
+/// # Note
+///
+/// A context switch deferred while the scheduler was locked occurs after the
+/// outermost call to this API
+pub fn scheduler_unlock() {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        if kernel.scheduler_unlock() {
+            SCB::set_pendsv();
+        }
+    });
+}
+
+/// Check if a context switch has been requested but not yet performed
+///
+/// # Returns
+///
+/// `true` if a context switch is pending, `false` if not
+///
+/// # Note
+///
+/// A context switch deferred while the scheduler is locked is not pending
+/// until the scheduler is unlocked
+pub fn switch_pending() -> bool {
+    free(|_| {
+        let kernel = unsafe { &*KERNEL.as_ptr() };
+        kernel.is_switch_pending()
+    })
+}
+
+/// Get the longest time spent in a context switch
+///
+/// # Returns
+///
+/// Worst-case number of CPU cycles spent in the kernel during a context
+/// switch since the kernel started, or `0` if no switch has happened
+///
+/// # Note
+///
+/// Requires the `switch-timing` feature, which enables the DWT cycle counter
+/// when the kernel starts. The count covers selecting the next task, not the
+/// register save and restore around it.
+#[cfg(feature = "switch-timing")]
+pub fn max_switch_cycles() -> u32 {
+    free(|_| unsafe { (*core::ptr::addr_of!(SWITCH_CYCLES)).max() })
+}
+
+/// Set the idle hook
+///
+/// # Arguments
+///
+/// * `hook`: Function called by the default idle task once per loop iteration
+///   before sleeping the core, or `None` to remove the hook
+///
+/// # Note
+///
+/// The hook runs in the idle task, so like a user idle task it must not block
+/// or call any kernel APIs (e.g. `sleep`). Has no effect with a user idle task.
+pub fn set_idle_hook(hook: Option<fn()>) {
+    free(|_| unsafe { IDLE_HOOK = hook });
 }
 
-/// Create a task
+/// Set the tick hook
 ///
 /// # Arguments
 ///
-/// * `id`: Task ID
-/// * `priority`: Task priority, with a lower number meaning higher priority
-/// * `stack`: Task stack memory
-/// * `entry`: Task function
-/// * `arg`: An optional argument to pass to `entry`
+/// * `hook`: Function called with the kernel tick from the SysTick handler,
+///   before the scheduler runs, or `None` to remove the hook
 ///
 /// # Note
 ///
-/// A context switch may occur after calling this API, if the kernel is running
-pub fn create(id: usize, priority: usize, stack: &mut [u8], entry: fn(u32) -> !, arg: Option<u32>) {
-    let mut stack_ptr = stack.as_mut_ptr() as u32 + stack.len() as u32;
-    let arg = arg.unwrap_or(0);
-
-    // Align the stack
-    stack_ptr &= 0xFFFF_FFF8;
-
-    let register_values = [
-        0x0100_0000,                   // xPSR
-        entry as *const () as u32,     // PC
-        task_exit as *const () as u32, // R14 (LR)
-        0x1212_1212,                   // R12
-        0x0303_0303,                   // R3
-        0x0202_0202,                   // R2
-        0x0101_0101,                   // R1
-        arg,                           // R0
-        0xFFFF_FFFD,                   // R14 (EXC_RETURN)
-        0x1111_1111,                   // R11
-        0x1010_1010,                   // R10
-        0x0909_0909,                   // R9
-        0x0808_0808,                   // R8
-        0x0707_0707,                   // R7
-        0x0606_0606,                   // R6
-        0x0505_0505,                   // R5
-        0x0404_0404,                   // R4
-    ];
-
-    for register_value in register_values {
-        stack_ptr -= 4;
-        unsafe { write_volatile(stack_ptr as *mut u32, register_value) };
-    }
-
+/// The hook runs in interrupt context on every tick, so it must be short. With
+/// tickless idle, more than one tick may have elapsed between calls.
+pub fn set_tick_hook(hook: Option<fn(Tick)>) {
     free(|_| {
         let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-        if kernel.create(id, priority, stack_ptr) {
-            SCB::set_pendsv();
-        }
+        kernel.set_tick_hook(hook);
     });
 }
 
-/// Delete a task
+/// Set the deadline miss hook
 ///
 /// # Arguments
 ///
-/// * `id`: Task to delete or `None` to delete the current task
+/// * `hook`: Function called with the task ID when a task misses its deadline,
+///   or `None` to remove the hook
 ///
 /// # Note
 ///
-/// A context switch may occur after calling this API
-pub fn delete(id: Option<usize>) {
+/// The hook runs in the task that missed its deadline, in a critical section
+pub fn set_deadline_hook(hook: Option<fn(usize)>) {
     free(|_| {
         let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-        if kernel.delete(id) {
-            SCB::set_pendsv();
-        }
+        kernel.set_deadline_hook(hook);
     });
 }
 
-/// Start the kernel
+/// Set the watchdog fault hook
 ///
 /// # Arguments
 ///
-/// * `scb`: System control block (from the `cortex-m` crate)
-/// * `systick`: System tick  (from the `cortex-m` crate)
-/// * `clock_freq_hz`: Core clock frequency in hertz
+/// * `hook`: Function called with the task ID when a task has not called
+///   `task_kick` within its watchdog timeout, or `None` to remove the hook
 ///
 /// # Note
 ///
-/// Does not return: Program execution continues from tasks or interrupt
-/// handlers after calling this API
-pub fn start(scb: &mut SCB, systick: &mut SYST, clock_freq_hz: u32) -> ! {
-    let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-    let first_task_stack_ptr = kernel.start();
-
-    systick.set_reload((clock_freq_hz / _TICK_RATE_HZ) - 1);
-    systick.clear_current();
-    systick.set_clock_source(SystClkSource::Core);
-    systick.enable_interrupt();
-    systick.enable_counter();
-
-    unsafe {
-        // Context switch should only happen once all interrupts have been serviced
-        scb.set_priority(scb::SystemHandler::PendSV, 0xFF);
-
-        asm!(
-            "cpsid  i",                    // Disable interrupts
-            "mov    r0, {tmp}",            // Get first task stack pointer
-            "msr    psp, r0",              // Write PSP
-            "mrs    r1, control",          // Read CONTROL
-            "orr    r1, r1, #2",           // Set SP = PSP
-            "bic    r1, r1, #4",           // Clear FPCA (reset FPU)
-            "msr    control, r1",          // Write CONTROL
-            "isb",                         // Sync instructions
-            "ldmia  sp!, {{r4-r11, r14}}", // Restore R4 - R11, LR
-            "ldmia  sp!, {{r0-r3}}",       // Restore R0 - R3
-            "ldmia  sp!, {{r12, r14}}",    // Load R12 and LR
-            "ldmia  sp!, {{r1, r2}}",      // Load PC and discard xPSR
-            "cpsie  i",                    // Enable interrupts
-            "bx     r1",                   // Branch to first task
-            tmp = in(reg) first_task_stack_ptr,
-            options(noreturn),
-        )
-    };
+/// The hook runs in the SysTick handler, in a critical section, and would
+/// typically reset the system with `SCB::sys_reset`
+pub fn set_watchdog_hook(hook: Option<fn(usize)>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_watchdog_hook(hook);
+    });
 }
 
-/// Get the ID of the current task
+/// Set the task delete hook
 ///
-/// # Returns
+/// # Arguments
 ///
-/// ID of the current task
-pub fn get_current_task() -> usize {
-    let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-
-    // Does not modify the kernel
-    kernel.get_current_task()
+/// * `hook`: Function called with the task ID after a task is deleted, or
+///   `None` to remove the hook
+///
+/// # Note
+///
+/// The hook runs once the critical section of `delete` has ended, in the task
+/// calling `delete`, or from the next SysTick handler or the default idle task
+/// if a task deleted itself (e.g. its function returned). It must be short and
+/// must not block, but may call kernel APIs that are allowed from interrupt
+/// handlers (e.g. `sem_give`) or free the task stack back into a
+/// `StaticMemPool`. A task that deleted itself has been switched out by then,
+/// so its stack is no longer in use.
+pub fn set_delete_hook(hook: Option<fn(usize)>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_delete_hook(hook);
+    });
 }
 
-/// Get the current value of the kernel tick
+/// Set the stack overflow hook
 ///
-/// # Returns
+/// # Arguments
 ///
-/// Current value of the kernel tick
+/// * `hook`: Function called with the task ID when a stack overflow is
+///   detected, or `None` to remove the hook
 ///
 /// # Note
 ///
-/// Ticks correspond to system time based on `TICK_RATE_HZ`
-pub fn get_current_tick() -> u64 {
-    let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-
-    // Does not modify the kernel
-    kernel.get_current_tick()
+/// With the `idle-stack-check` feature, the idle task canary is checked on every
+/// context switch to the idle task, see `check_stack`. The hook then runs in the
+/// PendSV handler with kernel interrupts masked, before the idle task resumes.
+pub fn set_overflow_hook(hook: Option<fn(usize)>) {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.set_overflow_hook(hook);
+    });
 }
 
-/// Sleep the current task
+/// Set the context switch hook
 ///
 /// # Arguments
 ///
-/// * `delay`: Number of ticks to sleep
+/// * `hook`: Function called on every context switch with the ID of the
+///   outgoing task (or `None` if there is none, e.g. it was deleted) and the ID
+///   of the incoming task, or `None` to remove the hook
 ///
 /// # Note
 ///
-/// Ticks correspond to system time based on `TICK_RATE_HZ`
-pub fn sleep(delay: u64) {
+/// The hook runs in the PendSV handler with kernel interrupts masked, so it must be
+/// minimal (e.g. toggle a GPIO or emit a trace event)
+pub fn set_switch_hook(hook: Option<fn(Option<usize>, usize)>) {
     free(|_| {
         let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-        if kernel.sleep(delay) {
-            SCB::set_pendsv();
-        }
+        kernel.set_switch_hook(hook);
     });
 }
 
-/// Suspend a task
+/// Set the no runnable task hook
 ///
 /// # Arguments
 ///
-/// * `id`: Task to suspend or `None` to suspend the current task
-///
-/// # Note
-///
-/// A context switch may occur after calling this API
-pub fn suspend(id: Option<usize>) {
+/// * `hook`: Function called when no task can run because the idle task does
+///   not exist, or `None` to panic instead (the default)
+pub fn set_no_runnable_hook(hook: Option<fn()>) {
     free(|_| {
         let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-        if kernel.suspend(id) {
-            SCB::set_pendsv();
-        }
+        kernel.set_no_runnable_hook(hook);
     });
 }
 
-/// Resume a task
+/// Enable or disable tickless idle
 ///
 /// # Arguments
 ///
-/// * `id`: Task to resume
+/// * `enable`: `true` to suppress tick interrupts while idle, `false` to tick
+///   at `TICK_RATE_HZ` at all times (the default)
 ///
 /// # Note
 ///
-/// A context switch may occur after calling this API
-pub fn resume(id: usize) {
+/// When the default idle task is the only task that can run, SysTick is
+/// reprogrammed to fire once at the earliest task wake up instead of every tick.
+/// The idle period is limited by the 24-bit SysTick reload register, so long
+/// sleeps are split into several idle periods. If another interrupt wakes the
+/// core early, the ticks that elapsed are accounted for and the tick period is
+/// restored on its original boundary. Has no effect with a user idle task.
+pub fn enable_tickless(enable: bool) {
+    free(|_| unsafe { TICKLESS = enable });
+}
+
+/// Set the deep sleep hook
+///
+/// # Arguments
+///
+/// * `hook`: Function called by the default idle task with the number of ticks
+///   until the next task wake up (or `Tick::MAX` if no task wakes up on its
+///   own), returning the number of ticks that actually elapsed, or `None` to
+///   remove the hook
+/// * `min_ticks`: Minimum number of idle ticks to call the hook for, shorter
+///   idle periods use `WFI` or tickless idle as usual
+///
+/// # Note
+///
+/// Intended for low power modes like STOP or STANDBY, where SysTick does not
+/// run. The hook enters the low power mode, restores the clocks on wake up and
+/// measures the time slept with a timer that keeps running, see `TickResync`.
+/// SysTick is stopped while the hook runs, and the kernel tick is advanced by
+/// the returned ticks once it returns. The hook runs with interrupts disabled,
+/// but a pending interrupt still wakes the core and is serviced after the hook
+/// returns. It must not call kernel APIs. Has no effect with a user idle task.
+pub fn set_deep_sleep_hook(hook: Option<fn(Tick) -> Tick>, min_ticks: Tick) {
+    free(|_| unsafe { DEEP_SLEEP = hook.map(|hook| (hook, min_ticks)) });
+}
+
+/// Request the kernel to re-evaluate which task should run
+///
+/// # Note
+///
+/// Intended for advanced use, where the scheduler has not run since the last
+/// change that may affect it. Task state must still be changed through kernel
+/// APIs, a task state written directly desyncs the ready list of the kernel,
+/// see `rucos::Kernel::reschedule`. May be called from a task or an interrupt
+/// handler. A context switch may occur after calling this API.
+pub fn request_reschedule() {
     free(|_| {
         let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-        if kernel.resume(id) {
+        if kernel.reschedule() {
             SCB::set_pendsv();
         }
     });
@@ -249,39 +3328,108 @@ pub fn resume(id: usize) {
 #[no_mangle]
 pub extern "C" fn SysTick() {
     free(|_| {
+        // Restore the tick period after an early wake up from tickless idle
+        if unsafe { TICKLESS_RESTORE } {
+            let mut systick = unsafe { cortex_m::Peripherals::steal() }.SYST;
+            systick.set_reload(unsafe { TICK_RELOAD });
+            systick.clear_current();
+            unsafe { TICKLESS_RESTORE = false };
+        }
+
         let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-        if kernel.tick_update(1) {
+        if tick_update(kernel, 1) {
             SCB::set_pendsv();
         }
     });
+
+    run_alarms();
+    run_delete_hooks();
+}
+
+/// Advance the kernel tick and its copy read by `get_current_tick`
+///
+/// # Arguments
+///
+/// * `kernel`: Kernel, borrowed in a critical section
+/// * `elapsed`: Number of ticks that have passed since last call
+///
+/// # Returns
+///
+/// `true` if a context switch is needed, `false` if not
+fn tick_update(kernel: &mut Kernel<u32, Tick, MAX_NUM_TASKS>, elapsed: Tick) -> bool {
+    let switch = kernel.tick_update(elapsed);
+    TICK_COUNTER.store(u64::from(kernel.get_current_tick()));
+    switch
+}
+
+/// Call the delete hook for tasks deleted since it was last called
+///
+/// # Note
+///
+/// Called outside the critical section, after the kernel borrow used for
+/// `delete` has ended, so the hook can call kernel APIs. A task that deleted
+/// itself never runs again, so its hook is called from the next `SysTick` or
+/// by the default idle task.
+fn run_delete_hooks() {
+    while let Some((id, hook)) = free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.take_deleted_task()
+    }) {
+        hook(id);
+    }
+}
+
+/// Call the callbacks of alarms fired by `tick_update`
+///
+/// # Note
+///
+/// Called outside the critical section, after the kernel borrow used for
+/// `tick_update` has ended, so a callback can call kernel APIs.
+fn run_alarms() {
+    while let Some((id, callback)) = free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.take_fired_alarm()
+    }) {
+        callback(id);
+    }
 }
 
 /// PendSV interrupt handler
 ///
-/// Context switch implementation
+/// Context switch implementation, masking the interrupt handlers that may call
+/// the kernel (priority value `KERNEL_INTERRUPT_PRIORITY` or higher) so more
+/// urgent interrupts are not delayed. SysTick has the same priority as PendSV,
+/// so it cannot preempt the context switch either way.
+///
+/// The floating point registers S16 - S31 are only saved and restored for a
+/// task whose saved EXC_RETURN has an extended frame, which only a task with
+/// `task_uses_fpu` set can have since the floating point unit is disabled for
+/// the others, see `UsageFault`. The exception returns with the EXC_RETURN of
+/// the next task, so its frame type matches the one on its stack.
 #[naked]
 #[no_mangle]
 pub extern "C" fn PendSV() {
     unsafe {
-        // TODO: Replace disabling interrupts with BASEPRI adjustment
         asm!(
-            "cpsid     i",                    // Disable interrupts
+            "mov       r2, #{basepri}",       // Mask kernel interrupts
+            "msr       basepri, r2",          // ...
+            "dsb",                            // ...
+            "isb",                            // ...
             "mrs       r0, psp",              // Read PSP
-            "mov       r1, lr",               // Save LR
             "tst       r14, #0x10",           // Check if FPU is being used
             "it        eq",                   // ...
             "vstmdbeq  r0!, {{s16-s31}}",     // Push the FPU registers
             "stmdb     r0!, {{r4-r11, r14}}", // Push the CPU registers
-            "push      {{r1}}",               // Push LR
             "bl        context_switch",       // context_switch(R0) -> R0
-            "pop       {{r1}}",               // Pop LR
             "ldmia     r0!, {{r4-r11, r14}}", // Pop the CPU registers
             "tst       r14, #0x10",           // Check if FPU is being used
             "it        eq",                   // ...
             "vldmiaeq  r0!, {{s16-s31}}",     // Pop the FPU registers
             "msr       psp, r0",              // Write PSP
-            "cpsie     i",                    // Enable interrupts
-            "bx        r1",                   // Branch to next task
+            "mov       r2, #0",               // Unmask kernel interrupts
+            "msr       basepri, r2",          // ...
+            "bx        lr",                   // Branch to next task
+            basepri = const KERNEL_INTERRUPT_PRIORITY,
             options(noreturn),
         );
     }
@@ -298,16 +3446,265 @@ pub extern "C" fn PendSV() {
 /// Stack pointer of the next task
 #[no_mangle]
 fn context_switch(curr_task_stack_ptr: u32) -> u32 {
+    #[cfg(feature = "switch-timing")]
+    return unsafe {
+        (*core::ptr::addr_of_mut!(SWITCH_CYCLES)).measure(&Dwt, || switch_task(curr_task_stack_ptr))
+    };
+
+    #[cfg(not(feature = "switch-timing"))]
+    switch_task(curr_task_stack_ptr)
+}
+
+/// Save the current task and select the next task, see `context_switch`
+fn switch_task(curr_task_stack_ptr: u32) -> u32 {
     let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
-    kernel.handle_context_switch(Some(curr_task_stack_ptr))
+
+    // The saved EXC_RETURN follows R4 - R11, with bit 4 clear if the task has
+    // an active floating point context, e.g. after the floating point unit was
+    // left enabled by an interrupt handler using it, see `UsageFault`. A
+    // restarted task starts over without it.
+    let restart = unsafe { PENDING_RESTART.take() };
+    let exc_return = unsafe { read_volatile((curr_task_stack_ptr as *const u32).add(8)) };
+    if exc_return & 0x10 == 0 && restart.is_none() {
+        kernel.mark_fpu_used();
+    }
+
+    // Writing the canary of a restarted task must not hit the watchpoint
+    #[cfg(feature = "stack-watchpoint")]
+    stack_watchpoint_disable();
+
+    // The restarted task is no longer running, so its stack can be rewritten
+    if let Some((id, arg)) = restart {
+        if let (Some((bottom, size)), Some(entry)) =
+            (kernel.get_stack_region(id), kernel.get_task_entry(id))
+        {
+            let stack = unsafe { core::slice::from_raw_parts_mut(bottom as *mut u8, size) };
+            let _ = init_stack(stack, entry as u32, arg);
+        }
+    }
+
+    let next_task_stack_ptr = kernel.handle_context_switch(Some(curr_task_stack_ptr));
+
+    // The next task only gets the floating point unit once it has used it, and
+    // must have it to restore its floating point registers
+    fpu_enable(kernel.task_uses_fpu(kernel.get_current_task()) == Some(true));
+
+    #[cfg(feature = "stack-watchpoint")]
+    stack_watchpoint_update(kernel);
+
+    next_task_stack_ptr
+}
+
+/// UsageFault exception handler
+///
+/// The floating point unit is disabled for a task until it has used it, so its
+/// first floating point instruction faults here. The task is recorded with
+/// `mark_fpu_used`, and the unit is enabled so the instruction runs again on
+/// return. Any other usage fault panics.
+///
+/// A floating point instruction in an interrupt handler only enables the unit
+/// until the next context switch, which records the current task if it used the
+/// unit in the meantime. The first floating point instruction of a task must not
+/// be inside a critical section, which escalates the fault to a HardFault.
+#[no_mangle]
+pub extern "C" fn UsageFault() {
+    let scb = unsafe { &*SCB::PTR };
+    let cfsr = scb.cfsr.read();
+    assert!(cfsr & CFSR_NOCP != 0, "UsageFault, CFSR {:#010x}", cfsr);
+    unsafe { scb.cfsr.write(CFSR_NOCP) };
+
+    // Returning to thread mode means the fault came from the current task, an
+    // interrupt handler may have preempted the kernel
+    if scb.icsr.read() & ICSR_RETTOBASE != 0 {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        kernel.mark_fpu_used();
+    }
+
+    fpu_enable(true);
+}
+
+/// Enable or disable the floating point unit
+fn fpu_enable(enable: bool) {
+    let scb = unsafe { &*SCB::PTR };
+    unsafe {
+        scb.cpacr.modify(|cpacr| {
+            if enable {
+                cpacr | CPACR_FPU
+            } else {
+                cpacr & !CPACR_FPU
+            }
+        })
+    };
+
+    cortex_m::asm::dsb();
+    cortex_m::asm::isb();
+}
+
+/// Write the initial context of a task to its stack, returning the task stack
+/// pointer
+///
+/// The context is a basic frame without floating point registers, since a new
+/// or restarted task has not used the floating point unit yet
+fn init_stack(stack: &mut [u8], entry: u32, arg: Option<u32>) -> u32 {
+    assert!(
+        rucos::stack_fits(
+            stack.as_ptr() as usize,
+            stack.len(),
+            INITIAL_CONTEXT_SIZE,
+            STACK_ALIGN
+        ),
+        "Task stack cannot fit the initial context, see MIN_STACK_SIZE"
+    );
+
+    rucos::stack_fill(stack);
+    rucos::stack_canary_write(stack);
+
+    let mut stack_ptr = stack_top(stack);
+    let arg = arg.unwrap_or(0);
+
+    let register_values = [
+        0x0100_0000,                   // xPSR
+        entry,                         // PC
+        task_exit as *const () as u32, // R14 (LR)
+        0x1212_1212,                   // R12
+        0x0303_0303,                   // R3
+        0x0202_0202,                   // R2
+        0x0101_0101,                   // R1
+        arg,                           // R0
+        0xFFFF_FFFD,                   // R14 (EXC_RETURN)
+        0x1111_1111,                   // R11
+        0x1010_1010,                   // R10
+        0x0909_0909,                   // R9
+        0x0808_0808,                   // R8
+        0x0707_0707,                   // R7
+        0x0606_0606,                   // R6
+        0x0505_0505,                   // R5
+        0x0404_0404,                   // R4
+    ];
+
+    for register_value in register_values {
+        stack_ptr -= 4;
+        unsafe { write_volatile(stack_ptr as *mut u32, register_value) };
+    }
+
+    stack_ptr
+}
+
+/// Aligned top of a task stack, where the initial context ends
+fn stack_top(stack: &[u8]) -> u32 {
+    rucos::stack_top(stack.as_ptr() as usize, stack.len(), STACK_ALIGN)
+        .expect("Task stack ends past the address space") as u32
+}
+
+/// Task stack pointer returned by `init_stack`, without writing to the stack
+fn initial_stack_ptr(stack: &[u8]) -> u32 {
+    stack_top(stack) - INITIAL_CONTEXT_SIZE as u32
 }
 
-/// Tasks should not exit
-fn task_exit() {
+/// Delete a task once its function returns
+extern "C" fn task_exit() -> ! {
+    delete(None);
+
+    // The context switch happens as soon as the deletion completes
     loop {}
 }
 
-/// Default idle task function
+/// Default idle task function, sleeping the core until the next interrupt
 fn idle_task(_: u32) -> ! {
-    loop {}
+    loop {
+        // A task that deleted itself may have left its hook to the idle task,
+        // SysTick can be suppressed by tickless idle
+        run_delete_hooks();
+
+        if let Some(hook) = free(|_| unsafe { IDLE_HOOK }) {
+            hook();
+        }
+
+        idle_sleep();
+    }
+}
+
+/// Sleep the core until the next interrupt, suppressing tick interrupts until
+/// the next task wake up if tickless idle is enabled
+fn idle_sleep() {
+    free(|_| {
+        let kernel = unsafe { &mut *KERNEL.as_mut_ptr() };
+        let tick_cycles = unsafe { TICK_RELOAD } + 1;
+        let max_ticks = ((SYSTICK_MAX_RELOAD + 1) / tick_cycles) as Tick;
+
+        let wakeup_ticks = match kernel.get_next_wakeup() {
+            Some(wakeup) if !kernel.get_current_tick().wrapping_ge(wakeup) => {
+                wakeup.wrapping_sub(kernel.get_current_tick())
+            }
+            Some(_) => 0,
+            None => Tick::MAX,
+        };
+
+        // Long idle periods are handed to the deep sleep hook, which keeps time
+        // itself while SysTick is stopped
+        if let Some((hook, min_ticks)) = unsafe { DEEP_SLEEP } {
+            if kernel.is_idle() && wakeup_ticks >= min_ticks.max(1) {
+                let mut systick = unsafe { cortex_m::Peripherals::steal() }.SYST;
+                systick.disable_counter();
+                let elapsed_ticks = hook(wakeup_ticks);
+
+                // Start a new tick period on wake up, any tickless idle period
+                // in progress is cut short
+                systick.set_reload(unsafe { TICK_RELOAD });
+                systick.clear_current();
+                systick.enable_counter();
+                unsafe { TICKLESS_RESTORE = false };
+
+                if tick_update(kernel, elapsed_ticks) {
+                    SCB::set_pendsv();
+                }
+                return;
+            }
+        }
+
+        let idle_ticks = wakeup_ticks.min(max_ticks);
+
+        // Interrupts are disabled, but a pending interrupt still wakes the core
+        // and is serviced when leaving the critical section
+        if unsafe { !TICKLESS } || !kernel.is_idle() || idle_ticks < 2 {
+            cortex_m::asm::wfi();
+            return;
+        }
+
+        let mut systick = unsafe { cortex_m::Peripherals::steal() }.SYST;
+
+        // Stretch the current tick until the wake up tick
+        systick.disable_counter();
+        let tick_remaining = SYST::get_current();
+        let reload = tick_remaining + (idle_ticks as u32 - 1) * tick_cycles - 1;
+        systick.set_reload(reload);
+        systick.clear_current();
+        systick.enable_counter();
+
+        cortex_m::asm::wfi();
+
+        systick.disable_counter();
+        let elapsed_ticks = if systick.has_wrapped() {
+            // The pending tick interrupt accounts for the last tick
+            systick.set_reload(tick_cycles - 1);
+            systick.clear_current();
+            idle_ticks - 1
+        } else {
+            // Woken early by another interrupt, end the current tick on its
+            // original boundary and restore the tick period after that
+            let cycles = (reload - SYST::get_current()) + (tick_cycles - tick_remaining);
+            let next_tick_cycles = tick_cycles - (cycles % tick_cycles);
+            systick.set_reload(next_tick_cycles.max(2) - 1);
+            systick.clear_current();
+            unsafe { TICKLESS_RESTORE = true };
+            (cycles / tick_cycles) as Tick
+        };
+        systick.enable_counter();
+
+        if tick_update(kernel, elapsed_ticks) {
+            SCB::set_pendsv();
+        }
+    });
+
+    run_alarms();
 }
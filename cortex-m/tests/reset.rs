@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+#[defmt_test::tests]
+mod tests {
+    use defmt::assert_eq;
+    use rucos_cortex_m as rucos;
+    use stm32f7xx_hal as _;
+
+    fn task(_: u32) -> ! {
+        loop {}
+    }
+
+    fn task_count() -> usize {
+        let mut count = 0;
+        rucos::task_report(|_| count += 1);
+        count
+    }
+
+    #[test]
+    fn reset_removes_tasks() {
+        let mut idle_stack = [0; 512];
+        let mut task_stack = [0; 512];
+        rucos::init(&mut idle_stack, None);
+        let _ = rucos::create(0, 0, &mut task_stack, task, None);
+        rucos::sem_create(0);
+        assert_eq!(task_count(), 2);
+
+        assert_eq!(rucos::reset(), Ok(()));
+        assert_eq!(task_count(), 0);
+    }
+
+    #[test]
+    fn reset_allows_same_ids() {
+        let mut idle_stack = [0; 512];
+        let mut task_stack = [0; 512];
+        rucos::init(&mut idle_stack, None);
+
+        // The task and semaphore IDs of the previous test case are free again
+        let _ = rucos::create(0, 0, &mut task_stack, task, None);
+        rucos::sem_create(0);
+        assert_eq!(rucos::sem_get_count(0), 0);
+        assert_eq!(rucos::reset(), Ok(()));
+    }
+}
@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+#[defmt_test::tests]
+mod tests {
+    use defmt::assert_eq;
+    use rucos_cortex_m as rucos;
+    use stm32f7xx_hal as _;
+
+    #[test]
+    fn systick_reload_core_clock() {
+        assert_eq!(rucos::systick_reload(216_000_000), 215_999);
+        assert_eq!(rucos::systick_reload(16_000_000), 15_999);
+    }
+
+    #[test]
+    fn systick_reload_reference_clock() {
+        // The STM32F7 reference clock is the core clock divided by 8
+        assert_eq!(rucos::systick_reload(216_000_000 / 8), 26_999);
+        assert_eq!(rucos::systick_reload(16_000_000 / 8), 1_999);
+    }
+}
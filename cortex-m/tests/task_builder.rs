@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+#[defmt_test::tests]
+mod tests {
+    use defmt::assert_eq;
+    use rucos_cortex_m as rucos;
+    use rucos_cortex_m::{TaskBuilder, TaskInfo};
+    use stm32f7xx_hal as _;
+
+    fn task(_: u32) -> ! {
+        loop {}
+    }
+
+    fn task_info(id: usize) -> Option<TaskInfo> {
+        let mut info = None;
+        rucos::task_report(|task| {
+            if task.id == id {
+                info = Some(*task);
+            }
+        });
+
+        info
+    }
+
+    #[test]
+    fn default_builder_matches_create() {
+        let mut idle_stack = [0; 512];
+        let mut create_stack = [0; 512];
+        let mut builder_stack = [0; 512];
+        rucos::init(&mut idle_stack, None);
+
+        let created = rucos::create(0, 0, &mut create_stack, task, None);
+        let built = TaskBuilder::new(task)
+            .id(1)
+            .stack(&mut builder_stack)
+            .spawn();
+
+        let created = task_info(created.id()).unwrap();
+        let built = task_info(built.id()).unwrap();
+        assert_eq!(built, TaskInfo { id: 1, ..created });
+        assert_eq!(rucos::stack_high_water(0), rucos::stack_high_water(1));
+    }
+}
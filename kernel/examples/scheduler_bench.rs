@@ -0,0 +1,42 @@
+//! Host benchmark of the scheduler, run once with and once without the
+//! `priority-bitmap` feature to compare the two paths:
+//!
+//! cargo run --release --example scheduler_bench
+//! cargo run --release --example scheduler_bench --features priority-bitmap
+
+use rucos::Kernel;
+use std::hint::black_box;
+use std::time::Instant;
+
+const NUM_TASKS: usize = 64;
+const ITERATIONS: u32 = 1_000_000;
+
+fn main() {
+    let mut kernel: Kernel<u32, u64, NUM_TASKS> = Kernel::new();
+
+    // One task per priority level, all but the highest priority task stay
+    // ready and are visited by the linear scan
+    for id in 0..NUM_TASKS {
+        let _ = kernel.create(id, id, 0);
+    }
+    let _ = kernel.start();
+
+    // The highest priority task sleeps for a tick, so the scheduler picks the
+    // next task, and preempts it again on wake up
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        if kernel.sleep(1) {
+            black_box(kernel.handle_context_switch(Some(0)));
+        }
+        if kernel.tick_update(1) {
+            black_box(kernel.handle_context_switch(Some(0)));
+        }
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} tasks: {:.1} ns per sleep and wake up",
+        NUM_TASKS,
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS)
+    );
+}
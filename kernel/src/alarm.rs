@@ -0,0 +1,18 @@
+//! RuCOS Alarm
+
+/// One-shot alarm control block
+///
+/// # Generics
+///
+/// * `TICK`: The kernel time data type, usually a numeric type
+#[derive(Debug)]
+pub struct Alarm<TICK> {
+    /// Alarm ID
+    pub id: usize,
+    /// Tick count at which the alarm fires
+    pub expiry: TICK,
+    /// Function called with the alarm ID when the alarm fires
+    pub callback: fn(usize),
+    /// Whether the alarm has fired and waits for its callback to be taken
+    pub fired: bool,
+}
@@ -0,0 +1,136 @@
+//! RuCOS tick counter readable without a critical section
+
+use core::sync::atomic::{fence, AtomicU32, Ordering};
+
+/// 64-bit tick value written by one context and read from any context
+///
+/// # Note
+///
+/// Intended to be placed in a `static` next to the kernel, mirroring its tick
+/// counter so it can be read without a critical section, e.g. from a logging
+/// timestamp. Only loads and stores of `AtomicU32` are used, so it works on
+/// cores without 64-bit atomics or compare-and-swap. The value is kept as two
+/// words guarded by a sequence number, which is odd while a store is in
+/// progress, and a load retries until it sees the same even sequence number
+/// before and after reading the words. At any time there may be at most one
+/// context calling `store`, and a `load` must not interrupt it on the same core
+/// (e.g. `store` is called in a critical section), or the `load` never ends.
+pub struct AtomicTick {
+    /// Number of stores started and finished, odd while a store is in progress
+    seq: AtomicU32,
+    /// Low word of the value
+    low: AtomicU32,
+    /// High word of the value
+    high: AtomicU32,
+}
+
+impl AtomicTick {
+    /// Create a tick value of `0`
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+            low: AtomicU32::new(0),
+            high: AtomicU32::new(0),
+        }
+    }
+
+    /// Update the value, only called by the writer
+    ///
+    /// # Arguments
+    ///
+    /// * `tick`: New tick value
+    pub fn store(&self, tick: u64) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+
+        // Readers that see a word of the new value also see the odd sequence
+        fence(Ordering::Release);
+        self.low.store(tick as u32, Ordering::Relaxed);
+        self.high.store((tick >> 32) as u32, Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Read the value
+    ///
+    /// # Returns
+    ///
+    /// Value of the last finished `store`, never a mix of two stores
+    pub fn load(&self) -> u64 {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            let low = self.low.load(Ordering::Relaxed);
+            let high = self.high.load(Ordering::Relaxed);
+
+            // The words are read before the sequence number is checked again
+            fence(Ordering::Acquire);
+            if seq & 1 == 0 && self.seq.load(Ordering::Relaxed) == seq {
+                return (u64::from(high) << 32) | u64::from(low);
+            }
+
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl Default for AtomicTick {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_atomic_tick() {
+        let tick = AtomicTick::new();
+        assert_eq!(tick.load(), 0);
+
+        tick.store(u64::from(u32::MAX));
+        assert_eq!(tick.load(), u64::from(u32::MAX));
+        tick.store(u64::from(u32::MAX) + 1);
+        assert_eq!(tick.load(), u64::from(u32::MAX) + 1);
+        tick.store(u64::MAX);
+        assert_eq!(tick.load(), u64::MAX);
+    }
+
+    #[test]
+    fn test_atomic_tick_threads() {
+        // Both words of every stored value are equal, so a read mixing two
+        // stores is caught
+        const STEP: u64 = (1 << 32) + 1;
+        const COUNT: u64 = 1_000_000;
+        let tick = Arc::new(AtomicTick::new());
+        let done = Arc::new(AtomicBool::new(false));
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let tick = tick.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        let value = tick.load();
+                        assert_eq!(value >> 32, value & 0xFFFF_FFFF);
+                        assert!(value >= last);
+                        last = value;
+                    }
+                })
+            })
+            .collect();
+
+        for count in 1..=COUNT {
+            tick.store(count * STEP);
+        }
+        done.store(true, Ordering::Relaxed);
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(tick.load(), COUNT * STEP);
+    }
+}
@@ -0,0 +1,12 @@
+//! RuCOS Barrier
+
+/// Barrier control block
+#[derive(Debug)]
+pub struct Barrier {
+    /// Barrier ID
+    pub id: usize,
+    /// Number of tasks that must arrive to release the barrier
+    pub count: usize,
+    /// Number of tasks waiting at the barrier
+    pub waiting: usize,
+}
@@ -0,0 +1,143 @@
+//! RuCOS priority bitmap
+
+/// Number of priority levels tracked by `PriorityBitmap`, task priorities must
+/// be lower than this when the `priority-bitmap` feature is enabled
+pub const PRIORITY_LEVELS: usize = 256;
+
+/// Number of 32-bit words in the bitmap
+const NUM_WORDS: usize = PRIORITY_LEVELS / 32;
+
+/// Set of priority levels that have at least one runnable task
+///
+/// Bit `31 - (p % 32)` of word `p / 32` is set if a runnable task has priority
+/// `p`, so the highest priority is found with a leading zeros count. The number
+/// of tasks at each level is kept, so a level is only cleared once its last task
+/// is removed.
+#[derive(Debug)]
+pub struct PriorityBitmap {
+    words: [u32; NUM_WORDS],
+    counts: [u16; PRIORITY_LEVELS],
+}
+
+impl PriorityBitmap {
+    /// Create an empty bitmap
+    pub const fn new() -> Self {
+        Self {
+            words: [0; NUM_WORDS],
+            counts: [0; PRIORITY_LEVELS],
+        }
+    }
+
+    /// Add a runnable task at a priority level
+    ///
+    /// # Arguments
+    ///
+    /// * `priority`: Priority of the task
+    ///
+    /// # Panics
+    ///
+    /// The `priority` is not lower than `PRIORITY_LEVELS`
+    pub fn insert(&mut self, priority: usize) {
+        assert!(
+            priority < PRIORITY_LEVELS,
+            "Task priority exceeds PRIORITY_LEVELS"
+        );
+
+        self.counts[priority] += 1;
+        self.words[priority / 32] |= 1 << (31 - priority % 32);
+    }
+
+    /// Remove a runnable task from a priority level
+    ///
+    /// # Arguments
+    ///
+    /// * `priority`: Priority of the task
+    ///
+    /// # Panics
+    ///
+    /// There is no task at the `priority` level
+    pub fn remove(&mut self, priority: usize) {
+        assert!(
+            priority < PRIORITY_LEVELS && self.counts[priority] > 0,
+            "No task at the priority level"
+        );
+
+        self.counts[priority] -= 1;
+        if self.counts[priority] == 0 {
+            self.words[priority / 32] &= !(1 << (31 - priority % 32));
+        }
+    }
+
+    /// Get the highest priority level with a runnable task
+    ///
+    /// # Returns
+    ///
+    /// Lowest priority number in the bitmap, or `None` if it is empty
+    pub fn highest(&self) -> Option<usize> {
+        self.words
+            .iter()
+            .position(|&word| word != 0)
+            .map(|idx| idx * 32 + self.words[idx].leading_zeros() as usize)
+    }
+}
+
+impl Default for PriorityBitmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stand-in for `PriorityBitmap` when the `priority-bitmap` feature is disabled
+#[cfg(not(feature = "priority-bitmap"))]
+#[derive(Debug)]
+pub struct NoBitmap;
+
+#[cfg(not(feature = "priority-bitmap"))]
+impl NoBitmap {
+    /// Create the stand-in
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Do nothing, see `PriorityBitmap::insert`
+    pub fn insert(&mut self, _priority: usize) {}
+
+    /// Do nothing, see `PriorityBitmap::remove`
+    pub fn remove(&mut self, _priority: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitmap_highest() {
+        let mut bitmap = PriorityBitmap::new();
+        assert_eq!(bitmap.highest(), None);
+
+        bitmap.insert(200);
+        bitmap.insert(33);
+        bitmap.insert(33);
+        assert_eq!(bitmap.highest(), Some(33));
+
+        bitmap.insert(0);
+        assert_eq!(bitmap.highest(), Some(0));
+
+        // A level is only cleared once its last task is removed
+        bitmap.remove(0);
+        bitmap.remove(33);
+        assert_eq!(bitmap.highest(), Some(33));
+        bitmap.remove(33);
+        assert_eq!(bitmap.highest(), Some(200));
+
+        bitmap.remove(200);
+        assert_eq!(bitmap.highest(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Task priority exceeds PRIORITY_LEVELS")]
+    fn test_bitmap_priority_too_low() {
+        let mut bitmap = PriorityBitmap::new();
+        bitmap.insert(PRIORITY_LEVELS);
+    }
+}
@@ -0,0 +1,8 @@
+//! RuCOS Condition Variable
+
+/// Condition variable control block
+#[derive(Debug)]
+pub struct CondVar {
+    /// Condition variable ID
+    pub id: usize,
+}
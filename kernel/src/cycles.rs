@@ -0,0 +1,117 @@
+//! RuCOS cycle count measurement
+
+/// Source of a free-running 32-bit cycle count, e.g. a hardware cycle counter
+pub trait CycleCounter {
+    /// Get the current cycle count
+    ///
+    /// # Returns
+    ///
+    /// Cycle count, which wraps around on overflow
+    fn now(&self) -> u32;
+}
+
+/// Worst-case duration of an operation, in cycles
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CycleStats {
+    max: u32,
+}
+
+impl CycleStats {
+    /// Create an empty measurement
+    pub const fn new() -> Self {
+        Self { max: 0 }
+    }
+
+    /// Run an operation and record how many cycles it took
+    ///
+    /// # Arguments
+    ///
+    /// * `counter`: Source of the cycle count
+    /// * `f`: Operation to measure
+    ///
+    /// # Returns
+    ///
+    /// Value returned by `f`
+    ///
+    /// # Note
+    ///
+    /// The cycle count may wrap once during the operation
+    pub fn measure<R>(&mut self, counter: &impl CycleCounter, f: impl FnOnce() -> R) -> R {
+        let start = counter.now();
+        let result = f();
+        let cycles = counter.now().wrapping_sub(start);
+
+        self.max = self.max.max(cycles);
+        result
+    }
+
+    /// Get the largest number of cycles recorded
+    ///
+    /// # Returns
+    ///
+    /// Worst-case cycles, or `0` if nothing was measured
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Forget every measurement recorded so far
+    pub fn reset(&mut self) {
+        self.max = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Counter advancing by a fixed step each time it is read
+    struct FakeCounter {
+        count: Cell<u32>,
+        step: Cell<u32>,
+    }
+
+    impl CycleCounter for FakeCounter {
+        fn now(&self) -> u32 {
+            let count = self.count.get();
+            self.count.set(count.wrapping_add(self.step.get()));
+            count
+        }
+    }
+
+    #[test]
+    fn test_cycle_stats_max() {
+        let counter = FakeCounter {
+            count: Cell::new(0),
+            step: Cell::new(100),
+        };
+        let mut stats = CycleStats::new();
+        assert_eq!(stats.max(), 0);
+
+        assert_eq!(stats.measure(&counter, || 7), 7);
+        assert_eq!(stats.max(), 100);
+
+        counter.step.set(40);
+        stats.measure(&counter, || ());
+        assert_eq!(stats.max(), 100);
+
+        counter.step.set(250);
+        stats.measure(&counter, || ());
+        assert_eq!(stats.max(), 250);
+
+        stats.reset();
+        assert_eq!(stats.max(), 0);
+    }
+
+    #[test]
+    fn test_cycle_stats_wrap() {
+        let counter = FakeCounter {
+            count: Cell::new(u32::MAX - 10),
+            step: Cell::new(30),
+        };
+        let mut stats = CycleStats::new();
+
+        stats.measure(&counter, || ());
+        assert_eq!(stats.max(), 30);
+    }
+}
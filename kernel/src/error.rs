@@ -0,0 +1,43 @@
+//! RuCOS Errors
+
+/// Errors when creating a task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CreateError {
+    /// The task ID is not unique
+    DuplicateId,
+    /// Too many tasks have been created, more than `MAX_NUM_TASKS`
+    CapacityExceeded,
+    /// The task ID is reserved for the idle task, see `IDLE_TASK_ID`
+    ReservedId,
+    /// The task stack is too small for the initial task context, only reported
+    /// by ports since the kernel does not manage task stacks
+    StackTooSmall,
+    /// The task priority exceeds the maximum priority, see
+    /// `Kernel::set_max_priority`, or is not lower than `PRIORITY_LEVELS` with
+    /// the `priority-bitmap` feature
+    PriorityOutOfRange,
+}
+
+/// Errors when operating on a task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TaskError {
+    /// The task ID does not correspond to a task
+    NotFound,
+    /// The operation is not allowed on the idle task
+    IdleTask,
+}
+
+/// Errors when locking a mutex
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MutexError {
+    /// Blocking would never end, the chain of mutex owners leads back to the
+    /// current task
+    Deadlock,
+}
+
+/// Errors when resetting the kernel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResetError {
+    /// The kernel is running, so its tasks cannot be torn down
+    Running,
+}
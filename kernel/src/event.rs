@@ -0,0 +1,30 @@
+//! RuCOS Event Group
+
+/// Event group control block
+#[derive(Clone, Copy, Debug)]
+pub struct EventGroup {
+    /// Event group ID
+    pub id: usize,
+    /// Event flags, one bit per event
+    pub flags: u32,
+}
+
+impl EventGroup {
+    /// Check if a combination of event flags is set
+    ///
+    /// # Arguments
+    ///
+    /// * `mask`: Event flags to check
+    /// * `wait_all`: `true` if all flags in `mask` must be set, `false` if any
+    ///
+    /// # Returns
+    ///
+    /// `true` if the flags are set, `false` if not
+    pub fn is_set(&self, mask: u32, wait_all: bool) -> bool {
+        if wait_all {
+            (self.flags & mask) == mask
+        } else {
+            (self.flags & mask) != 0
+        }
+    }
+}
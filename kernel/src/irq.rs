@@ -0,0 +1,153 @@
+//! RuCOS interrupt priority helpers
+//!
+//! For interrupt controllers that keep a priority in the most significant bits
+//! of an 8-bit field, with the unimplemented low bits reading as zero (e.g. the
+//! Arm NVIC). A lower value means a more urgent interrupt.
+
+/// Get the number of implemented priority bits
+///
+/// # Arguments
+///
+/// * `readback`: Priority field read back after writing `0xFF` to it
+///
+/// # Returns
+///
+/// Number of implemented priority bits, e.g. `NVIC_PRIO_BITS`
+pub const fn irq_priority_bits(readback: u8) -> u8 {
+    readback.leading_ones() as u8
+}
+
+/// Get the priority field value of a priority level
+///
+/// # Arguments
+///
+/// * `level`: Priority level, from `0` (most urgent) to `2^prio_bits - 1`
+/// * `prio_bits`: Number of implemented priority bits
+///
+/// # Returns
+///
+/// Value to write to the priority field
+///
+/// # Panics
+///
+/// * The `prio_bits` is not between `1` and `8`
+/// * The `level` does not fit in `prio_bits`
+pub const fn irq_priority(level: u8, prio_bits: u8) -> u8 {
+    assert!(
+        prio_bits >= 1 && prio_bits <= 8,
+        "Priority bits must be between 1 and 8"
+    );
+    assert!(
+        (level as u16) < 1 << prio_bits,
+        "Priority level does not fit the priority bits"
+    );
+
+    ((level as u16) << (8 - prio_bits)) as u8
+}
+
+/// Get the least urgent priority field value
+///
+/// # Arguments
+///
+/// * `prio_bits`: Number of implemented priority bits
+///
+/// # Returns
+///
+/// Value to write to the priority field for the lowest priority
+///
+/// # Panics
+///
+/// The `prio_bits` is not between `1` and `8`
+pub const fn irq_priority_lowest(prio_bits: u8) -> u8 {
+    irq_priority(((1u16 << prio_bits) - 1) as u8, prio_bits)
+}
+
+/// Check that a priority field value is kept as is by the implemented bits
+///
+/// # Arguments
+///
+/// * `priority`: Priority field value
+/// * `prio_bits`: Number of implemented priority bits
+///
+/// # Returns
+///
+/// `true` if none of the unimplemented low bits are set, `false` if the value
+/// would be truncated to a more urgent priority
+pub const fn irq_priority_implemented(priority: u8, prio_bits: u8) -> bool {
+    prio_bits >= 8 || priority & (0xFF >> prio_bits) == 0
+}
+
+/// Check that an interrupt priority is allowed by the kernel
+///
+/// # Arguments
+///
+/// * `priority`: Priority field value of the interrupt
+/// * `ceiling`: Most urgent priority field value of interrupts that call the
+///   kernel, e.g. the `BASEPRI` value masked by the kernel
+/// * `calls_kernel`: The interrupt handler calls kernel APIs
+/// * `prio_bits`: Number of implemented priority bits
+///
+/// # Returns
+///
+/// `true` if the `priority` is kept as is by the implemented bits and, for an
+/// interrupt calling the kernel, is not more urgent than the `ceiling`
+///
+/// # Note
+///
+/// An interrupt that does not call the kernel may have any priority, including
+/// one more urgent than the `ceiling`, since the kernel never masks it
+pub const fn irq_priority_allowed(
+    priority: u8,
+    ceiling: u8,
+    calls_kernel: bool,
+    prio_bits: u8,
+) -> bool {
+    irq_priority_implemented(priority, prio_bits) && (!calls_kernel || priority >= ceiling)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_irq_priority() {
+        // An STM32F7 implements 4 bits
+        assert_eq!(irq_priority_bits(0xF0), 4);
+        assert_eq!(irq_priority(1, 4), 0x10);
+        assert_eq!(irq_priority_lowest(4), 0xF0);
+        assert!(irq_priority_implemented(0x10, 4));
+        assert!(!irq_priority_implemented(0x18, 4));
+
+        // An implementation with 3 bits drops the level of 0x10, which would
+        // become the most urgent priority
+        assert_eq!(irq_priority_bits(0xE0), 3);
+        assert_eq!(irq_priority(1, 3), 0x20);
+        assert_eq!(irq_priority_lowest(3), 0xE0);
+        assert!(!irq_priority_implemented(0x10, 3));
+
+        assert_eq!(irq_priority_bits(0xFF), 8);
+        assert_eq!(irq_priority_lowest(8), 0xFF);
+        assert!(irq_priority_implemented(0x01, 8));
+    }
+
+    #[test]
+    fn test_irq_priority_allowed() {
+        // Interrupts calling the kernel are at or below the ceiling
+        assert!(irq_priority_allowed(0x10, 0x10, true, 4));
+        assert!(irq_priority_allowed(0xF0, 0x10, true, 4));
+        assert!(!irq_priority_allowed(0x00, 0x10, true, 4));
+
+        // Other interrupts may be above it
+        assert!(irq_priority_allowed(0x00, 0x10, false, 4));
+
+        // Truncated priorities are rejected either way
+        assert!(!irq_priority_allowed(0x18, 0x10, true, 4));
+        assert!(!irq_priority_allowed(0x08, 0x10, false, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Priority level does not fit the priority bits")]
+    fn test_irq_priority_level_too_big() {
+        let _ = irq_priority(16, 4);
+    }
+}
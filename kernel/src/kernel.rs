@@ -1,12 +1,80 @@
 //! RuCOS kernel
 
-use crate::task::{Task, TaskPendReason, TaskState};
-use core::cmp::PartialOrd;
+use crate::alarm::Alarm;
+use crate::barrier::Barrier;
+use crate::condvar::CondVar;
+#[cfg(feature = "deadlock-detection")]
+use crate::error::MutexError;
+use crate::error::{CreateError, ResetError, TaskError};
+use crate::event::EventGroup;
+use crate::mailbox::Mailbox;
+use crate::mutex::Mutex;
+use crate::ready::ReadyList;
+use crate::rwlock::RwLock;
+use crate::semaphore::{Semaphore, WakeOrder};
+#[cfg(feature = "stats")]
+use crate::task::Jitter;
+use crate::task::{
+    NotifyAction, PendReason, Task, TaskInfo, TaskPendReason, TaskState, WaitResult, WakeReason,
+};
+use crate::tick::Tick;
 use core::default::Default;
-use core::fmt::Debug;
+use core::fmt::{self, Debug};
 use core::marker::Copy;
-use core::ops::{Add, AddAssign};
-use heapless::Vec;
+use heapless::{Deque, Vec};
+
+/// ID of the idle task, which cannot be deleted or suspended
+///
+/// # Note
+///
+/// This ID is reserved, tasks other than the idle task cannot be created with
+/// it, see `create_idle`
+pub const IDLE_TASK_ID: usize = usize::MAX;
+
+/// Priority of the idle task, which is excluded from round-robin scheduling
+pub const IDLE_TASK_PRIORITY: usize = usize::MAX;
+
+/// Number of task-local storage slots of each task, see `tls_set`
+pub const TLS_SLOTS: usize = 4;
+
+/// Number of read-write locks each task can hold for reading at once, see
+/// `rwlock_read_lock`
+pub const MAX_READ_LOCKS: usize = 4;
+
+/// Scheduler tie-break policies, used to select among runnable tasks with the
+/// same priority
+///
+/// The policy only applies when the current task is not one of the candidates,
+/// since a running task is never preempted by a task of the same priority
+/// outside of round-robin scheduling.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TieBreak {
+    /// Prefer the task that was created first
+    #[default]
+    ByCreationOrder,
+    /// Prefer the task with the lowest ID
+    ById,
+    /// Prefer the task that was switched in least recently
+    ByLeastRecentlyRun,
+}
+
+/// Scheduling policies
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SchedPolicy {
+    /// Tasks run at the priority they were given, the highest priority
+    /// runnable task always runs
+    #[default]
+    Priority,
+    /// Multi-level feedback queue, where the priority of a task follows its
+    /// behavior: a task that uses up its time slice is demoted by one level,
+    /// and a task that blocks is promoted by one level, up to the priority it
+    /// was given. Only tasks with a time slice are ever demoted, so tasks
+    /// without one keep strict priority scheduling.
+    Mlfq {
+        /// Number of levels a task can be demoted by below its priority
+        levels: usize,
+    },
+}
 
 /// Kernel
 ///
@@ -14,7 +82,8 @@ use heapless::Vec;
 ///
 /// * `SP`: The stack pointer type
 /// * `TICK`: The kernel time data type, usually a numeric type
-/// * `MAX_NUM_TASKS`: Upper bound on the number of tasks for the kernel
+/// * `MAX_NUM_TASKS`: Upper bound on the number of tasks for the kernel, and on
+///   the number of each kind of kernel object (e.g. semaphores)
 pub struct Kernel<SP, TICK, const MAX_NUM_TASKS: usize> {
     /// Kernel state
     is_running: bool,
@@ -22,25 +91,189 @@ pub struct Kernel<SP, TICK, const MAX_NUM_TASKS: usize> {
     tick_counter: TICK,
     /// Task list
     task_list: Vec<Task<SP, TICK>, MAX_NUM_TASKS>,
+    /// Semaphore list
+    semaphore_list: Vec<Semaphore, MAX_NUM_TASKS>,
+    /// Mutex list
+    mutex_list: Vec<Mutex, MAX_NUM_TASKS>,
+    /// Event group list
+    event_list: Vec<EventGroup, MAX_NUM_TASKS>,
+    /// Mailbox list
+    mailbox_list: Vec<Mailbox, MAX_NUM_TASKS>,
+    /// Read-write lock list
+    rwlock_list: Vec<RwLock, MAX_NUM_TASKS>,
+    /// Barrier list
+    barrier_list: Vec<Barrier, MAX_NUM_TASKS>,
+    /// Condition variable list
+    cond_list: Vec<CondVar, MAX_NUM_TASKS>,
+    /// Armed alarm list
+    alarm_list: Vec<Alarm<TICK>, MAX_NUM_TASKS>,
+    /// Deferred work items, in the order they were deferred
+    work_queue: Deque<usize, MAX_NUM_TASKS>,
     /// Current task ID
     curr_task_id: Option<usize>,
     /// Next task ID
     next_task_id: Option<usize>,
+    /// Scheduler tie-break policy
+    tie_break: TieBreak,
+    /// Scheduling policy
+    sched_policy: SchedPolicy,
+    /// Highest priority number a task may be given, if checked
+    max_priority: Option<usize>,
+    /// Number of context switches performed
+    switch_counter: u64,
+    /// Number of times a task started pending
+    pend_counter: u64,
+    /// Scheduler lock nesting count, context switches are deferred if non-zero
+    sched_lock_count: usize,
+    /// A context switch was deferred while the scheduler was locked
+    switch_deferred: bool,
+    /// Function called with the updated tick counter on every tick update
+    tick_hook: Option<fn(TICK)>,
+    /// Function called with the outgoing and incoming task IDs on every context
+    /// switch
+    switch_hook: Option<fn(Option<usize>, usize)>,
+    /// Function called when no task can run because the idle task is missing
+    no_runnable_hook: Option<fn()>,
+    /// Function called with the task ID when a task misses its deadline
+    deadline_hook: Option<fn(usize)>,
+    /// Function called with the task ID when a task misses a watchdog kick
+    watchdog_hook: Option<fn(usize)>,
+    /// Function called with the task ID after a task is deleted
+    delete_hook: Option<fn(usize)>,
+    /// Deleted task IDs and the delete hook to call with them, not taken yet
+    deleted_tasks: Deque<(usize, fn(usize)), MAX_NUM_TASKS>,
+    /// Function called with the task ID when a stack overflow is detected
+    overflow_hook: Option<fn(usize)>,
+    /// Function returning `true` if the canary of a stack region is intact
+    stack_check: Option<fn(usize, usize) -> bool>,
+    /// Function returning `true` if called from an interrupt handler
+    isr_check: Option<fn() -> bool>,
+    /// Runnable tasks, kept in step with the task list
+    ready_list: ReadyList<MAX_NUM_TASKS>,
 }
 
 impl<SP, TICK, const MAX_NUM_TASKS: usize> Kernel<SP, TICK, MAX_NUM_TASKS>
 where
     SP: Copy + Debug,
-    TICK: Add<Output = TICK> + AddAssign + Copy + Debug + Default + PartialOrd,
+    TICK: Tick,
 {
     /// Initialize the kernel
     pub fn new() -> Self {
+        Self::new_with_tie_break(TieBreak::default())
+    }
+
+    /// Initialize the kernel with a scheduler tie-break policy
+    ///
+    /// # Arguments
+    ///
+    /// * `tie_break`: Policy used to select among runnable tasks with the same
+    ///   priority
+    pub fn new_with_tie_break(tie_break: TieBreak) -> Self {
         Self {
             is_running: false,
             tick_counter: TICK::default(),
             task_list: Vec::new(),
+            semaphore_list: Vec::new(),
+            mutex_list: Vec::new(),
+            event_list: Vec::new(),
+            mailbox_list: Vec::new(),
+            rwlock_list: Vec::new(),
+            barrier_list: Vec::new(),
+            cond_list: Vec::new(),
+            alarm_list: Vec::new(),
+            work_queue: Deque::new(),
             curr_task_id: None,
             next_task_id: None,
+            tie_break,
+            sched_policy: SchedPolicy::default(),
+            max_priority: None,
+            switch_counter: 0,
+            pend_counter: 0,
+            sched_lock_count: 0,
+            switch_deferred: false,
+            tick_hook: None,
+            switch_hook: None,
+            no_runnable_hook: None,
+            deadline_hook: None,
+            watchdog_hook: None,
+            delete_hook: None,
+            deleted_tasks: Deque::new(),
+            overflow_hook: None,
+            stack_check: None,
+            isr_check: None,
+            ready_list: ReadyList::new(),
+        }
+    }
+
+    /// Set the scheduling policy
+    ///
+    /// # Arguments
+    ///
+    /// * `policy`: Scheduling policy, the default is `SchedPolicy::Priority`
+    ///
+    /// # Panics
+    ///
+    /// If called after the kernel is running
+    pub fn set_sched_policy(&mut self, policy: SchedPolicy) {
+        assert!(
+            !self.is_running,
+            "Scheduling policy must be set before the kernel starts"
+        );
+        self.sched_policy = policy;
+    }
+
+    /// Set the maximum priority number a task may be given
+    ///
+    /// # Arguments
+    ///
+    /// * `max_priority`: Highest allowed priority number, i.e. the lowest
+    ///   priority, or `None` to allow any priority (the default)
+    ///
+    /// # Panics
+    ///
+    /// The `max_priority` is not below `IDLE_TASK_PRIORITY`
+    ///
+    /// # Note
+    ///
+    /// Catches configuration errors (e.g. a mistyped priority) when a task is
+    /// created or its priority is changed, rather than creating a task that
+    /// never runs. Tasks that already exist are not checked, so this is usually
+    /// called before creating any task.
+    pub fn set_max_priority(&mut self, max_priority: Option<usize>) {
+        if let Some(max_priority) = max_priority {
+            assert!(
+                max_priority < IDLE_TASK_PRIORITY,
+                "The maximum priority must be below IDLE_TASK_PRIORITY"
+            );
+        }
+
+        self.max_priority = max_priority;
+    }
+
+    /// Create the idle task, with ID `IDLE_TASK_ID` and priority
+    /// `IDLE_TASK_PRIORITY`
+    ///
+    /// # Arguments
+    ///
+    /// * `stack_ptr`: Idle task stack pointer
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The idle task has already been created
+    /// * Too many tasks have been created, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// See `create` for stack requirements
+    pub fn create_idle(&mut self, stack_ptr: SP) -> bool {
+        match self.push_task(IDLE_TASK_ID, IDLE_TASK_PRIORITY, stack_ptr, None) {
+            Ok(switch_needed) => switch_needed,
+            Err(CreateError::DuplicateId) => panic!("The idle task already exists"),
+            Err(_) => panic!("Number of tasks exceeds MAX_NUM_TASKS"),
         }
     }
 
@@ -59,29 +292,219 @@ where
     /// # Panics
     ///
     /// * The task `id` is not unique
+    /// * The task `id` is `IDLE_TASK_ID`
     /// * Too many tasks have been created, more than `MAX_NUM_TASKS`
+    /// * The `priority` exceeds the maximum priority, see `set_max_priority`, or is
+    ///   not lower than `PRIORITY_LEVELS` with the `priority-bitmap` feature
     ///
     /// # Note
     ///
     /// The kernel does not manage the task stack, caller is responsible for
-    /// allocation and initialization of stack memory
+    /// allocation and initialization of stack memory. A task may be created
+    /// after the kernel is started, in which case a context switch is needed if
+    /// the new task has a higher priority than the current task.
     pub fn create(&mut self, id: usize, priority: usize, stack_ptr: SP) -> bool {
+        self.create_with_time_slice(id, priority, stack_ptr, None)
+    }
+
+    /// Create a task with a name
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task ID
+    /// * `priority`: Task priority, with a lower number meaning higher priority
+    /// * `stack_ptr`: Task stack pointer
+    /// * `name`: Task name for debugging
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The task `id` is not unique
+    /// * The task `id` is `IDLE_TASK_ID`
+    /// * Too many tasks have been created, more than `MAX_NUM_TASKS`
+    /// * The `priority` exceeds the maximum priority, see `set_max_priority`, or is
+    ///   not lower than `PRIORITY_LEVELS` with the `priority-bitmap` feature
+    ///
+    /// # Note
+    ///
+    /// See `create` for stack requirements
+    pub fn create_named(
+        &mut self,
+        id: usize,
+        priority: usize,
+        stack_ptr: SP,
+        name: &'static str,
+    ) -> bool {
+        let switch_needed = self.create(id, priority, stack_ptr);
+        self.set_task_name(id, name);
+        switch_needed
+    }
+
+    /// Create a task with a time slice
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task ID
+    /// * `priority`: Task priority, with a lower number meaning higher priority
+    /// * `stack_ptr`: Task stack pointer
+    /// * `time_slice`: Number of ticks the task runs before being switched out
+    ///   for a ready task of the same priority, or `None` to run until blocked
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The task `id` is not unique
+    /// * The task `id` is `IDLE_TASK_ID`
+    /// * Too many tasks have been created, more than `MAX_NUM_TASKS`
+    /// * The `priority` exceeds the maximum priority, see `set_max_priority`, or is
+    ///   not lower than `PRIORITY_LEVELS` with the `priority-bitmap` feature
+    ///
+    /// # Note
+    ///
+    /// See `create` for stack requirements
+    pub fn create_with_time_slice(
+        &mut self,
+        id: usize,
+        priority: usize,
+        stack_ptr: SP,
+        time_slice: Option<TICK>,
+    ) -> bool {
+        match self.try_create_with_time_slice(id, priority, stack_ptr, time_slice) {
+            Ok(switch_needed) => switch_needed,
+            Err(CreateError::DuplicateId) => panic!("The task ID is not unique"),
+            Err(CreateError::CapacityExceeded) => panic!("Number of tasks exceeds MAX_NUM_TASKS"),
+            Err(CreateError::ReservedId) => panic!("The task ID is reserved for the idle task"),
+            Err(CreateError::StackTooSmall) => unreachable!("The kernel does not check stacks"),
+            Err(CreateError::PriorityOutOfRange) => {
+                panic!("Task priority exceeds the maximum priority, see set_max_priority")
+            }
+        }
+    }
+
+    /// Create a task, returning an error instead of panicking
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task ID
+    /// * `priority`: Task priority, with a lower number meaning higher priority
+    /// * `stack_ptr`: Task stack pointer
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if a context switch is needed, `Ok(false)` if not, or the
+    /// reason the task could not be created
+    ///
+    /// # Note
+    ///
+    /// See `create` for stack requirements
+    pub fn try_create(
+        &mut self,
+        id: usize,
+        priority: usize,
+        stack_ptr: SP,
+    ) -> Result<bool, CreateError> {
+        self.try_create_with_time_slice(id, priority, stack_ptr, None)
+    }
+
+    /// Create a task with a time slice, returning an error instead of panicking
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task ID
+    /// * `priority`: Task priority, with a lower number meaning higher priority
+    /// * `stack_ptr`: Task stack pointer
+    /// * `time_slice`: Number of ticks the task runs before being switched out
+    ///   for a ready task of the same priority, or `None` to run until blocked
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if a context switch is needed, `Ok(false)` if not, or the
+    /// reason the task could not be created
+    ///
+    /// # Note
+    ///
+    /// See `create` for stack requirements
+    pub fn try_create_with_time_slice(
+        &mut self,
+        id: usize,
+        priority: usize,
+        stack_ptr: SP,
+        time_slice: Option<TICK>,
+    ) -> Result<bool, CreateError> {
+        if id == IDLE_TASK_ID {
+            return Err(CreateError::ReservedId);
+        }
+
+        if !self.priority_in_range(priority) {
+            return Err(CreateError::PriorityOutOfRange);
+        }
+
+        self.push_task(id, priority, stack_ptr, time_slice)
+    }
+
+    /// Add a task to the task list and run the scheduler
+    fn push_task(
+        &mut self,
+        id: usize,
+        priority: usize,
+        stack_ptr: SP,
+        time_slice: Option<TICK>,
+    ) -> Result<bool, CreateError> {
         // Ensure the task ID is unique
-        for task in self.task_list.iter() {
-            assert!(task.id != id, "The task ID is not unique");
+        if self.task_list.iter().any(|t| t.id == id) {
+            return Err(CreateError::DuplicateId);
         }
 
         self.task_list
             .push(Task {
                 id,
+                name: None,
                 priority,
+                base_priority: priority,
                 stack_ptr,
                 state: TaskState::Ready,
                 pend: TaskPendReason::NotPending,
+                last_run: 0,
+                time_slice,
+                slice_end: TICK::default(),
+                timed_out: false,
+                wait_result: WaitResult::Acquired,
+                pend_seq: 0,
+                mlfq_level: 0,
+                stack_region: None,
+                entry: None,
+                uses_fpu: false,
+                stack_watch: false,
+                run_ticks: TICK::default(),
+                deadline: None,
+                release: None,
+                #[cfg(feature = "stats")]
+                jitter_release: None,
+                #[cfg(feature = "stats")]
+                jitter: None,
+                missed_deadlines: 0,
+                watchdog: None,
+                last_kick: TICK::default(),
+                message: None,
+                notification: None,
+                notify_action: NotifyAction::default(),
+                pre_suspend: None,
+                suspended_wait: None,
+                tls: [core::ptr::null_mut(); TLS_SLOTS],
+                read_locks: Vec::new(),
             })
-            .expect("Number of tasks exceeds MAX_NUM_TASKS");
+            .map_err(|_| CreateError::CapacityExceeded)?;
 
-        self.scheduler()
+        let task_idx = self.task_list.len() - 1;
+        self.ready_list.push(task_idx, &self.task_list[task_idx]);
+
+        Ok(self.scheduler())
     }
 
     /// Delete a task
@@ -97,7 +520,13 @@ where
     /// # Panics
     ///
     /// * The `id` provided does not correspond to a task
+    /// * The task is the idle task, which cannot be deleted
     /// * If called before the kernel is running
+    ///
+    /// # Note
+    ///
+    /// Mutexes and read-write locks held by the task are released, each is
+    /// handed to the highest priority task waiting on it, if any
     pub fn delete(&mut self, id: Option<usize>) -> bool {
         let curr_task_idx = self.find_task_idx(self.curr_task_id.expect("Kernel not running"));
         let task_idx = match id {
@@ -105,87 +534,133 @@ where
             None => curr_task_idx,
         };
 
-        self.task_list.remove(task_idx);
+        assert!(
+            self.task_list[task_idx].id != IDLE_TASK_ID,
+            "The idle task cannot be deleted"
+        );
+
+        let task = self.task_list.remove(task_idx);
+        self.ready_list.remove(task_idx, &task);
 
         if curr_task_idx == task_idx {
             self.curr_task_id = None;
         }
 
-        self.scheduler()
-    }
+        // A mutex held by the task would otherwise stay locked by a task that
+        // no longer exists
+        for idx in 0..self.mutex_list.len() {
+            if self.mutex_list[idx].owner == Some(task.id) {
+                let mutex_id = self.mutex_list[idx].id;
+                self.release_mutex(mutex_id, None);
+            }
+        }
 
-    /// Start the kernel
-    ///
-    /// # Returns
-    ///
-    /// Stack pointer for the first task to run
-    ///
-    /// # Panics
-    ///
-    /// * No tasks have been created
-    /// * The kernel is already running
-    pub fn start(&mut self) -> SP {
-        assert!(!self.is_running, "Kernel already running");
+        self.release_rwlocks(task.id, &task.read_locks);
 
-        self.is_running = true;
+        // The task might have been boosting the priority of a mutex owner
+        if let TaskPendReason::MutexWait(..) = task.pend {
+            self.update_mutex_owner_priorities();
+        }
 
-        if self.scheduler() == true {
-            self.handle_context_switch(None)
-        } else {
-            panic!("No tasks created")
+        // The task will never arrive at the barrier it was waiting at again, a
+        // suspended task was already not counted as waiting
+        if let TaskPendReason::BarrierWait(barrier_id) = task.pend {
+            let barrier = self.find_barrier(barrier_id);
+            barrier.waiting -= 1;
+            barrier.count -= 1;
+        }
+
+        let suspended_wait = task
+            .suspended_wait
+            .or(task.pre_suspend.map(|(_, pend)| pend));
+        if let Some(TaskPendReason::BarrierWait(barrier_id)) = suspended_wait {
+            self.find_barrier(barrier_id).count -= 1;
+        }
+
+        // Wake up all tasks waiting for the task to be deleted
+        for (idx, joiner) in self.task_list.iter_mut().enumerate() {
+            if let TaskPendReason::Join(id) = joiner.pend {
+                if id == task.id {
+                    self.ready_list.set_ready(idx, joiner);
+                }
+            }
+        }
+
+        // The hook is called once the kernel is no longer borrowed
+        if let Some(hook) = self.delete_hook {
+            self.deleted_tasks
+                .push_back((task.id, hook))
+                .expect("Number of deleted tasks not taken exceeds MAX_NUM_TASKS");
         }
+
+        self.scheduler()
     }
 
-    /// Get the ID of the current task
+    /// Delete a task, returning an error instead of panicking
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to delete or `None` to delete the current task
     ///
     /// # Returns
     ///
-    /// ID of the current task
+    /// `Ok(true)` if a context switch is needed, `Ok(false)` if not,
+    /// `Err(TaskError::NotFound)` if the `id` does not correspond to a task, or
+    /// `Err(TaskError::IdleTask)` if the task is the idle task
     ///
     /// # Panics
     ///
     /// If called before the kernel is running
-    pub fn get_current_task(&self) -> usize {
-        self.curr_task_id.expect("Kernel not running")
+    pub fn try_delete(&mut self, id: Option<usize>) -> Result<bool, TaskError> {
+        self.check_task_exists(id)?;
+        self.check_not_idle_task(id)?;
+        Ok(self.delete(id))
     }
 
-    /// Get the value of the global tick counter
+    /// Set the task delete hook
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// Current value of the global tick counter
-    pub fn get_current_tick(&self) -> TICK {
-        self.tick_counter
-    }
-
-    /// Sleep the current task
+    /// * `hook`: Function called with the task ID after a task is deleted, or
+    ///   `None` to remove the hook
     ///
-    /// # Arguments
+    /// # Note
     ///
-    /// * `delay`: Number of ticks to sleep
+    /// The hook is not called by the kernel, each deleted task is kept with the
+    /// hook until taken with `take_deleted_task`, so the caller can run it once
+    /// the kernel is no longer borrowed. The task no longer exists, so its ID
+    /// may already be reused by then.
+    pub fn set_delete_hook(&mut self, hook: Option<fn(usize)>) {
+        self.delete_hook = hook;
+    }
+
+    /// Take a deleted task whose delete hook has not been called yet
     ///
     /// # Returns
     ///
-    /// `true` if a context switch is needed, `false` if not
+    /// ID of the deleted task and the delete hook set when it was deleted, or
+    /// `None` if there is none, in the order the tasks were deleted
     ///
     /// # Panics
     ///
-    /// If called before the kernel is running
-    pub fn sleep(&mut self, delay: TICK) -> bool {
-        let new_tick_counter = self.tick_counter + delay;
-        let curr_task = self.find_task(self.curr_task_id.expect("Kernel not running"));
-
-        curr_task.state = TaskState::Pending;
-        curr_task.pend = TaskPendReason::Sleep(new_tick_counter);
-
-        self.scheduler()
+    /// `delete` panics if more than `MAX_NUM_TASKS` deleted tasks have not been
+    /// taken
+    ///
+    /// # Note
+    ///
+    /// Intended to be called after `delete`, until it returns `None`. The hook
+    /// is meant to be called once the kernel is no longer borrowed, so it can
+    /// call kernel APIs (e.g. give a semaphore).
+    pub fn take_deleted_task(&mut self) -> Option<(usize, fn(usize))> {
+        self.deleted_tasks.pop_front()
     }
 
-    /// Suspend a task
+    /// Restart a task from its entry point
     ///
     /// # Arguments
     ///
-    /// * `id`: Task to suspend or `None` to suspend the current task
+    /// * `id`: Task to restart
+    /// * `stack_ptr`: Stack pointer for the re-initialized stack of the task
     ///
     /// # Returns
     ///
@@ -194,288 +669,6916 @@ where
     /// # Panics
     ///
     /// * The `id` provided does not correspond to a task
-    /// * If called before the kernel is running
-    pub fn suspend(&mut self, id: Option<usize>) -> bool {
-        let task: &mut Task<SP, TICK> = match id {
-            Some(id) => self.find_task(id),
-            None => {
-                let curr_task_id = self.curr_task_id.expect("Kernel not running");
-                self.find_task(curr_task_id)
-            }
-        };
+    /// * The task owns a mutex
+    ///
+    /// # Note
+    ///
+    /// The port is responsible for re-initializing the stack of the task. The
+    /// task becomes ready with its base priority, and read-write locks it holds
+    /// are released. If the task is the current task, its context is discarded
+    /// rather than saved on the next context switch.
+    pub fn restart(&mut self, id: usize, stack_ptr: SP) -> bool {
+        assert!(
+            !self.mutex_list.iter().any(|m| m.owner == Some(id)),
+            "A task owning a mutex cannot be restarted"
+        );
+
+        let task = self.find_task(id);
+        let was_mutex_wait = matches!(task.pend, TaskPendReason::MutexWait(..));
+
+        // The task starts over, so it has not arrived at the barrier yet
+        if let TaskPendReason::BarrierWait(barrier_id) = task.pend {
+            self.find_barrier(barrier_id).waiting -= 1;
+        }
+
+        let tick_counter = self.tick_counter;
+        let task_idx = self.find_task_idx(id);
+        let task = &mut self.task_list[task_idx];
+        let base_priority = task.base_priority;
+
+        self.ready_list.set_priority(task, base_priority);
+        self.ready_list.set_ready(task_idx, task);
+        task.mlfq_level = 0;
+        task.timed_out = false;
+        task.wait_result = WaitResult::Acquired;
+        task.stack_ptr = stack_ptr;
+        task.uses_fpu = false;
+        task.release = None;
+        #[cfg(feature = "stats")]
+        {
+            task.jitter_release = None;
+            task.jitter = None;
+        }
+        task.last_kick = tick_counter;
+        task.message = None;
+        task.notification = None;
+        task.tls = [core::ptr::null_mut(); TLS_SLOTS];
+        task.pre_suspend = None;
+        task.suspended_wait = None;
+        let read_locks = core::mem::take(&mut task.read_locks);
+
+        if self.curr_task_id == Some(id) {
+            self.curr_task_id = None;
+        }
 
-        task.state = TaskState::Pending;
-        task.pend = TaskPendReason::Suspended;
+        self.release_rwlocks(id, &read_locks);
+
+        // The task might have been boosting the priority of a mutex owner
+        if was_mutex_wait {
+            self.update_mutex_owner_priorities();
+        }
 
         self.scheduler()
     }
 
-    /// Resume a task
-    ///
-    /// # Arguments
-    ///
-    /// * `id`: Task to resume
+    /// Start the kernel
     ///
     /// # Returns
     ///
-    /// `true` if a context switch is needed, `false` if not
+    /// Stack pointer for the first task to run
     ///
     /// # Panics
     ///
-    /// The `id` provided does not correspond to a task
-    pub fn resume(&mut self, id: usize) -> bool {
-        let task: &mut Task<SP, TICK> = self.find_task(id);
+    /// * No tasks have been created, other than the idle task
+    /// * The kernel is already running
+    pub fn start(&mut self) -> SP {
+        assert!(!self.is_running, "Kernel already running");
+        assert!(
+            self.task_list.iter().any(|t| t.id != IDLE_TASK_ID),
+            "No tasks created other than the idle task"
+        );
 
-        task.state = TaskState::Ready;
-        task.pend = TaskPendReason::NotPending;
+        self.is_running = true;
 
-        self.scheduler()
+        if self.scheduler() {
+            self.handle_context_switch(None)
+        } else {
+            panic!("No tasks created")
+        }
     }
 
-    /// Update the global tick counter
+    /// Reset the kernel to the state it had when it was initialized
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `elapsed`: Number of ticks that have passed since last call
+    /// `Ok(())` on success, or `Err(ResetError::Running)` if the kernel is
+    /// running
     ///
-    /// # Returns
+    /// # Note
     ///
-    /// `true` if a context switch is needed, `false` if not
-    pub fn tick_update(&mut self, elapsed: TICK) -> bool {
-        self.tick_counter += elapsed;
+    /// Intended for test harnesses that set up the kernel repeatedly. All tasks
+    /// (including the idle task) and kernel objects are removed, and all hooks
+    /// are cleared. The tie-break policy is kept, the scheduling policy is not.
+    pub fn reset(&mut self) -> Result<(), ResetError> {
+        if self.is_running {
+            return Err(ResetError::Running);
+        }
 
-        self.scheduler()
+        *self = Self::new_with_tie_break(self.tie_break);
+        Ok(())
     }
 
-    /// Handle a context switch
+    /// Get the ID of the current task
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `updated_stack_ptr`: The updated stack pointer for the current task or
-    ///   `None` if there is no current task
+    /// ID of the current task
+    ///
+    /// # Panics
+    ///
+    /// If called before the kernel is running
+    pub fn get_current_task(&self) -> usize {
+        self.curr_task_id.expect("Kernel not running")
+    }
+
+    /// Check if a task is the current task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
     ///
     /// # Returns
     ///
-    /// The stack pointer for the next task
+    /// `true` if the task is running, `false` if another task is running or
+    /// the kernel is not running
+    pub fn is_current_task(&self, id: usize) -> bool {
+        self.curr_task_id == Some(id)
+    }
+
+    /// Get the state of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// State of the task, or `None` if the `id` does not correspond to a task
+    pub fn get_task_state(&self, id: usize) -> Option<TaskState> {
+        self.task_list.iter().find(|t| t.id == id).map(|t| t.state)
+    }
+
+    /// Get the reason a task is pending
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Pend reason of the task, or `None` if the `id` does not correspond to a
+    /// task
+    pub fn get_pend_reason(&self, id: usize) -> Option<PendReason> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.pend.reason())
+    }
+
+    /// Get the number of tasks
+    ///
+    /// # Returns
+    ///
+    /// Number of tasks, including the idle task if it has been created
+    pub fn task_count(&self) -> usize {
+        self.task_list.len()
+    }
+
+    /// Get the number of runnable tasks
+    ///
+    /// # Returns
+    ///
+    /// Number of tasks that are ready or running, not including the idle task
+    ///
+    /// # Note
+    ///
+    /// Sampled periodically, this gives a simple measure of contention for the
+    /// CPU
+    pub fn ready_count(&self) -> usize {
+        self.task_list
+            .iter()
+            .filter(|t| t.id != IDLE_TASK_ID && t.is_runnable())
+            .count()
+    }
+
+    /// Visit a snapshot of every task
+    ///
+    /// # Arguments
+    ///
+    /// * `f`: Function called with the snapshot of each task
+    ///
+    /// # Note
+    ///
+    /// Tasks are visited in creation order, except that deleting a task moves
+    /// later tasks up
+    pub fn for_each_task(&self, mut f: impl FnMut(&TaskInfo)) {
+        for task in self.task_list.iter() {
+            f(&task.info());
+        }
+    }
+
+    /// Format the state of every task for a post-mortem, e.g. from a panic
+    /// handler
+    ///
+    /// # Arguments
+    ///
+    /// * `w`: Writer to format the task state into
+    /// * `stack_high_water`: Function returning the peak stack usage in bytes of
+    ///   a task, or `None` if unknown
+    ///
+    /// # Returns
+    ///
+    /// Result of writing to `w`
+    ///
+    /// # Note
+    ///
+    /// Each task is written on its own line, in the order of `for_each_task`
+    pub fn dump_state(
+        &self,
+        w: &mut impl fmt::Write,
+        mut stack_high_water: impl FnMut(usize) -> Option<usize>,
+    ) -> fmt::Result {
+        for task in self.task_list.iter() {
+            let info = task.info();
+            write!(
+                w,
+                "task {} priority {} state {:?} pend {:?} stack ",
+                info.id, info.priority, info.state, info.pend
+            )?;
+
+            match stack_high_water(info.id) {
+                Some(bytes) => writeln!(w, "{}", bytes)?,
+                None => writeln!(w, "unknown")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Name a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `name`: Task name for debugging
     ///
     /// # Panics
     ///
-    /// If called when a context switch is not necessary
-    pub fn handle_context_switch(&mut self, updated_stack_ptr: Option<SP>) -> SP {
-        // Update current task
-        match self.curr_task_id {
-            Some(curr_task_id) => {
-                let curr_task = self.find_task(curr_task_id);
+    /// The `id` provided does not correspond to a task
+    pub fn set_task_name(&mut self, id: usize, name: &'static str) {
+        self.find_task(id).name = Some(name);
+    }
 
-                match updated_stack_ptr {
-                    Some(sp) => curr_task.stack_ptr = sp,
-                    None => (),
-                };
+    /// Get the name of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Name of the task, or `None` if the `id` does not correspond to a task or
+    /// the task is unnamed
+    pub fn get_task_name(&self, id: usize) -> Option<&'static str> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .and_then(|t| t.name)
+    }
 
-                curr_task.state = match curr_task.state {
-                    TaskState::Running => TaskState::Ready,
-                    _ => curr_task.state,
-                };
+    /// Record the stack memory of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `bottom`: Lowest address of the task stack
+    /// * `size`: Size of the task stack in bytes
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// The kernel does not access the stack, the region is stored for the port
+    /// (e.g. to check for stack overflow)
+    pub fn set_stack_region(&mut self, id: usize, bottom: usize, size: usize) {
+        self.find_task(id).stack_region = Some((bottom, size));
+    }
+
+    /// Get the stack memory of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Lowest address and size in bytes of the task stack, or `None` if the `id`
+    /// does not correspond to a task or its stack region was never recorded
+    pub fn get_stack_region(&self, id: usize) -> Option<(usize, usize)> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .and_then(|t| t.stack_region)
+    }
+
+    /// Record the entry function of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `entry`: Address of the task entry function
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// The kernel does not call the entry function, the address is stored for
+    /// the port (e.g. to restart the task)
+    pub fn set_task_entry(&mut self, id: usize, entry: usize) {
+        self.find_task(id).entry = Some(entry);
+    }
+
+    /// Get the entry function of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Address of the task entry function, or `None` if the `id` does not
+    /// correspond to a task or its entry function was never recorded
+    pub fn get_task_entry(&self, id: usize) -> Option<usize> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .and_then(|t| t.entry)
+    }
+
+    /// Set a task-local storage slot of the current task
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: Slot to set, lower than `TLS_SLOTS`
+    /// * `value`: Pointer to store in the slot
+    ///
+    /// # Panics
+    ///
+    /// * The `index` is not lower than `TLS_SLOTS`
+    /// * If called before the kernel is running
+    ///
+    /// # Note
+    ///
+    /// The kernel does not access the pointer. Slots start out null and are
+    /// cleared when the task is restarted, a new task with the ID of a deleted
+    /// task does not see its slots.
+    pub fn tls_set(&mut self, index: usize, value: *mut ()) {
+        assert!(index < TLS_SLOTS, "TLS slot index exceeds TLS_SLOTS");
+
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        self.find_task(curr_task_id).tls[index] = value;
+    }
+
+    /// Get a task-local storage slot of the current task, see `tls_set`
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: Slot to get, lower than `TLS_SLOTS`
+    ///
+    /// # Returns
+    ///
+    /// Pointer stored in the slot, or null if it was never set
+    ///
+    /// # Panics
+    ///
+    /// * The `index` is not lower than `TLS_SLOTS`
+    /// * If called before the kernel is running
+    pub fn tls_get(&self, index: usize) -> *mut () {
+        assert!(index < TLS_SLOTS, "TLS slot index exceeds TLS_SLOTS");
+
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        self.task_list
+            .iter()
+            .find(|t| t.id == curr_task_id)
+            .expect("Task does not exist")
+            .tls[index]
+    }
+
+    /// Record that the current task has used the floating point unit
+    ///
+    /// # Note
+    ///
+    /// Intended for the port, which can detect floating point use when saving
+    /// the context of a task. Does nothing if there is no current task.
+    pub fn mark_fpu_used(&mut self) {
+        if let Some(curr_task_id) = self.curr_task_id {
+            self.find_task(curr_task_id).uses_fpu = true;
+        }
+    }
+
+    /// Check if a task has used the floating point unit
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// `true` if the task has used the floating point unit, `false` if not, or
+    /// `None` if the `id` does not correspond to a task
+    pub fn task_uses_fpu(&self, id: usize) -> Option<bool> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.uses_fpu)
+    }
+
+    /// Set if the stack limit of a task is watched
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `watch`: `true` to watch the task stack limit while the task is running
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// Intended for the port, which programs the watchpoint hardware for the
+    /// current task on every context switch. Kept when the task is restarted.
+    pub fn set_stack_watch(&mut self, id: usize, watch: bool) {
+        self.find_task(id).stack_watch = watch;
+    }
+
+    /// Check if the stack limit of a task is watched
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// `true` if the task stack limit is watched while the task is running,
+    /// `false` if not, or `None` if the `id` does not correspond to a task
+    pub fn task_stack_watch(&self, id: usize) -> Option<bool> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.stack_watch)
+    }
+
+    /// Get the CPU usage of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Number of ticks that elapsed while the task was running and the value of
+    /// the global tick counter, or `None` if the `id` does not correspond to a
+    /// task
+    ///
+    /// # Note
+    ///
+    /// Each tick is counted against the task running when it elapses, which is
+    /// the same as adding the ticks since the last context switch to the task
+    /// switched out. The resolution is one tick: a task that runs for part of a
+    /// tick and blocks is not counted, so many short bursts are under-reported.
+    /// The idle task is included, so the idle percentage is
+    /// `run_ticks / total_ticks` for `IDLE_TASK_ID`.
+    pub fn cpu_usage(&self, id: usize) -> Option<(TICK, TICK)> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| (t.run_ticks, self.tick_counter))
+    }
+
+    /// Check if the idle task is the only task that can run
+    ///
+    /// # Returns
+    ///
+    /// `true` if the current task is the idle task and no context switch is
+    /// pending, `false` if not
+    pub fn is_idle(&self) -> bool {
+        self.next_task_id.is_none() && self.curr_task_id == Some(IDLE_TASK_ID)
+    }
+
+    /// Check if a context switch has been requested but not yet performed
+    ///
+    /// # Returns
+    ///
+    /// `true` if the scheduler selected a task to switch to, `false` if not
+    ///
+    /// # Note
+    ///
+    /// A context switch deferred while the scheduler is locked is not pending
+    /// until the scheduler is unlocked, see `scheduler_lock`
+    pub fn is_switch_pending(&self) -> bool {
+        self.next_task_id.is_some()
+    }
+
+    /// Get the earliest tick at which a pending task wakes up on its own
+    ///
+    /// # Returns
+    ///
+    /// Earliest wake up or wait timeout tick among pending tasks, or alarm
+    /// expiry tick, or `None` if no task is waiting with a timeout and no alarm
+    /// is armed
+    ///
+    /// # Note
+    ///
+    /// Intended for tickless idle, the tick interrupt can be suppressed until
+    /// this tick as long as no interrupt handler makes a task ready
+    pub fn get_next_wakeup(&self) -> Option<TICK> {
+        let mut next_wakeup: Option<TICK> = None;
+        let timeouts = self.task_list.iter().filter_map(|t| t.pend.timeout());
+        let expiries = self
+            .alarm_list
+            .iter()
+            .filter(|a| !a.fired)
+            .map(|a| a.expiry);
+        for timeout in timeouts.chain(expiries) {
+            if next_wakeup.is_none_or(|next| !timeout.wrapping_ge(next)) {
+                next_wakeup = Some(timeout);
             }
-            None => (),
         }
 
-        // Update kernel
-        let next_task_id = self.next_task_id.expect("No context switch required");
-        self.curr_task_id = Some(next_task_id);
-        self.next_task_id = None;
+        next_wakeup
+    }
+
+    /// Get the value of the global tick counter
+    ///
+    /// # Returns
+    ///
+    /// Current value of the global tick counter
+    pub fn get_current_tick(&self) -> TICK {
+        self.tick_counter
+    }
+
+    /// Sleep the current task
+    ///
+    /// # Arguments
+    ///
+    /// * `delay`: Number of ticks to sleep
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// A `delay` of `0` does not pend the task, this behaves like `yield_task`
+    /// instead. The wake up tick may be past a wrap of the tick counter, as long
+    /// as `delay` is less than half the tick range, see `Tick::wrapping_ge`. A
+    /// `delay` of `Tick::MAX` is the exception, see `sleep_forever`.
+    pub fn sleep(&mut self, delay: TICK) -> bool {
+        if delay == TICK::MAX {
+            return self.sleep_forever();
+        }
+
+        let new_tick_counter = self.tick_counter.wrapping_add(delay);
+        let curr_task_id = self.blocking_task_id();
+        self.check_deadline(curr_task_id, new_tick_counter);
+
+        if self.tick_counter.wrapping_ge(new_tick_counter) {
+            return self.yield_task();
+        }
+
+        #[cfg(feature = "stats")]
+        {
+            self.find_task(curr_task_id).jitter_release = Some(new_tick_counter);
+        }
+        self.pend_task(curr_task_id, TaskPendReason::Sleep(Some(new_tick_counter)));
+
+        self.scheduler()
+    }
+
+    /// Sleep the current task until an absolute tick count
+    ///
+    /// # Arguments
+    ///
+    /// * `wakeup_tick`: Value of the global tick counter to wake up at
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// If `wakeup_tick` has already been reached the task does not sleep, and
+    /// this behaves like `yield_task` instead. Unlike
+    /// `sleep`, the wake up time does not depend on when this is called, so a
+    /// periodic task can avoid drift by advancing `wakeup_tick` by its period.
+    pub fn sleep_until(&mut self, wakeup_tick: TICK) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        self.check_deadline(curr_task_id, wakeup_tick);
+
+        if self.tick_counter.wrapping_ge(wakeup_tick) {
+            return self.yield_task();
+        }
+
+        #[cfg(feature = "stats")]
+        {
+            self.find_task(curr_task_id).jitter_release = Some(wakeup_tick);
+        }
+        self.pend_task(curr_task_id, TaskPendReason::Sleep(Some(wakeup_tick)));
+
+        self.scheduler()
+    }
+
+    /// Sleep the current task until it is woken up
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// The tick never wakes the task, only `wake` does. Intended for a task
+    /// waiting to be signaled, leaving `suspend` and `resume` for external
+    /// control.
+    pub fn sleep_forever(&mut self) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        self.pend_task(curr_task_id, TaskPendReason::Sleep(None));
+
+        self.scheduler()
+    }
+
+    /// Change the time slice of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `time_slice`: Number of ticks the task runs before being switched out
+    ///   for another ready task with the same priority, or `None` to run until
+    ///   blocked
+    /// * `restart`: `true` to start a new time slice of the new length now,
+    ///   `false` to let the current time slice run out first
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// Only matters for the current task, any other task starts a new time
+    /// slice of the new length when it is next switched in. A task that had no
+    /// time slice always starts a new one.
+    pub fn set_time_slice(&mut self, id: usize, time_slice: Option<TICK>, restart: bool) {
+        let tick_counter = self.tick_counter;
+        let task = self.find_task(id);
+        if let Some(slice) = time_slice {
+            if restart || task.time_slice.is_none() {
+                task.slice_end = tick_counter.wrapping_add(slice);
+            }
+        }
+
+        task.time_slice = time_slice;
+    }
+
+    /// Set the deadline of a periodic task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `deadline`: Number of ticks after each wake up tick by which the task
+    ///   must call `sleep` or `sleep_until` again, or `None` to stop checking
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// The wake up tick is the one the task asked for, not when it actually ran,
+    /// so time spent waiting for higher priority tasks counts against the
+    /// deadline. The first period is not checked, since there is no wake up tick
+    /// before the task first sleeps.
+    pub fn set_deadline(&mut self, id: usize, deadline: Option<TICK>) {
+        self.find_task(id).deadline = deadline;
+    }
+
+    /// Get the number of deadlines a task has missed
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Number of missed deadlines, or `None` if the `id` does not correspond to
+    /// a task
+    pub fn missed_deadlines(&self, id: usize) -> Option<u64> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.missed_deadlines)
+    }
+
+    /// Get the jitter of a periodic task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Smallest, largest and latest number of ticks between the tick a task
+    /// asked to wake up at and the tick it started running again, or `None` if
+    /// the `id` does not correspond to a task or nothing was measured yet
+    ///
+    /// # Note
+    ///
+    /// Requires the `stats` feature. Measured for every wake up from `sleep`
+    /// and `sleep_until` once the task is switched in, so time spent waiting
+    /// for higher priority tasks counts as jitter. A sleep whose wake up tick
+    /// already passed and a task woken early are not measured.
+    #[cfg(feature = "stats")]
+    pub fn periodic_jitter(&self, id: usize) -> Option<Jitter<TICK>> {
+        self.task_list
+            .iter()
+            .find(|t| t.id == id)
+            .and_then(|t| t.jitter)
+    }
+
+    /// Set the deadline miss hook
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: Function called with the task ID when a task misses its
+    ///   deadline, or `None` to remove the hook
+    ///
+    /// # Note
+    ///
+    /// The hook runs in the context of the task that missed its deadline, from
+    /// within `sleep` or `sleep_until`
+    pub fn set_deadline_hook(&mut self, hook: Option<fn(usize)>) {
+        self.deadline_hook = hook;
+    }
+
+    /// Set the software watchdog of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `timeout`: Number of ticks within which the task must call `task_kick`,
+    ///   or `None` to stop checking
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// Usually called right after creating the task. The watchdog is armed at
+    /// the current tick, and restarting the task arms it again. Watchdogs are
+    /// checked on every call to `tick_update`, see `set_watchdog_hook`.
+    pub fn set_watchdog(&mut self, id: usize, timeout: Option<TICK>) {
+        let tick_counter = self.tick_counter;
+        let task = self.find_task(id);
+        task.watchdog = timeout;
+        task.last_kick = tick_counter;
+    }
+
+    /// Kick the software watchdog of a task, see `set_watchdog`
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task checking in
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    pub fn task_kick(&mut self, id: usize) {
+        let tick_counter = self.tick_counter;
+        self.find_task(id).last_kick = tick_counter;
+    }
+
+    /// Set the watchdog fault hook
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: Function called with the task ID when a task has not called
+    ///   `task_kick` within its watchdog timeout, or `None` to remove the hook
+    ///
+    /// # Note
+    ///
+    /// The hook runs from within `tick_update`, usually in interrupt context,
+    /// and would typically reset the system. If it returns, the watchdog of the
+    /// task is armed again, so the hook is called once per missed timeout.
+    pub fn set_watchdog_hook(&mut self, hook: Option<fn(usize)>) {
+        self.watchdog_hook = hook;
+    }
+
+    /// Yield the CPU to another ready task of the same priority
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Note
+    ///
+    /// The current task stays ready and resumes once its peers have run. If no
+    /// other task of the same priority is ready, this does nothing.
+    pub fn yield_task(&mut self) -> bool {
+        self.schedule(true)
+    }
+
+    /// Suspend a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to suspend or `None` to suspend the current task
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a task
+    /// * The task is the idle task, which cannot be suspended
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// A task blocked on a kernel object stops waiting until it is resumed, see
+    /// `resume`
+    pub fn suspend(&mut self, id: Option<usize>) -> bool {
+        let task: &mut Task<SP, TICK> = match id {
+            Some(id) => self.find_task(id),
+            None => {
+                let curr_task_id = self.blocking_task_id();
+                self.find_task(curr_task_id)
+            }
+        };
+
+        assert!(task.id != IDLE_TASK_ID, "The idle task cannot be suspended");
+
+        let id = task.id;
+        let wait = match task.pend {
+            TaskPendReason::NotPending | TaskPendReason::Suspended => None,
+            pend => Some(pend),
+        };
+
+        self.pend_task(id, TaskPendReason::Suspended);
+        if let Some(wait) = wait {
+            self.find_task(id).suspended_wait = Some(wait);
+            self.pause_wait(wait);
+        }
+
+        self.scheduler()
+    }
+
+    /// Suspend a task, returning an error instead of panicking
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to suspend or `None` to suspend the current task
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if a context switch is needed, `Ok(false)` if not,
+    /// `Err(TaskError::NotFound)` if the `id` does not correspond to a task, or
+    /// `Err(TaskError::IdleTask)` if the task is the idle task
+    ///
+    /// # Panics
+    ///
+    /// If called before the kernel is running
+    pub fn try_suspend(&mut self, id: Option<usize>) -> Result<bool, TaskError> {
+        self.check_task_exists(id)?;
+        self.check_not_idle_task(id)?;
+        Ok(self.suspend(id))
+    }
+
+    /// Resume a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to resume
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// A task that was blocked on a kernel object when it was suspended goes
+    /// back to waiting on it, and takes it right away if it became available in
+    /// the meantime. A timeout that expired in the meantime ends the wait right
+    /// away. Does nothing if the task is not suspended.
+    pub fn resume(&mut self, id: usize) -> bool {
+        let task_idx = self.find_task_idx(id);
+        let task = &mut self.task_list[task_idx];
+        if !matches!(task.pend, TaskPendReason::Suspended) {
+            return false;
+        }
+
+        let (state, pend) = match (task.pre_suspend.take(), task.suspended_wait.take()) {
+            (Some(pre_suspend), _) => pre_suspend,
+            (None, Some(wait)) => (TaskState::Pending, wait),
+            (None, None) => (TaskState::Ready, TaskPendReason::NotPending),
+        };
+
+        self.ready_list.set_state(task_idx, task, state, pend);
+        self.continue_wait(id);
+
+        self.scheduler()
+    }
+
+    /// Suspend every task except one task and the idle task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to keep running, usually the current task
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// The state and pend reason of each task are saved, so `resume_all` puts
+    /// the tasks back the way they were. A task waiting on a kernel object stops
+    /// waiting until then, see `resume`. Tasks that are already suspended are
+    /// left as they are.
+    pub fn suspend_all_except(&mut self, id: usize) -> bool {
+        let _ = self.find_task(id);
+
+        for idx in 0..self.task_list.len() {
+            let task = &mut self.task_list[idx];
+            if task.id == id
+                || task.id == IDLE_TASK_ID
+                || matches!(task.pend, TaskPendReason::Suspended)
+            {
+                continue;
+            }
+
+            let state = match task.state {
+                TaskState::Running => TaskState::Ready,
+                state => state,
+            };
+            let pend = task.pend;
+            task.pre_suspend = Some((state, pend));
+            self.ready_list
+                .set_state(idx, task, TaskState::Pending, TaskPendReason::Suspended);
+            self.pause_wait(pend);
+        }
+
+        self.scheduler()
+    }
+
+    /// Resume every task suspended by `suspend_all_except`
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Note
+    ///
+    /// Each task returns to the state and pend reason it had before it was
+    /// suspended. A task resumed with `resume` in the meantime is not affected.
+    pub fn resume_all(&mut self) -> bool {
+        for idx in 0..self.task_list.len() {
+            let task = &mut self.task_list[idx];
+            if let Some((state, pend)) = task.pre_suspend.take() {
+                let id = task.id;
+                self.ready_list.set_state(idx, task, state, pend);
+                self.continue_wait(id);
+            }
+        }
+
+        self.scheduler()
+    }
+
+    /// Resume a task, returning an error instead of panicking
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to resume
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if a context switch is needed, `Ok(false)` if not, or
+    /// `Err(TaskError::NotFound)` if the `id` does not correspond to a task
+    pub fn try_resume(&mut self, id: usize) -> Result<bool, TaskError> {
+        self.check_task_exists(Some(id))?;
+        Ok(self.resume(id))
+    }
+
+    /// Wake up a sleeping task before its wake up tick
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to wake up
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// Only ends a `sleep` or `sleep_until`, a task that is not sleeping is
+    /// left as it is. Use `resume` for a suspended task.
+    pub fn wake(&mut self, id: usize) -> bool {
+        if !matches!(self.find_task(id).pend, TaskPendReason::Sleep(_)) {
+            return false;
+        }
+
+        self.make_ready(id);
+        self.scheduler()
+    }
+
+    /// Change the priority of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `priority`: New task priority, with a lower number meaning higher
+    ///   priority
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a task
+    /// * The task is the idle task, whose priority cannot be changed
+    /// * The `priority` exceeds the maximum priority, see `set_max_priority`, or is
+    ///   not lower than `PRIORITY_LEVELS` with the `priority-bitmap` feature
+    ///
+    /// # Note
+    ///
+    /// Replaces the priority assigned at creation. A task owning a mutex keeps
+    /// any higher priority inherited from the tasks waiting on it.
+    pub fn set_priority(&mut self, id: usize, priority: usize) -> bool {
+        self.set_base_priority(id, priority);
+        self.scheduler()
+    }
+
+    /// Assign task priorities by rate monotonic scheduling
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks`: Task IDs and their periods
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * An `id` provided does not correspond to a task
+    /// * A task is the idle task, whose priority cannot be changed
+    /// * A priority exceeds the maximum priority, see `set_max_priority`, or is
+    ///   not lower than `PRIORITY_LEVELS` with the `priority-bitmap` feature
+    ///
+    /// # Note
+    ///
+    /// The task with the shortest period gets priority `0`, the next shortest
+    /// `1` and so on. Tasks with the same period are ordered by ID, lowest
+    /// first. Tasks not in `tasks` keep their priority.
+    pub fn assign_rate_monotonic(&mut self, tasks: &[(usize, TICK)]) -> bool {
+        for &(id, period) in tasks.iter() {
+            let priority = tasks
+                .iter()
+                .filter(|&&(other_id, other_period)| {
+                    other_period < period || (other_period == period && other_id < id)
+                })
+                .count();
+            self.set_base_priority(id, priority);
+        }
+
+        self.scheduler()
+    }
+
+    /// Block the current task until another task is deleted
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to wait for
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided is the current task
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// Returns immediately if the `id` does not correspond to a task, e.g. it
+    /// has already been deleted
+    pub fn join(&mut self, id: usize) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        assert!(id != curr_task_id, "A task cannot join itself");
+
+        if !self.task_list.iter().any(|t| t.id == id) {
+            return false;
+        }
+
+        self.pend_task(curr_task_id, TaskPendReason::Join(id));
+
+        self.scheduler()
+    }
+
+    /// Create a binary semaphore
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Semaphore ID
+    ///
+    /// # Panics
+    ///
+    /// * The semaphore `id` is not unique
+    /// * Too many semaphores have been created, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// The semaphore is created unavailable, so the first `sem_take` blocks
+    /// until a `sem_give`
+    pub fn sem_create(&mut self, id: usize) {
+        self.sem_create_counting(id, 0, 1);
+    }
+
+    /// Create a counting semaphore
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Semaphore ID
+    /// * `count`: Initial count
+    /// * `max_count`: Upper bound on the count, `sem_give` saturates at this value
+    ///
+    /// # Panics
+    ///
+    /// * The semaphore `id` is not unique
+    /// * Too many semaphores have been created, more than `MAX_NUM_TASKS`
+    /// * The initial `count` exceeds `max_count`
+    pub fn sem_create_counting(&mut self, id: usize, count: usize, max_count: usize) {
+        self.sem_create_with_order(id, count, max_count, WakeOrder::Priority);
+    }
+
+    /// Create a counting semaphore with an order to wake up waiters in
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Semaphore ID
+    /// * `count`: Initial count
+    /// * `max_count`: Upper bound on the count, `sem_give` saturates at this value
+    /// * `wake_order`: Order in which `sem_give` wakes up waiting tasks
+    ///
+    /// # Panics
+    ///
+    /// * The semaphore `id` is not unique
+    /// * Too many semaphores have been created, more than `MAX_NUM_TASKS`
+    /// * The initial `count` exceeds `max_count`
+    ///
+    /// # Note
+    ///
+    /// With `WakeOrder::Fifo`, waiters are woken up in the order they called
+    /// `sem_take`, e.g. to serve requests in the order they were made
+    pub fn sem_create_with_order(
+        &mut self,
+        id: usize,
+        count: usize,
+        max_count: usize,
+        wake_order: WakeOrder,
+    ) {
+        assert!(count <= max_count, "Semaphore count exceeds max_count");
+
+        // Ensure the semaphore ID is unique
+        for sem in self.semaphore_list.iter() {
+            assert!(sem.id != id, "The semaphore ID is not unique");
+        }
+
+        self.semaphore_list
+            .push(Semaphore {
+                id,
+                count,
+                max_count,
+                wake_order,
+            })
+            .expect("Number of semaphores exceeds MAX_NUM_TASKS");
+    }
+
+    /// Get the count of a semaphore
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Semaphore to query
+    ///
+    /// # Returns
+    ///
+    /// Number of times the semaphore can be taken without blocking
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a semaphore
+    pub fn sem_get_count(&self, id: usize) -> usize {
+        self.semaphore_list
+            .iter()
+            .find(|s| s.id == id)
+            .expect("Semaphore does not exist")
+            .count
+    }
+
+    /// Take a semaphore, blocking the current task if it is unavailable
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Semaphore to take
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a semaphore
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    pub fn sem_take(&mut self, id: usize, timeout: Option<TICK>) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        let sem = self.find_semaphore(id);
+
+        if sem.count > 0 {
+            sem.count -= 1;
+            return false;
+        }
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(curr_task_id, TaskPendReason::SemWait(id, timeout));
+
+        self.scheduler()
+    }
+
+    /// Give a semaphore, waking the highest priority task waiting on it
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Semaphore to give
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a semaphore
+    ///
+    /// # Note
+    ///
+    /// If a task is waiting, the semaphore is handed directly to it and the
+    /// count is unchanged. Otherwise the count is incremented, saturating at
+    /// the maximum count of the semaphore.
+    pub fn sem_give(&mut self, id: usize) -> bool {
+        let wake_order = self.find_semaphore(id).wake_order;
+
+        let is_waiting = |pend: &TaskPendReason<TICK>| match pend {
+            TaskPendReason::SemWait(sem_id, _) => *sem_id == id,
+            _ => false,
+        };
+        let waiter = match wake_order {
+            WakeOrder::Priority => self.find_highest_priority_waiter(is_waiting),
+            WakeOrder::Fifo => self.find_longest_waiter(is_waiting),
+        };
+
+        match waiter {
+            Some(task_id) => self.make_ready(task_id),
+            None => {
+                let sem = self.find_semaphore(id);
+                sem.count = sem.max_count.min(sem.count.saturating_add(1));
+            }
+        }
+
+        self.scheduler()
+    }
+
+    /// Create a mutex
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mutex ID
+    ///
+    /// # Panics
+    ///
+    /// * The mutex `id` is not unique
+    /// * Too many mutexes have been created, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// The mutex uses priority inheritance, see `mutex_lock`
+    pub fn mutex_create(&mut self, id: usize) {
+        self.push_mutex(id, None);
+    }
+
+    /// Create a mutex using the immediate priority ceiling protocol
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mutex ID
+    /// * `ceiling`: Priority of the highest priority task that locks the mutex
+    ///
+    /// # Panics
+    ///
+    /// * The mutex `id` is not unique
+    /// * Too many mutexes have been created, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// A task locking the mutex is raised to the `ceiling` priority right away
+    /// and keeps it until the mutex is released, so no task that also locks
+    /// the mutex can preempt it in the meantime. Unlike priority inheritance,
+    /// this bounds priority inversion to a single critical section and
+    /// prevents deadlock between mutexes whose ceilings are set this way.
+    pub fn mutex_create_with_ceiling(&mut self, id: usize, ceiling: usize) {
+        self.push_mutex(id, Some(ceiling));
+    }
+
+    /// Lock a mutex, blocking the current task if another task holds it
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mutex to lock
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a mutex
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    /// * With the `deadlock-detection` feature, if blocking would deadlock, see
+    ///   `try_mutex_lock`
+    ///
+    /// # Note
+    ///
+    /// The mutex is recursive: If the current task already holds it, the lock
+    /// count is incremented and the call returns immediately.
+    ///
+    /// While the current task is blocked, the task holding the mutex inherits
+    /// its priority if it is higher. This prevents a medium priority task from
+    /// starving the owner, and therefore the current task (priority inversion).
+    /// A mutex with a priority ceiling raises its owner to the ceiling instead,
+    /// see `mutex_create_with_ceiling`.
+    pub fn mutex_lock(&mut self, id: usize, timeout: Option<TICK>) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        let mutex = self.find_mutex(id);
+
+        let owner = match mutex.owner {
+            Some(owner) if owner == curr_task_id => {
+                mutex.lock_count += 1;
+                return false;
+            }
+            Some(owner) => owner,
+            None => {
+                mutex.owner = Some(curr_task_id);
+                mutex.lock_count = 1;
+                self.update_inherited_priority(curr_task_id);
+                return false;
+            }
+        };
+
+        #[cfg(feature = "deadlock-detection")]
+        assert!(
+            !self.would_deadlock(owner, curr_task_id),
+            "Mutex lock would deadlock"
+        );
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(curr_task_id, TaskPendReason::MutexWait(id, timeout));
+
+        self.update_inherited_priority(owner);
+
+        self.scheduler()
+    }
+
+    /// Lock a mutex, returning an error instead of blocking forever
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mutex to lock
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if a context switch is needed, `Ok(false)` if not, or
+    /// `Err(MutexError::Deadlock)` if the task holding the mutex is, directly or
+    /// through other mutexes, waiting on a mutex held by the current task
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a mutex
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// Requires the `deadlock-detection` feature. The chain of mutex owners is
+    /// walked whenever the current task would block, which is bounded by the
+    /// number of tasks.
+    #[cfg(feature = "deadlock-detection")]
+    pub fn try_mutex_lock(&mut self, id: usize, timeout: Option<TICK>) -> Result<bool, MutexError> {
+        let curr_task_id = self.blocking_task_id();
+
+        if let Some(owner) = self.find_mutex(id).owner {
+            if self.would_deadlock(owner, curr_task_id) {
+                return Err(MutexError::Deadlock);
+            }
+        }
+
+        Ok(self.mutex_lock(id, timeout))
+    }
+
+    /// Unlock a mutex held by the current task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mutex to unlock
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a mutex
+    /// * The current task does not hold the mutex
+    /// * If called before the kernel is running
+    ///
+    /// # Note
+    ///
+    /// The mutex is only released once it has been unlocked as many times as
+    /// it was locked. It is then handed to the highest priority waiting task,
+    /// if any, and the current task gives up any priority it inherited through
+    /// the mutex.
+    pub fn mutex_unlock(&mut self, id: usize) -> bool {
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        let mutex = self.find_mutex(id);
+
+        assert!(
+            mutex.owner == Some(curr_task_id),
+            "Mutex not locked by the current task"
+        );
+
+        mutex.lock_count -= 1;
+        if mutex.lock_count > 0 {
+            return false;
+        }
+
+        self.release_mutex(id, Some(curr_task_id));
+        self.scheduler()
+    }
+
+    /// Create a condition variable
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Condition variable ID
+    ///
+    /// # Panics
+    ///
+    /// * The condition variable `id` is not unique
+    /// * Too many condition variables have been created, more than
+    ///   `MAX_NUM_TASKS`
+    pub fn cond_create(&mut self, id: usize) {
+        // Ensure the condition variable ID is unique
+        for cond in self.cond_list.iter() {
+            assert!(cond.id != id, "The condition variable ID is not unique");
+        }
+
+        self.cond_list
+            .push(CondVar { id })
+            .expect("Number of condition variables exceeds MAX_NUM_TASKS");
+    }
+
+    /// Unlock a mutex and wait on a condition variable, blocking the current
+    /// task until it is notified and has locked the mutex again
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Condition variable to wait on
+    /// * `mutex_id`: Mutex held by the current task, protecting the condition
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a condition variable
+    /// * The `mutex_id` provided does not correspond to a mutex
+    /// * The mutex is not locked by the current task, or is locked recursively
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// Unlocking the mutex and blocking happen together, so a notification
+    /// sent after the mutex is unlocked cannot be missed. Notifications are not
+    /// remembered though, so the condition itself must be checked with the
+    /// mutex locked before waiting.
+    pub fn cond_wait(&mut self, id: usize, mutex_id: usize) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        let _ = self.find_cond(id);
+        let mutex = self.find_mutex(mutex_id);
+
+        assert!(
+            mutex.owner == Some(curr_task_id),
+            "Mutex not locked by the current task"
+        );
+        assert!(mutex.lock_count == 1, "Mutex locked recursively");
+
+        self.release_mutex(mutex_id, Some(curr_task_id));
+
+        self.pend_task(curr_task_id, TaskPendReason::CondWait(id, mutex_id));
+
+        self.scheduler()
+    }
+
+    /// Notify the highest priority task waiting on a condition variable
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Condition variable to notify
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a condition variable
+    ///
+    /// # Note
+    ///
+    /// The notified task then waits to lock its mutex again. Does nothing if no
+    /// task is waiting.
+    pub fn cond_notify_one(&mut self, id: usize) -> bool {
+        let _ = self.find_cond(id);
+
+        if let Some(task_id) = self.find_cond_waiter(id) {
+            self.cond_wake(task_id);
+        }
+
+        self.scheduler()
+    }
+
+    /// Notify all tasks waiting on a condition variable
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Condition variable to notify
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a condition variable
+    ///
+    /// # Note
+    ///
+    /// The notified tasks lock their mutex again one at a time, highest
+    /// priority first
+    pub fn cond_notify_all(&mut self, id: usize) -> bool {
+        let _ = self.find_cond(id);
+
+        while let Some(task_id) = self.find_cond_waiter(id) {
+            self.cond_wake(task_id);
+        }
+
+        self.scheduler()
+    }
+
+    /// Create a read-write lock
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Read-write lock ID
+    ///
+    /// # Panics
+    ///
+    /// * The read-write lock `id` is not unique
+    /// * Too many read-write locks have been created, more than `MAX_NUM_TASKS`
+    pub fn rwlock_create(&mut self, id: usize) {
+        // Ensure the read-write lock ID is unique
+        for rwlock in self.rwlock_list.iter() {
+            assert!(rwlock.id != id, "The read-write lock ID is not unique");
+        }
+
+        self.rwlock_list
+            .push(RwLock {
+                id,
+                readers: 0,
+                writer: None,
+            })
+            .expect("Number of read-write locks exceeds MAX_NUM_TASKS");
+    }
+
+    /// Lock a read-write lock for reading, blocking the current task if a task
+    /// holds it for writing or is waiting to
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Read-write lock to lock
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a read-write lock
+    /// * The current task already holds `MAX_READ_LOCKS` read locks
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// Any number of tasks can hold the lock for reading at once. New readers
+    /// wait behind a waiting writer, so a steady stream of readers cannot
+    /// starve writers.
+    pub fn rwlock_read_lock(&mut self, id: usize, timeout: Option<TICK>) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        assert!(
+            !self.find_task(curr_task_id).read_locks.is_full(),
+            "Number of read locks held exceeds MAX_READ_LOCKS"
+        );
+
+        let writer_waiting = self.find_highest_priority_waiter(|pend| match pend {
+            TaskPendReason::WriteWait(rwlock_id, _) => *rwlock_id == id,
+            _ => false,
+        });
+
+        let rwlock = self.find_rwlock(id);
+        if rwlock.writer.is_none() && writer_waiting.is_none() {
+            rwlock.readers += 1;
+            self.add_read_lock(curr_task_id, id);
+            return false;
+        }
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(curr_task_id, TaskPendReason::ReadWait(id, timeout));
+
+        self.scheduler()
+    }
+
+    /// Unlock a read-write lock held for reading
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Read-write lock to unlock
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a read-write lock
+    /// * The read-write lock is not held for reading by the current task
+    /// * If called before the kernel is running
+    pub fn rwlock_read_unlock(&mut self, id: usize) -> bool {
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        let _ = self.find_rwlock(id);
+
+        let read_locks = &mut self.find_task(curr_task_id).read_locks;
+        let idx = read_locks
+            .iter()
+            .position(|&rwlock_id| rwlock_id == id)
+            .expect("Read-write lock not locked for reading by the current task");
+        read_locks.swap_remove(idx);
+
+        self.find_rwlock(id).readers -= 1;
+        self.rwlock_grant(id);
+
+        self.scheduler()
+    }
+
+    /// Lock a read-write lock for writing, blocking the current task if any
+    /// task holds it
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Read-write lock to lock
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a read-write lock
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// Unlike a mutex, the lock is not recursive and there is no priority
+    /// inheritance
+    pub fn rwlock_write_lock(&mut self, id: usize, timeout: Option<TICK>) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        let rwlock = self.find_rwlock(id);
+
+        if rwlock.writer.is_none() && rwlock.readers == 0 {
+            rwlock.writer = Some(curr_task_id);
+            return false;
+        }
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(curr_task_id, TaskPendReason::WriteWait(id, timeout));
+
+        self.scheduler()
+    }
+
+    /// Unlock a read-write lock held for writing
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Read-write lock to unlock
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a read-write lock
+    /// * The read-write lock is not held for writing by the current task
+    /// * If called before the kernel is running
+    ///
+    /// # Note
+    ///
+    /// The lock is handed to the highest priority waiting task. If that is a
+    /// reader, all waiting readers get the lock together.
+    pub fn rwlock_write_unlock(&mut self, id: usize) -> bool {
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        let rwlock = self.find_rwlock(id);
+
+        assert!(
+            rwlock.writer == Some(curr_task_id),
+            "Read-write lock not locked for writing by the current task"
+        );
+
+        rwlock.writer = None;
+        self.rwlock_grant(id);
+
+        self.scheduler()
+    }
+
+    /// Create a barrier
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Barrier ID
+    /// * `count`: Number of tasks that must arrive to release the barrier
+    ///
+    /// # Panics
+    ///
+    /// * The barrier `id` is not unique
+    /// * Too many barriers have been created, more than `MAX_NUM_TASKS`
+    /// * The `count` is zero
+    pub fn barrier_create(&mut self, id: usize, count: usize) {
+        assert!(count > 0, "Barrier count must be non-zero");
+
+        // Ensure the barrier ID is unique
+        for barrier in self.barrier_list.iter() {
+            assert!(barrier.id != id, "The barrier ID is not unique");
+        }
+
+        self.barrier_list
+            .push(Barrier {
+                id,
+                count,
+                waiting: 0,
+            })
+            .expect("Number of barriers exceeds MAX_NUM_TASKS");
+    }
+
+    /// Wait at a barrier, blocking the current task until enough tasks arrive
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Barrier to wait at
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a barrier
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// The last task to arrive releases all waiting tasks and does not block,
+    /// and the barrier resets for the next round. If a task waiting at the
+    /// barrier is deleted, the barrier waits for one less task from then on.
+    pub fn barrier_wait(&mut self, id: usize) -> bool {
+        let curr_task_id = self.blocking_task_id();
+        let barrier = self.find_barrier(id);
+
+        barrier.waiting += 1;
+        if barrier.waiting < barrier.count {
+            self.pend_task(curr_task_id, TaskPendReason::BarrierWait(id));
+
+            return self.scheduler();
+        }
+
+        self.barrier_release(id);
+
+        self.scheduler()
+    }
+
+    /// Create an event group
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Event group ID
+    ///
+    /// # Panics
+    ///
+    /// * The event group `id` is not unique
+    /// * Too many event groups have been created, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// The event group is created with all flags cleared
+    pub fn event_create(&mut self, id: usize) {
+        // Ensure the event group ID is unique
+        for event in self.event_list.iter() {
+            assert!(event.id != id, "The event group ID is not unique");
+        }
+
+        self.event_list
+            .push(EventGroup { id, flags: 0 })
+            .expect("Number of event groups exceeds MAX_NUM_TASKS");
+    }
+
+    /// Wait on a combination of flags in an event group, blocking the current
+    /// task until they are set
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Event group to wait on
+    /// * `mask`: Event flags to wait on
+    /// * `wait_all`: `true` to wait on all flags in `mask`, `false` to wait on
+    ///   any
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to an event group
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// Waiting does not clear any flags, use `event_clear` to consume them
+    pub fn event_wait(
+        &mut self,
+        id: usize,
+        mask: u32,
+        wait_all: bool,
+        timeout: Option<TICK>,
+    ) -> bool {
+        let curr_task_id = self.blocking_task_id();
+
+        if self.find_event(id).is_set(mask, wait_all) {
+            return false;
+        }
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(
+            curr_task_id,
+            TaskPendReason::EventWait {
+                id,
+                mask,
+                wait_all,
+                timeout,
+            },
+        );
+
+        self.scheduler()
+    }
+
+    /// Set flags in an event group, waking all tasks waiting on them
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Event group to update
+    /// * `bits`: Event flags to set
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to an event group
+    pub fn event_set(&mut self, id: usize, bits: u32) -> bool {
+        let event = self.find_event(id);
+        event.flags |= bits;
+        let event = *event;
+
+        for (idx, task) in self.task_list.iter_mut().enumerate() {
+            if let TaskPendReason::EventWait {
+                id: event_id,
+                mask,
+                wait_all,
+                ..
+            } = task.pend
+            {
+                if event_id == id && event.is_set(mask, wait_all) {
+                    self.ready_list.set_ready(idx, task);
+                }
+            }
+        }
+
+        self.scheduler()
+    }
+
+    /// Clear flags in an event group
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Event group to update
+    /// * `bits`: Event flags to clear
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to an event group
+    pub fn event_clear(&mut self, id: usize, bits: u32) {
+        self.find_event(id).flags &= !bits;
+    }
+
+    /// Get the flags of an event group
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Event group to query
+    ///
+    /// # Returns
+    ///
+    /// Current event flags
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to an event group
+    pub fn event_get(&self, id: usize) -> u32 {
+        self.event_list
+            .iter()
+            .find(|e| e.id == id)
+            .expect("Event group does not exist")
+            .flags
+    }
+
+    /// Create a mailbox
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mailbox ID
+    ///
+    /// # Panics
+    ///
+    /// * The mailbox `id` is not unique
+    /// * Too many mailboxes have been created, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// The mailbox is created empty
+    pub fn mailbox_create(&mut self, id: usize) {
+        // Ensure the mailbox ID is unique
+        for mailbox in self.mailbox_list.iter() {
+            assert!(mailbox.id != id, "The mailbox ID is not unique");
+        }
+
+        self.mailbox_list
+            .push(Mailbox { id, item: None })
+            .expect("Number of mailboxes exceeds MAX_NUM_TASKS");
+    }
+
+    /// Post an item to a mailbox, waking the highest priority task waiting on it
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mailbox to post to
+    /// * `item`: Item to post
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a mailbox
+    ///
+    /// # Note
+    ///
+    /// If a task is waiting, the item is handed directly to it. Otherwise the
+    /// item replaces any item not yet fetched.
+    pub fn mailbox_post(&mut self, id: usize, item: usize) -> bool {
+        let _ = self.find_mailbox(id);
+
+        let waiter = self.find_highest_priority_waiter(|pend| match pend {
+            TaskPendReason::MailboxWait(mailbox_id, _) => *mailbox_id == id,
+            _ => false,
+        });
+
+        match waiter {
+            Some(task_id) => {
+                self.make_ready(task_id);
+                self.find_task(task_id).message = Some(item);
+            }
+            None => self.find_mailbox(id).item = Some(item),
+        }
+
+        self.scheduler()
+    }
+
+    /// Fetch the item in a mailbox, blocking the current task if it is empty
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Mailbox to fetch from
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * The `id` provided does not correspond to a mailbox
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// The item is consumed and given to the current task, see `take_message`.
+    /// If the task blocks, it receives the item once it runs again, or nothing
+    /// if the timeout expired.
+    pub fn mailbox_fetch(&mut self, id: usize, timeout: Option<TICK>) -> bool {
+        let curr_task_id = self.blocking_task_id();
+
+        if let Some(item) = self.find_mailbox(id).item.take() {
+            self.find_task(curr_task_id).message = Some(item);
+            return false;
+        }
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(curr_task_id, TaskPendReason::MailboxWait(id, timeout));
+
+        self.scheduler()
+    }
+
+    /// Get the outcome of the last blocking call of the current task
+    ///
+    /// # Returns
+    ///
+    /// `WaitResult::TimedOut` if the timeout of the call expired, or
+    /// `WaitResult::Acquired` if not (including when the call did not block)
+    ///
+    /// # Panics
+    ///
+    /// If called before the kernel is running
+    ///
+    /// # Note
+    ///
+    /// Blocking calls return before the task has waited, so a port calls this
+    /// once the task runs again, e.g. to report whether `sem_take`,
+    /// `mutex_lock`, `rwlock_read_lock`, `rwlock_write_lock`, or `event_wait`
+    /// timed out. The outcome is recorded by the context switch that resumes
+    /// the task, so it stays the same until the task makes another blocking
+    /// call.
+    pub fn get_wait_result(&self) -> WaitResult {
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        self.task_list
+            .iter()
+            .find(|t| t.id == curr_task_id)
+            .map_or(WaitResult::Acquired, |t| t.wait_result)
+    }
+
+    /// Take the item received by the current task
+    ///
+    /// # Returns
+    ///
+    /// Item received by the current task, or `None` if it has not received one
+    /// (e.g. a wait timed out)
+    ///
+    /// # Panics
+    ///
+    /// If called before the kernel is running
+    pub fn take_message(&mut self) -> Option<usize> {
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        self.find_task(curr_task_id).message.take()
+    }
+
+    /// Arm a one-shot alarm, without blocking the current task
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Alarm ID
+    /// * `delay`: Number of ticks until the alarm fires
+    /// * `callback`: Function called with the alarm ID when the alarm fires
+    ///
+    /// # Panics
+    ///
+    /// Too many alarms are armed, more than `MAX_NUM_TASKS`
+    ///
+    /// # Note
+    ///
+    /// The callback is not called by the kernel, when the alarm fires in
+    /// `tick_update` it is kept until taken with `take_fired_alarm`, so the
+    /// caller can run it once the kernel is no longer borrowed. Arming an alarm
+    /// that is already armed or has fired but not been taken restarts it with
+    /// the new `delay` and `callback`.
+    pub fn alarm_set(&mut self, id: usize, delay: TICK, callback: fn(usize)) {
+        let expiry = self.tick_counter.wrapping_add(delay);
+
+        match self.alarm_list.iter_mut().find(|a| a.id == id) {
+            Some(alarm) => {
+                alarm.expiry = expiry;
+                alarm.callback = callback;
+                alarm.fired = false;
+            }
+            None => self
+                .alarm_list
+                .push(Alarm {
+                    id,
+                    expiry,
+                    callback,
+                    fired: false,
+                })
+                .expect("Number of alarms exceeds MAX_NUM_TASKS"),
+        }
+    }
+
+    /// Cancel an alarm before it fires
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Alarm to cancel
+    ///
+    /// # Returns
+    ///
+    /// `true` if the alarm was armed, `false` if not
+    ///
+    /// # Note
+    ///
+    /// An alarm that has fired but not been taken is cancelled too, so its
+    /// callback is never taken.
+    pub fn alarm_cancel(&mut self, id: usize) -> bool {
+        match self.alarm_list.iter().position(|a| a.id == id) {
+            Some(idx) => {
+                let _ = self.alarm_list.swap_remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take an alarm that has fired, disarming it
+    ///
+    /// # Returns
+    ///
+    /// ID and callback of a fired alarm, or `None` if no alarm has fired
+    ///
+    /// # Note
+    ///
+    /// Intended to be called after `tick_update`, until it returns `None`. The
+    /// callback is meant to be called once the kernel is no longer borrowed, so
+    /// it can call kernel APIs (e.g. set an event flag or give a semaphore) or
+    /// re-arm the alarm.
+    pub fn take_fired_alarm(&mut self) -> Option<(usize, fn(usize))> {
+        let idx = self.alarm_list.iter().position(|a| a.fired)?;
+        let alarm = self.alarm_list.swap_remove(idx);
+        Some((alarm.id, alarm.callback))
+    }
+
+    /// Defer work to a task, e.g. from an interrupt handler
+    ///
+    /// # Arguments
+    ///
+    /// * `work_id`: Token identifying the work to do
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// More than `MAX_NUM_TASKS` work items are waiting to be taken
+    ///
+    /// # Note
+    ///
+    /// The highest priority task waiting in `work_wait` is made ready. Work
+    /// items are taken in the order they were deferred, see `take_work`.
+    pub fn defer(&mut self, work_id: usize) -> bool {
+        self.work_queue
+            .push_back(work_id)
+            .expect("Deferred work queue is full");
+
+        let waiter =
+            self.find_highest_priority_waiter(|pend| matches!(pend, TaskPendReason::WorkWait));
+
+        if let Some(task_id) = waiter {
+            self.make_ready(task_id);
+        }
+
+        self.scheduler()
+    }
+
+    /// Wait for deferred work, blocking the current task if there is none
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// The work item is not consumed, call `take_work` once the task runs again
+    pub fn work_wait(&mut self) -> bool {
+        let curr_task_id = self.blocking_task_id();
+
+        if !self.work_queue.is_empty() {
+            return false;
+        }
+
+        self.pend_task(curr_task_id, TaskPendReason::WorkWait);
+
+        self.scheduler()
+    }
+
+    /// Take the oldest deferred work item
+    ///
+    /// # Returns
+    ///
+    /// Token of the work item, or `None` if there is no deferred work
+    pub fn take_work(&mut self) -> Option<usize> {
+        self.work_queue.pop_front()
+    }
+
+    /// Set how notifications sent to a task are combined
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to update
+    /// * `action`: How a new notification is combined with one the task has
+    ///   not taken yet, the default is `NotifyAction::Overwrite`
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    pub fn set_notify_action(&mut self, id: usize, action: NotifyAction) {
+        self.find_task(id).notify_action = action;
+    }
+
+    /// Send a notification value to a task, waking it if it is waiting for one
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to notify
+    /// * `value`: Notification value
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// The `id` provided does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// The value is kept on the task until it is taken, see `notify_wait` and
+    /// `set_notify_action`
+    pub fn notify(&mut self, id: usize, value: u32) -> bool {
+        let task = self.find_task(id);
+        task.notification = match (task.notify_action, task.notification) {
+            (NotifyAction::SetBits, Some(pending)) => Some(pending | value),
+            _ => Some(value),
+        };
+
+        if matches!(task.pend, TaskPendReason::NotifyWait(_)) {
+            self.make_ready(id);
+        }
+
+        self.scheduler()
+    }
+
+    /// Wait for a notification, blocking the current task if it has none
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout`: Maximum number of ticks to wait or `None` to wait forever
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// The notification is not consumed, call `take_notification` once the task
+    /// runs again
+    pub fn notify_wait(&mut self, timeout: Option<TICK>) -> bool {
+        let curr_task_id = self.blocking_task_id();
+
+        if self.find_task(curr_task_id).notification.is_some() {
+            return false;
+        }
+
+        let timeout = timeout.map(|delay| self.tick_counter.wrapping_add(delay));
+        self.pend_task(curr_task_id, TaskPendReason::NotifyWait(timeout));
+
+        self.scheduler()
+    }
+
+    /// Wait until an absolute tick count or a notification, blocking the
+    /// current task if it has no notification
+    ///
+    /// # Arguments
+    ///
+    /// * `wakeup_tick`: Value of the global tick counter at which the wait ends
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// * If called before the kernel is running
+    /// * If called from an interrupt handler, see `set_isr_check`
+    ///
+    /// # Note
+    ///
+    /// If `wakeup_tick` has already been reached the task does not block. Like
+    /// `sleep_until`, successive timed waits can advance `wakeup_tick` by a
+    /// period without drift. Call `take_wake_reason` once the task runs again.
+    pub fn wait_until_or_signal(&mut self, wakeup_tick: TICK) -> bool {
+        let curr_task_id = self.blocking_task_id();
+
+        if self.find_task(curr_task_id).notification.is_some()
+            || self.tick_counter.wrapping_ge(wakeup_tick)
+        {
+            return false;
+        }
+
+        self.pend_task(curr_task_id, TaskPendReason::NotifyWait(Some(wakeup_tick)));
+
+        self.scheduler()
+    }
+
+    /// Take the condition that ended `wait_until_or_signal` for the current
+    /// task
+    ///
+    /// # Arguments
+    ///
+    /// * `max_value`: Largest notification value to report, a larger value is
+    ///   reported as `max_value`
+    ///
+    /// # Returns
+    ///
+    /// `WakeReason::Signalled` with the notification value, which is cleared,
+    /// or `WakeReason::Deadline` if the task has not been notified
+    ///
+    /// # Panics
+    ///
+    /// If called before the kernel is running
+    pub fn take_wake_reason(&mut self, max_value: u32) -> WakeReason {
+        match self.take_notification() {
+            Some(value) => WakeReason::Signalled(value.min(max_value)),
+            None => WakeReason::Deadline,
+        }
+    }
+
+    /// Take the notification value received by the current task
+    ///
+    /// # Returns
+    ///
+    /// Notification value, or `None` if the current task has not been notified
+    /// (e.g. a wait timed out)
+    ///
+    /// # Panics
+    ///
+    /// If called before the kernel is running
+    pub fn take_notification(&mut self) -> Option<u32> {
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        self.find_task(curr_task_id).notification.take()
+    }
+
+    /// Set the tick hook
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: Function called with the updated tick counter on every call to
+    ///   `tick_update`, before the scheduler runs, or `None` to remove the hook
+    ///
+    /// # Note
+    ///
+    /// The hook usually runs in interrupt context, so it must be short. More
+    /// than one tick may have elapsed between calls (e.g. after tickless idle).
+    pub fn set_tick_hook(&mut self, hook: Option<fn(TICK)>) {
+        self.tick_hook = hook;
+    }
+
+    /// Set the context switch hook
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: Function called on every context switch with the ID of the
+    ///   outgoing task (or `None` if there is no current task, e.g. the task was
+    ///   deleted) and the ID of the incoming task, or `None` to remove the hook
+    ///
+    /// # Note
+    ///
+    /// The hook runs in the context switch handler, so it must be minimal
+    pub fn set_switch_hook(&mut self, hook: Option<fn(Option<usize>, usize)>) {
+        self.switch_hook = hook;
+    }
+
+    /// Set the no runnable task hook
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: Function called when the scheduler finds no runnable task and
+    ///   the idle task does not exist, or `None` to panic instead (the default)
+    ///
+    /// # Note
+    ///
+    /// The idle task is always runnable and cannot be deleted, so this only
+    /// happens if it was never created. No context switch happens, so the current task
+    /// keeps running even though it might be pending.
+    pub fn set_no_runnable_hook(&mut self, hook: Option<fn()>) {
+        self.no_runnable_hook = hook;
+    }
+
+    /// Set the stack overflow hook
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: Function called with the task ID when a stack overflow is
+    ///   detected, or `None` to remove the hook
+    ///
+    /// # Note
+    ///
+    /// With the `idle-stack-check` feature, the idle task stack is checked on
+    /// every context switch to the idle task, see `set_stack_check`. The hook
+    /// runs in the context switch handler, before the idle task resumes.
+    pub fn set_overflow_hook(&mut self, hook: Option<fn(usize)>) {
+        self.overflow_hook = hook;
+    }
+
+    /// Set the stack canary check
+    ///
+    /// # Arguments
+    ///
+    /// * `check`: Function called with the lowest address and size of a stack
+    ///   region returning `true` if its canary is intact, or `None` to skip the
+    ///   check (the default)
+    ///
+    /// # Note
+    ///
+    /// The kernel does not access the stack, so the port provides the check for
+    /// the regions recorded with `set_stack_region`
+    pub fn set_stack_check(&mut self, check: Option<fn(usize, usize) -> bool>) {
+        self.stack_check = check;
+    }
+
+    /// Set the interrupt context check
+    ///
+    /// # Arguments
+    ///
+    /// * `check`: Function returning `true` if called from an interrupt
+    ///   handler, or `None` to skip the check (the default)
+    ///
+    /// # Note
+    ///
+    /// APIs that can block the current task (e.g. `sleep` or `sem_take`) panic
+    /// if the check returns `true`, since an interrupt handler has no task of
+    /// its own to block. They panic even if they would not block this time.
+    /// APIs that only make tasks ready (e.g. `sem_give` or `resume`) are
+    /// allowed from interrupt handlers.
+    pub fn set_isr_check(&mut self, check: Option<fn() -> bool>) {
+        self.isr_check = check;
+    }
+
+    /// Update the global tick counter
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed`: Number of ticks that have passed since last call
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Note
+    ///
+    /// If the time slice of the current task has expired and other tasks with
+    /// the same priority are ready, the current task is switched out for the
+    /// one that ran least recently (round-robin). The idle task priority is
+    /// excluded. Under `SchedPolicy::Mlfq` the current task is also demoted.
+    pub fn tick_update(&mut self, elapsed: TICK) -> bool {
+        self.tick_counter = self.tick_counter.wrapping_add(elapsed);
+
+        if let Some(curr_task_id) = self.curr_task_id {
+            let curr_task = self.find_task(curr_task_id);
+            curr_task.run_ticks = curr_task.run_ticks.wrapping_add(elapsed);
+        }
+
+        if let Some(hook) = self.tick_hook {
+            hook(self.tick_counter);
+        }
+
+        self.fire_alarms();
+        self.check_watchdogs();
+
+        let round_robin = self.update_time_slice();
+        if round_robin {
+            if let Some(curr_task_id) = self.curr_task_id {
+                self.mlfq_update(curr_task_id, true);
+            }
+        }
+
+        self.schedule(round_robin)
+    }
+
+    /// Lock the scheduler, deferring context switches away from the current task
+    ///
+    /// # Note
+    ///
+    /// Calls may be nested, the scheduler is unlocked once `scheduler_unlock`
+    /// has been called the same number of times. Ticks keep counting and tasks
+    /// keep becoming ready while locked. If the current task blocks, a context
+    /// switch still happens, so blocking APIs should not be called while locked.
+    pub fn scheduler_lock(&mut self) {
+        self.sched_lock_count += 1;
+    }
+
+    /// Unlock the scheduler
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Panics
+    ///
+    /// If the scheduler is not locked
+    pub fn scheduler_unlock(&mut self) -> bool {
+        assert!(self.sched_lock_count > 0, "Scheduler not locked");
+        self.sched_lock_count -= 1;
+
+        if self.sched_lock_count == 0 && self.switch_deferred {
+            self.switch_deferred = false;
+            self.scheduler()
+        } else {
+            false
+        }
+    }
+
+    /// Run the scheduler without performing any other kernel operation
+    ///
+    /// # Returns
+    ///
+    /// `true` if a context switch is needed, `false` if not
+    ///
+    /// # Note
+    ///
+    /// Intended for advanced use, where the scheduler has not run since the
+    /// last change that may affect it. Task state must still be changed through
+    /// kernel APIs, which keep the ready list in step with the task list. The
+    /// scheduler only considers tasks on the ready list, so a task state
+    /// written directly desyncs it and is not acted on by this call.
+    pub fn reschedule(&mut self) -> bool {
+        self.scheduler()
+    }
+
+    /// Take the pending context switch decision without applying it
+    ///
+    /// # Returns
+    ///
+    /// ID of the task the scheduler selected to run next, or `None` if no
+    /// context switch is needed
+    ///
+    /// # Note
+    ///
+    /// Intended for advanced use, where deciding and applying a context switch
+    /// are decoupled. The decision is cleared, so the caller must either apply
+    /// it (e.g. `reschedule` followed by `handle_context_switch`) or it is lost
+    /// until the scheduler next runs.
+    pub fn take_switch_decision(&mut self) -> Option<usize> {
+        self.next_task_id.take()
+    }
+
+    /// Handle a context switch
+    ///
+    /// # Arguments
+    ///
+    /// * `updated_stack_ptr`: The updated stack pointer for the current task or
+    ///   `None` if there is no current task
+    ///
+    /// # Returns
+    ///
+    /// The stack pointer for the next task
+    ///
+    /// # Panics
+    ///
+    /// If called when a context switch is not necessary
+    pub fn handle_context_switch(&mut self, updated_stack_ptr: Option<SP>) -> SP {
+        self.handle_context_switch_traced(updated_stack_ptr).0
+    }
+
+    /// Handle a context switch, also reporting the task switched out
+    ///
+    /// # Arguments
+    ///
+    /// * `updated_stack_ptr`: The updated stack pointer for the current task or
+    ///   `None` if there is no current task
+    ///
+    /// # Returns
+    ///
+    /// The stack pointer for the next task and the ID of the task switched out,
+    /// or `None` if there was no current task (e.g. it was deleted)
+    ///
+    /// # Panics
+    ///
+    /// If called when a context switch is not necessary
+    ///
+    /// # Note
+    ///
+    /// Intended for tracing and run time accounting in the port, the outgoing
+    /// task is the same one passed to the switch hook
+    pub fn handle_context_switch_traced(
+        &mut self,
+        updated_stack_ptr: Option<SP>,
+    ) -> (SP, Option<usize>) {
+        let prev_task_id = self.curr_task_id;
+
+        // Update current task
+        if let Some(curr_task_id) = self.curr_task_id {
+            let curr_task = self.find_task(curr_task_id);
+
+            if let Some(sp) = updated_stack_ptr {
+                curr_task.stack_ptr = sp;
+            }
+
+            curr_task.state = match curr_task.state {
+                TaskState::Running => TaskState::Ready,
+                _ => curr_task.state,
+            };
+        }
+
+        // Update kernel
+        let next_task_id = self.next_task_id.expect("No context switch required");
+        if let Some(hook) = self.switch_hook {
+            hook(self.curr_task_id, next_task_id);
+        }
+
+        self.curr_task_id = Some(next_task_id);
+        self.next_task_id = None;
+        self.switch_counter += 1;
+
+        // The idle task runs often on a small stack, so checking it here catches
+        // an overflow (e.g. from an idle hook) early
+        #[cfg(feature = "idle-stack-check")]
+        if next_task_id == IDLE_TASK_ID {
+            self.check_idle_stack();
+        }
+
+        // Update next task
+        let switch_counter = self.switch_counter;
+        let tick_counter = self.tick_counter;
+        let next_task = self.find_task(next_task_id);
+        next_task.state = TaskState::Running;
+        next_task.last_run = switch_counter;
+
+        // The outcome of its wait is fixed as the task resumes, so it cannot
+        // change before the task reads it
+        next_task.wait_result = if next_task.timed_out {
+            WaitResult::TimedOut
+        } else {
+            WaitResult::Acquired
+        };
+        if let Some(time_slice) = next_task.time_slice {
+            next_task.slice_end = tick_counter.wrapping_add(time_slice);
+        }
+
+        // A task woken before its wake up tick (e.g. by `wake`) has no deviation
+        // to record
+        #[cfg(feature = "stats")]
+        if let Some(release) = next_task.jitter_release.take() {
+            if tick_counter.wrapping_ge(release) {
+                let deviation = tick_counter.wrapping_sub(release);
+                next_task.jitter = Some(match next_task.jitter {
+                    Some(jitter) => Jitter {
+                        min: if deviation < jitter.min {
+                            deviation
+                        } else {
+                            jitter.min
+                        },
+                        max: if deviation > jitter.max {
+                            deviation
+                        } else {
+                            jitter.max
+                        },
+                        last: deviation,
+                    },
+                    None => Jitter {
+                        min: deviation,
+                        max: deviation,
+                        last: deviation,
+                    },
+                });
+            }
+        }
+
+        // Return the next task stack pointer
+        (next_task.stack_ptr, prev_task_id)
+    }
+
+    #[cfg(feature = "idle-stack-check")]
+    fn check_idle_stack(&mut self) {
+        let region = self.find_task(IDLE_TASK_ID).stack_region;
+        if let (Some(check), Some(hook), Some((bottom, size))) =
+            (self.stack_check, self.overflow_hook, region)
+        {
+            if !check(bottom, size) {
+                hook(IDLE_TASK_ID);
+            }
+        }
+    }
+
+    fn scheduler(&mut self) -> bool {
+        self.schedule(false)
+    }
+
+    fn schedule(&mut self, round_robin: bool) -> bool {
+        if !self.is_running {
+            return false;
+        }
+
+        // Update pending tasks, as they might be ready to run now
+        self.update_pending_tasks();
+
+        // Update next task to run
+        match self.find_highest_priority_runnable_task(round_robin) {
+            Some(next_task_id) => {
+                match self.curr_task_id {
+                    Some(curr_task_id) => {
+                        // Case 1: Current task should continue running
+                        if curr_task_id == next_task_id {
+                            self.next_task_id = None;
+                        // Case 2: Current task should be switched out
+                        } else {
+                            self.next_task_id = Some(next_task_id);
+                        }
+                    }
+                    // Case 3: There is no current task (starting the kernel)
+                    None => self.next_task_id = Some(next_task_id),
+                }
+            }
+            // All tasks pending, nothing to do
+            None => {
+                // The idle task is always runnable, unless it was never created
+                if !self.task_list.iter().any(|t| t.id == IDLE_TASK_ID) {
+                    match self.no_runnable_hook {
+                        Some(hook) => hook(),
+                        None => panic!("No runnable task and no idle task"),
+                    }
+                }
+
+                self.next_task_id = None
+            }
+        }
+
+        // Scheduler locked, the current task keeps running if it can
+        if self.sched_lock_count > 0 && self.next_task_id.is_some() {
+            let curr_task_runnable = match self.curr_task_id {
+                Some(id) => self.task_list.iter().any(|t| t.id == id && t.is_runnable()),
+                None => false,
+            };
+
+            if curr_task_runnable {
+                self.switch_deferred = true;
+                self.next_task_id = None;
+            }
+        }
+
+        self.next_task_id.is_some()
+    }
+
+    fn fire_alarms(&mut self) {
+        let tick_counter = self.tick_counter;
+
+        // Callbacks are taken with `take_fired_alarm`, outside the kernel
+        for alarm in self.alarm_list.iter_mut() {
+            if tick_counter.wrapping_ge(alarm.expiry) {
+                alarm.fired = true;
+            }
+        }
+    }
+
+    fn update_time_slice(&mut self) -> bool {
+        let tick_counter = self.tick_counter;
+        let curr_task = match self.curr_task_id {
+            Some(curr_task_id) => self.find_task(curr_task_id),
+            None => return false,
+        };
+
+        // Start a new time slice when the current one expires, in case the task
+        // keeps running because it has no peers
+        match curr_task.time_slice {
+            Some(time_slice) if tick_counter.wrapping_ge(curr_task.slice_end) => {
+                curr_task.slice_end = tick_counter.wrapping_add(time_slice);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn update_pending_tasks(&mut self) {
+        let mut mutex_wait_expired = false;
+
+        for (idx, task) in self.task_list.iter_mut().enumerate() {
+            if let Some(timeout) = task.pend.timeout() {
+                if self.tick_counter.wrapping_ge(timeout) {
+                    if let TaskPendReason::MutexWait(..) = task.pend {
+                        mutex_wait_expired = true;
+                    }
+
+                    task.timed_out = true;
+                    self.ready_list.set_ready(idx, task);
+                }
+            }
+        }
+
+        // A task that stopped waiting might have been boosting a mutex owner
+        if mutex_wait_expired {
+            self.update_mutex_owner_priorities();
+        }
+    }
+
+    fn check_deadline(&mut self, id: usize, next_release: TICK) {
+        let tick_counter = self.tick_counter;
+        let task = self.find_task(id);
+        let missed = match (task.release, task.deadline) {
+            (Some(release), Some(deadline)) => {
+                !release.wrapping_add(deadline).wrapping_ge(tick_counter)
+            }
+            _ => false,
+        };
+
+        task.release = Some(next_release);
+        if missed {
+            task.missed_deadlines += 1;
+            if let Some(hook) = self.deadline_hook {
+                hook(id);
+            }
+        }
+    }
+
+    fn check_watchdogs(&mut self) {
+        let tick_counter = self.tick_counter;
+        for task in self.task_list.iter_mut() {
+            let Some(timeout) = task.watchdog else {
+                continue;
+            };
+
+            if !task
+                .last_kick
+                .wrapping_add(timeout)
+                .wrapping_ge(tick_counter)
+            {
+                task.last_kick = tick_counter;
+                if let Some(hook) = self.watchdog_hook {
+                    hook(task.id);
+                }
+            }
+        }
+    }
+
+    fn update_mutex_owner_priorities(&mut self) {
+        for idx in 0..self.mutex_list.len() {
+            if let Some(owner) = self.mutex_list[idx].owner {
+                self.update_inherited_priority(owner);
+            }
+        }
+    }
+
+    fn set_base_priority(&mut self, id: usize, priority: usize) {
+        assert!(
+            id != IDLE_TASK_ID,
+            "The idle task priority cannot be changed"
+        );
+        assert!(
+            self.priority_in_range(priority),
+            "Task priority exceeds the maximum priority, see set_max_priority"
+        );
+
+        self.find_task(id).base_priority = priority;
+        self.update_inherited_priority(id);
+    }
+
+    fn update_inherited_priority(&mut self, id: usize) {
+        let mut task_id = id;
+
+        // Follow the chain of mutex owners, which is bounded by the number of tasks
+        for _ in 0..self.task_list.len() {
+            let task = self.find_task(task_id);
+            let mut priority = Self::mlfq_priority(task.base_priority, task.mlfq_level);
+            for mutex in self.mutex_list.iter().filter(|m| m.owner == Some(task_id)) {
+                if let Some(ceiling) = mutex.ceiling {
+                    priority = priority.min(ceiling);
+                    continue;
+                }
+
+                for task in self.task_list.iter() {
+                    if let TaskPendReason::MutexWait(mutex_id, _) = task.pend {
+                        if mutex_id == mutex.id {
+                            priority = priority.min(task.priority);
+                        }
+                    }
+                }
+            }
+
+            let task_idx = self.find_task_idx(task_id);
+            let task = &mut self.task_list[task_idx];
+            self.ready_list.set_priority(task, priority);
+
+            // If the task is waiting on a mutex itself, its owner inherits too
+            let mutex_id = match task.pend {
+                TaskPendReason::MutexWait(mutex_id, _) => mutex_id,
+                _ => break,
+            };
+
+            match self.find_mutex(mutex_id).owner {
+                Some(owner) => task_id = owner,
+                None => break,
+            }
+        }
+    }
+
+    fn find_highest_priority_runnable_task(&self, round_robin: bool) -> Option<usize> {
+        let ready_tasks = self.ready_list.iter().map(|idx| &self.task_list[idx]);
+
+        // Only the tasks at the highest priority level with a runnable task compete
+        #[cfg(feature = "priority-bitmap")]
+        let level = Some(
+            self.ready_list
+                .highest_level()
+                .unwrap_or(IDLE_TASK_PRIORITY),
+        );
+        #[cfg(not(feature = "priority-bitmap"))]
+        let level = None;
+
+        let next_task_id = self.select_runnable_task(ready_tasks, round_robin, level);
+
+        #[cfg(test)]
+        assert_eq!(
+            next_task_id,
+            self.select_runnable_task(
+                self.task_list.iter().filter(|t| t.is_runnable()),
+                round_robin,
+                None
+            ),
+            "Ready list out of step with the task list"
+        );
+
+        next_task_id
+    }
+
+    // The runnable `tasks` are visited in creation order
+    fn select_runnable_task<'a>(
+        &'a self,
+        tasks: impl Iterator<Item = &'a Task<SP, TICK>> + Clone,
+        round_robin: bool,
+        level: Option<usize>,
+    ) -> Option<usize> {
+        let mut highest_prio_runnable_task: Option<&Task<SP, TICK>> = None;
+        for task in tasks.clone() {
+            if level.is_none_or(|priority| task.priority == priority) {
+                highest_prio_runnable_task = match highest_prio_runnable_task {
+                    Some(other) => {
+                        if task < other || (task == other && self.wins_tie_break(task, other)) {
+                            Some(task)
+                        } else {
+                            Some(other)
+                        }
+                    }
+                    None => Some(task),
+                };
+            }
+        }
+
+        let highest_prio_runnable_task = highest_prio_runnable_task?;
+
+        // Tasks of the same priority do not preempt the current task
+        let curr_task = match self.curr_task_id {
+            Some(id) => self.task_list.iter().find(|t| t.id == id),
+            None => None,
+        };
+
+        let curr_task = match curr_task {
+            Some(task) if task.is_runnable() && task == highest_prio_runnable_task => task,
+            _ => return Some(highest_prio_runnable_task.id),
+        };
+
+        if !round_robin || curr_task.priority == IDLE_TASK_PRIORITY {
+            return Some(curr_task.id);
+        }
+
+        // Round-robin: Rotate to the task of the same priority that ran least recently
+        let mut next_task = curr_task;
+        for task in tasks {
+            if task == curr_task && task.last_run < next_task.last_run {
+                next_task = task;
+            }
+        }
+
+        Some(next_task.id)
+    }
+
+    // Tasks are visited in creation order, so `other` was created before `task`
+    fn wins_tie_break(&self, task: &Task<SP, TICK>, other: &Task<SP, TICK>) -> bool {
+        match self.tie_break {
+            TieBreak::ByCreationOrder => false,
+            TieBreak::ById => task.id < other.id,
+            TieBreak::ByLeastRecentlyRun => task.last_run < other.last_run,
+        }
+    }
+
+    fn check_task_exists(&self, id: Option<usize>) -> Result<(), TaskError> {
+        match id {
+            Some(id) if !self.task_list.iter().any(|t| t.id == id) => Err(TaskError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_not_idle_task(&self, id: Option<usize>) -> Result<(), TaskError> {
+        match id.or(self.curr_task_id) {
+            Some(IDLE_TASK_ID) => Err(TaskError::IdleTask),
+            _ => Ok(()),
+        }
+    }
+
+    fn blocking_task_id(&mut self) -> usize {
+        if let Some(check) = self.isr_check {
+            assert!(!check(), "Blocking API called from an interrupt handler");
+        }
+
+        // A new wait starts, so an earlier timeout no longer applies
+        let curr_task_id = self.curr_task_id.expect("Kernel not running");
+        let task = self.find_task(curr_task_id);
+        task.timed_out = false;
+        task.wait_result = WaitResult::Acquired;
+        curr_task_id
+    }
+
+    fn find_task(&mut self, id: usize) -> &mut Task<SP, TICK> {
+        self.task_list
+            .iter_mut()
+            .find(|t| t.id == id)
+            .expect("Task does not exist")
+    }
+
+    fn find_highest_priority_waiter(
+        &self,
+        is_waiting: impl Fn(&TaskPendReason<TICK>) -> bool,
+    ) -> Option<usize> {
+        let mut waiter: Option<&Task<SP, TICK>> = None;
+        for task in self.task_list.iter() {
+            if is_waiting(&task.pend) && waiter.is_none_or(|other| task < other) {
+                waiter = Some(task);
+            }
+        }
+
+        waiter.map(|task| task.id)
+    }
+
+    fn find_longest_waiter(
+        &self,
+        is_waiting: impl Fn(&TaskPendReason<TICK>) -> bool,
+    ) -> Option<usize> {
+        self.task_list
+            .iter()
+            .filter(|t| is_waiting(&t.pend))
+            .min_by_key(|t| t.pend_seq)
+            .map(|t| t.id)
+    }
+
+    fn make_ready(&mut self, id: usize) {
+        let task_idx = self.find_task_idx(id);
+        self.ready_list
+            .set_ready(task_idx, &mut self.task_list[task_idx]);
+    }
+
+    fn pend_task(&mut self, id: usize, pend: TaskPendReason<TICK>) {
+        // Being suspended by another task is not blocking
+        let blocks = self.curr_task_id == Some(id) && !matches!(pend, TaskPendReason::Suspended);
+
+        let task_idx = self.find_task_idx(id);
+        self.pend_counter += 1;
+        self.task_list[task_idx].pend_seq = self.pend_counter;
+        self.ready_list.set_state(
+            task_idx,
+            &mut self.task_list[task_idx],
+            TaskState::Pending,
+            pend,
+        );
+
+        if blocks {
+            self.mlfq_update(id, false);
+        }
+    }
+
+    fn mlfq_update(&mut self, id: usize, demote: bool) {
+        let levels = match self.sched_policy {
+            SchedPolicy::Priority => return,
+            SchedPolicy::Mlfq { levels } => levels,
+        };
+
+        let task = self.find_task(id);
+        let level = if demote {
+            (task.mlfq_level + 1).min(levels)
+        } else {
+            task.mlfq_level.saturating_sub(1)
+        };
+
+        if id != IDLE_TASK_ID && level != task.mlfq_level {
+            task.mlfq_level = level;
+            self.update_inherited_priority(id);
+        }
+    }
+
+    fn priority_in_range(&self, priority: usize) -> bool {
+        // The bitmap has no level for higher priority numbers
+        #[cfg(feature = "priority-bitmap")]
+        if priority >= crate::bitmap::PRIORITY_LEVELS {
+            return false;
+        }
+
+        self.max_priority.is_none_or(|max| priority <= max)
+    }
+
+    fn mlfq_priority(base_priority: usize, level: usize) -> usize {
+        // Demoted tasks stay above the idle task, and within the bitmap
+        #[cfg(feature = "priority-bitmap")]
+        let lowest = crate::bitmap::PRIORITY_LEVELS - 1;
+        #[cfg(not(feature = "priority-bitmap"))]
+        let lowest = IDLE_TASK_PRIORITY - 1;
+
+        base_priority
+            .saturating_add(level)
+            .min(lowest)
+            .max(base_priority)
+    }
+
+    fn find_semaphore(&mut self, id: usize) -> &mut Semaphore {
+        self.semaphore_list
+            .iter_mut()
+            .find(|s| s.id == id)
+            .expect("Semaphore does not exist")
+    }
+
+    #[cfg(feature = "deadlock-detection")]
+    fn would_deadlock(&self, owner: usize, curr_task_id: usize) -> bool {
+        // A recursive lock never blocks
+        if owner == curr_task_id {
+            return false;
+        }
+
+        // Follow the chain of mutex owners, which is bounded by the number of tasks
+        let mut task_id = owner;
+        for _ in 0..self.task_list.len() {
+            if task_id == curr_task_id {
+                return true;
+            }
+
+            let pend = self
+                .task_list
+                .iter()
+                .find(|t| t.id == task_id)
+                .map(|t| &t.pend);
+            let mutex_id = match pend {
+                Some(TaskPendReason::MutexWait(mutex_id, _)) => *mutex_id,
+                _ => return false,
+            };
+
+            match self
+                .mutex_list
+                .iter()
+                .find(|m| m.id == mutex_id)
+                .and_then(|m| m.owner)
+            {
+                Some(owner) => task_id = owner,
+                None => return false,
+            }
+        }
+
+        false
+    }
+
+    fn push_mutex(&mut self, id: usize, ceiling: Option<usize>) {
+        // Ensure the mutex ID is unique
+        for mutex in self.mutex_list.iter() {
+            assert!(mutex.id != id, "The mutex ID is not unique");
+        }
+
+        self.mutex_list
+            .push(Mutex {
+                id,
+                owner: None,
+                lock_count: 0,
+                ceiling,
+            })
+            .expect("Number of mutexes exceeds MAX_NUM_TASKS");
+    }
+
+    fn find_mutex(&mut self, id: usize) -> &mut Mutex {
+        self.mutex_list
+            .iter_mut()
+            .find(|m| m.id == id)
+            .expect("Mutex does not exist")
+    }
+
+    fn find_event(&mut self, id: usize) -> &mut EventGroup {
+        self.event_list
+            .iter_mut()
+            .find(|e| e.id == id)
+            .expect("Event group does not exist")
+    }
+
+    fn find_cond_waiter(&self, id: usize) -> Option<usize> {
+        self.find_highest_priority_waiter(|pend| match pend {
+            TaskPendReason::CondWait(cond_id, _) => *cond_id == id,
+            _ => false,
+        })
+    }
+
+    fn cond_wake(&mut self, task_id: usize) {
+        let mutex_id = match self.find_task(task_id).pend {
+            TaskPendReason::CondWait(_, mutex_id) => mutex_id,
+            _ => return,
+        };
+
+        // Lock the mutex on behalf of the task, or wait for it like `mutex_lock`
+        let mutex = self.find_mutex(mutex_id);
+        match mutex.owner {
+            None => {
+                mutex.owner = Some(task_id);
+                mutex.lock_count = 1;
+                self.make_ready(task_id);
+                self.update_inherited_priority(task_id);
+            }
+            Some(owner) => {
+                self.find_task(task_id).pend = TaskPendReason::MutexWait(mutex_id, None);
+                self.update_inherited_priority(owner);
+            }
+        }
+    }
+
+    fn find_cond(&mut self, id: usize) -> &mut CondVar {
+        self.cond_list
+            .iter_mut()
+            .find(|c| c.id == id)
+            .expect("Condition variable does not exist")
+    }
+
+    fn release_mutex(&mut self, id: usize, owner: Option<usize>) {
+        let waiter = self.find_highest_priority_waiter(|pend| match pend {
+            TaskPendReason::MutexWait(mutex_id, _) => *mutex_id == id,
+            _ => false,
+        });
+
+        let mutex = self.find_mutex(id);
+        mutex.owner = waiter;
+        mutex.lock_count = waiter.map_or(0, |_| 1);
+
+        // A deleted owner has no priority left to restore
+        if let Some(owner) = owner {
+            self.update_inherited_priority(owner);
+        }
+
+        if let Some(task_id) = waiter {
+            self.make_ready(task_id);
+            self.update_inherited_priority(task_id);
+        }
+    }
+
+    fn rwlock_grant(&mut self, id: usize) {
+        let rwlock = self.find_rwlock(id);
+        if rwlock.writer.is_some() || rwlock.readers > 0 {
+            return;
+        }
+
+        let waiter = self.find_highest_priority_waiter(|pend| match pend {
+            TaskPendReason::ReadWait(rwlock_id, _) | TaskPendReason::WriteWait(rwlock_id, _) => {
+                *rwlock_id == id
+            }
+            _ => false,
+        });
+
+        let Some(task_id) = waiter else {
+            return;
+        };
+
+        if let TaskPendReason::WriteWait(..) = self.find_task(task_id).pend {
+            self.find_rwlock(id).writer = Some(task_id);
+            self.make_ready(task_id);
+            return;
+        }
+
+        // Wake up all waiting readers together
+        let mut readers = 0;
+        for (idx, task) in self.task_list.iter_mut().enumerate() {
+            if let TaskPendReason::ReadWait(rwlock_id, _) = task.pend {
+                if rwlock_id == id {
+                    self.ready_list.set_ready(idx, task);
+                    task.read_locks
+                        .push(id)
+                        .expect("Number of read locks held exceeds MAX_READ_LOCKS");
+                    readers += 1;
+                }
+            }
+        }
+
+        self.find_rwlock(id).readers = readers;
+    }
+
+    fn add_read_lock(&mut self, task_id: usize, id: usize) {
+        self.find_task(task_id)
+            .read_locks
+            .push(id)
+            .expect("Number of read locks held exceeds MAX_READ_LOCKS");
+    }
+
+    fn release_rwlocks(&mut self, task_id: usize, read_locks: &[usize]) {
+        for idx in 0..self.rwlock_list.len() {
+            let rwlock = &mut self.rwlock_list[idx];
+            let reads = read_locks.iter().filter(|&&id| id == rwlock.id).count();
+            let write = rwlock.writer == Some(task_id);
+            if reads == 0 && !write {
+                continue;
+            }
+
+            rwlock.readers -= reads;
+            if write {
+                rwlock.writer = None;
+            }
+
+            let rwlock_id = rwlock.id;
+            self.rwlock_grant(rwlock_id);
+        }
+    }
+
+    fn find_rwlock(&mut self, id: usize) -> &mut RwLock {
+        self.rwlock_list
+            .iter_mut()
+            .find(|r| r.id == id)
+            .expect("Read-write lock does not exist")
+    }
+
+    fn barrier_release(&mut self, id: usize) {
+        self.find_barrier(id).waiting = 0;
+        for (idx, task) in self.task_list.iter_mut().enumerate() {
+            if let TaskPendReason::BarrierWait(barrier_id) = task.pend {
+                if barrier_id == id {
+                    self.ready_list.set_ready(idx, task);
+                }
+            }
+        }
+    }
+
+    // A suspended task no longer counts as waiting on its kernel object
+    fn pause_wait(&mut self, pend: TaskPendReason<TICK>) {
+        match pend {
+            TaskPendReason::BarrierWait(barrier_id) => {
+                self.find_barrier(barrier_id).waiting -= 1;
+            }
+            TaskPendReason::MutexWait(..) => self.update_mutex_owner_priorities(),
+            _ => (),
+        }
+    }
+
+    // A resumed task waits on its kernel object again, which may have become
+    // available while it was suspended
+    fn continue_wait(&mut self, id: usize) {
+        let pend = self.find_task(id).pend;
+        match pend {
+            TaskPendReason::SemWait(sem_id, _) if self.find_semaphore(sem_id).count > 0 => {
+                self.find_semaphore(sem_id).count -= 1;
+                self.make_ready(id);
+            }
+            TaskPendReason::MutexWait(mutex_id, _) => {
+                let mutex = self.find_mutex(mutex_id);
+                let owner = match mutex.owner {
+                    Some(owner) => owner,
+                    None => {
+                        mutex.owner = Some(id);
+                        mutex.lock_count = 1;
+                        self.make_ready(id);
+                        id
+                    }
+                };
+                self.update_inherited_priority(owner);
+            }
+            TaskPendReason::EventWait {
+                id: event_id,
+                mask,
+                wait_all,
+                ..
+            } if self.find_event(event_id).is_set(mask, wait_all) => self.make_ready(id),
+            TaskPendReason::MailboxWait(mailbox_id, _) => {
+                if let Some(item) = self.find_mailbox(mailbox_id).item.take() {
+                    self.make_ready(id);
+                    self.find_task(id).message = Some(item);
+                }
+            }
+            TaskPendReason::ReadWait(rwlock_id, _) | TaskPendReason::WriteWait(rwlock_id, _) => {
+                self.rwlock_grant(rwlock_id);
+            }
+            TaskPendReason::BarrierWait(barrier_id) => {
+                let barrier = self.find_barrier(barrier_id);
+                barrier.waiting += 1;
+                if barrier.waiting >= barrier.count {
+                    self.barrier_release(barrier_id);
+                }
+            }
+            TaskPendReason::Join(join_id) if !self.task_list.iter().any(|t| t.id == join_id) => {
+                self.make_ready(id);
+            }
+            TaskPendReason::WorkWait if !self.work_queue.is_empty() => self.make_ready(id),
+            TaskPendReason::NotifyWait(_) if self.find_task(id).notification.is_some() => {
+                self.make_ready(id);
+            }
+            _ => (),
+        }
+    }
+
+    fn find_barrier(&mut self, id: usize) -> &mut Barrier {
+        self.barrier_list
+            .iter_mut()
+            .find(|b| b.id == id)
+            .expect("Barrier does not exist")
+    }
+
+    fn find_mailbox(&mut self, id: usize) -> &mut Mailbox {
+        self.mailbox_list
+            .iter_mut()
+            .find(|m| m.id == id)
+            .expect("Mailbox does not exist")
+    }
+
+    fn find_task_idx(&self, id: usize) -> usize {
+        self.task_list
+            .iter()
+            .position(|t| t.id == id)
+            .expect("Task does not exist")
+    }
+}
+
+/// Version of the binary format written by `Kernel::serialize_state`
+///
+/// # Note
+///
+/// Version 2 added the pend reasons of kernel object waits (`3` to `13`), and
+/// `u64::MAX` as the pend data of a Sleep with no wake up tick
+pub const STATE_FORMAT_VERSION: u8 = 2;
+
+/// Serialized size of the kernel state, excluding tasks
+const STATE_HEADER_SIZE: usize = 32;
+
+/// Serialized size of a single task
+const STATE_TASK_SIZE: usize = 26;
+
+impl<SP, TICK, const MAX_NUM_TASKS: usize> Kernel<SP, TICK, MAX_NUM_TASKS>
+where
+    SP: Copy + Debug,
+    TICK: Tick + Into<u64>,
+{
+    /// Get the number of ticks left before a sleeping task wakes up
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: Task to query
+    ///
+    /// # Returns
+    ///
+    /// Ticks until the wake up tick, `0` if it has already been reached,
+    /// `u64::MAX` if the task sleeps until woken up, or `None` if the task is
+    /// not sleeping or the `id` does not correspond to a task
+    ///
+    /// # Note
+    ///
+    /// Only covers `sleep` and `sleep_until`, a timeout on a kernel object does
+    /// not count as sleeping
+    pub fn sleep_remaining(&self, id: usize) -> Option<u64> {
+        let task = self.task_list.iter().find(|t| t.id == id)?;
+        match task.pend {
+            TaskPendReason::Sleep(Some(wakeup_tick)) => {
+                if self.tick_counter.wrapping_ge(wakeup_tick) {
+                    Some(0)
+                } else {
+                    Some(wakeup_tick.wrapping_sub(self.tick_counter).into())
+                }
+            }
+            TaskPendReason::Sleep(None) => Some(u64::MAX),
+            _ => None,
+        }
+    }
+
+    /// Serialize the kernel state into a versioned binary snapshot
+    ///
+    /// # Arguments
+    ///
+    /// * `buf`: Buffer to write the snapshot into
+    ///
+    /// # Returns
+    ///
+    /// Number of bytes written, or `0` if `buf` is too small
+    ///
+    /// # Format
+    ///
+    /// All multi-byte fields are little-endian. An optional task ID is a 1 byte
+    /// tag (`0`: `None`, `1`: `Some`) followed by an 8 byte ID. The header is:
+    ///
+    /// * Format version, `STATE_FORMAT_VERSION` (1 byte)
+    /// * Kernel running (1 byte)
+    /// * Global tick counter (8 bytes)
+    /// * Current task ID (optional task ID, 9 bytes)
+    /// * Next task ID (optional task ID, 9 bytes)
+    /// * Number of tasks (4 bytes)
+    ///
+    /// Followed by each task in the task list:
+    ///
+    /// * Task ID (8 bytes)
+    /// * Task priority, including inherited priority (8 bytes)
+    /// * Task state (1 byte, `0`: Pending, `1`: Ready, `2`: Running)
+    /// * Task pend reason (1 byte, `0`: Not pending, `1`: Suspended, `2`: Sleep,
+    ///   `3`: Semaphore wait, `4`: Mutex wait, `5`: Event wait, `6`: Join,
+    ///   `7`: Mailbox wait, `8`: Read wait, `9`: Write wait, `10`: Barrier wait,
+    ///   `11`: Condition wait, `12`: Work wait, `13`: Notify wait)
+    /// * Task pend data (8 bytes, the wake up tick for Sleep or `u64::MAX` if
+    ///   there is none, the object ID for Semaphore, Mutex, Event, Mailbox,
+    ///   Read, Write, Barrier, or Condition wait, the task ID for Join, or `0`)
+    pub fn serialize_state(&self, buf: &mut [u8]) -> usize {
+        let len = STATE_HEADER_SIZE + self.task_list.len() * STATE_TASK_SIZE;
+        if buf.len() < len {
+            return 0;
+        }
+
+        let mut pos = 0;
+        let mut write = |bytes: &[u8]| {
+            buf[pos..pos + bytes.len()].copy_from_slice(bytes);
+            pos += bytes.len();
+        };
+
+        write(&[STATE_FORMAT_VERSION, self.is_running as u8]);
+        write(&self.tick_counter.into().to_le_bytes());
+
+        for task_id in [self.curr_task_id, self.next_task_id] {
+            write(&[task_id.is_some() as u8]);
+            write(&(task_id.unwrap_or(0) as u64).to_le_bytes());
+        }
+
+        write(&(self.task_list.len() as u32).to_le_bytes());
+
+        for task in self.task_list.iter() {
+            let state: u8 = match task.state {
+                TaskState::Pending => 0,
+                TaskState::Ready => 1,
+                TaskState::Running => 2,
+            };
+
+            let (pend, pend_data): (u8, u64) = match task.pend {
+                TaskPendReason::NotPending => (0, 0),
+                TaskPendReason::Suspended => (1, 0),
+                TaskPendReason::Sleep(timeout) => (2, timeout.map_or(u64::MAX, Into::into)),
+                TaskPendReason::SemWait(id, _) => (3, id as u64),
+                TaskPendReason::MutexWait(id, _) => (4, id as u64),
+                TaskPendReason::EventWait { id, .. } => (5, id as u64),
+                TaskPendReason::Join(id) => (6, id as u64),
+                TaskPendReason::MailboxWait(id, _) => (7, id as u64),
+                TaskPendReason::ReadWait(id, _) => (8, id as u64),
+                TaskPendReason::WriteWait(id, _) => (9, id as u64),
+                TaskPendReason::BarrierWait(id) => (10, id as u64),
+                TaskPendReason::CondWait(id, _) => (11, id as u64),
+                TaskPendReason::WorkWait => (12, 0),
+                TaskPendReason::NotifyWait(_) => (13, 0),
+            };
+
+            write(&(task.id as u64).to_le_bytes());
+            write(&(task.priority as u64).to_le_bytes());
+            write(&[state, pend]);
+            write(&pend_data.to_le_bytes());
+        }
+
+        len
+    }
+}
+
+impl<SP, TICK, const MAX_NUM_TASKS: usize> Default for Kernel<SP, TICK, MAX_NUM_TASKS>
+where
+    SP: Copy + Debug,
+    TICK: Tick,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+// The original tests compare against `true` and `false`
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    fn setup() -> Kernel<u32, u64, 2> {
+        let mut kernel = Kernel::new();
+
+        let mut task0_stack: [u8; 128] = [0; 128];
+        kernel.create(0, 99, task0_stack.as_mut_ptr() as u32);
+
+        let mut task1_stack: [u8; 128] = [0; 128];
+        kernel.create(1, 100, task1_stack.as_mut_ptr() as u32);
+
+        kernel.start();
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, None);
+        assert_eq!(kernel.get_current_task(), 0);
+
+        kernel
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TaskSnapshot {
+        id: u64,
+        priority: u64,
+        state: u8,
+        pend: u8,
+        pend_data: u64,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct StateSnapshot {
+        version: u8,
+        is_running: bool,
+        tick: u64,
+        curr_task_id: Option<u64>,
+        next_task_id: Option<u64>,
+        tasks: std::vec::Vec<TaskSnapshot>,
+    }
+
+    fn decode_state(buf: &[u8]) -> StateSnapshot {
+        let u64_at = |pos: usize| u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+        let task_id_at = |pos: usize| match buf[pos] {
+            0 => None,
+            _ => Some(u64_at(pos + 1)),
+        };
+
+        let num_tasks = u32::from_le_bytes(buf[28..32].try_into().unwrap()) as usize;
+        let tasks = (0..num_tasks)
+            .map(|n| {
+                let pos = STATE_HEADER_SIZE + n * STATE_TASK_SIZE;
+                TaskSnapshot {
+                    id: u64_at(pos),
+                    priority: u64_at(pos + 8),
+                    state: buf[pos + 16],
+                    pend: buf[pos + 17],
+                    pend_data: u64_at(pos + 18),
+                }
+            })
+            .collect();
+
+        StateSnapshot {
+            version: buf[0],
+            is_running: buf[1] != 0,
+            tick: u64_at(2),
+            curr_task_id: task_id_at(10),
+            next_task_id: task_id_at(19),
+            tasks,
+        }
+    }
+
+    #[test]
+    fn test_sleep() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.sleep(2), true);
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, Some(1));
+
+        let _ = kernel.handle_context_switch(None);
+
+        assert_eq!(kernel.curr_task_id, Some(1));
+        assert_eq!(kernel.next_task_id, None);
+        assert_eq!(kernel.get_current_task(), 1);
+
+        assert_eq!(kernel.tick_update(3), true);
+        assert_eq!(kernel.get_current_tick(), 3);
+        assert_eq!(kernel.curr_task_id, Some(1));
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_sleep_until() {
+        let mut kernel = setup();
+
+        // Task 0 runs for 1 tick before sleeping, a relative sleep drifts
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(4));
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.get_current_tick(), 6);
+        let _ = kernel.handle_context_switch(None);
+
+        // Same again with an absolute sleep, waking on the period boundary
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.sleep_until(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.get_current_tick(), 10);
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_sleep_until_past() {
+        let mut kernel = setup();
+
+        assert!(!kernel.tick_update(5));
+        assert!(!kernel.sleep_until(5));
+        assert!(!kernel.sleep_until(3));
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Blocking API called from an interrupt handler")]
+    fn test_isr_check() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        fn in_isr() -> bool {
+            true
+        }
+
+        // Non-blocking APIs are allowed
+        kernel.set_isr_check(Some(in_isr));
+        assert!(!kernel.sem_give(0));
+        assert!(!kernel.resume(1));
+
+        let _ = kernel.sleep(1);
+    }
+
+    #[test]
+    fn test_alarm() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FIRED: AtomicUsize = AtomicUsize::new(0);
+
+        fn callback(id: usize) {
+            assert_eq!(id, 3);
+            FIRED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn tick(kernel: &mut Kernel<u32, u64, 2>, elapsed: u64) {
+            assert!(!kernel.tick_update(elapsed));
+            while let Some((id, callback)) = kernel.take_fired_alarm() {
+                callback(id);
+            }
+        }
+
+        let mut kernel = setup();
+        kernel.alarm_set(3, 2, callback);
+        assert_eq!(kernel.get_next_wakeup(), Some(2));
+
+        // The current task keeps running while the alarm is armed
+        tick(&mut kernel, 1);
+        assert_eq!(FIRED.load(Ordering::Relaxed), 0);
+
+        // The callback is only called once taken
+        assert!(!kernel.tick_update(1));
+        assert_eq!(FIRED.load(Ordering::Relaxed), 0);
+        assert_eq!(kernel.get_next_wakeup(), None);
+        let (id, fired) = kernel.take_fired_alarm().unwrap();
+        fired(id);
+        assert_eq!(FIRED.load(Ordering::Relaxed), 1);
+        assert!(kernel.take_fired_alarm().is_none());
+
+        // Fires only once
+        for _ in 0..5 {
+            tick(&mut kernel, 1);
+        }
+        assert_eq!(FIRED.load(Ordering::Relaxed), 1);
+        assert_eq!(kernel.get_next_wakeup(), None);
+
+        // Re-arming restarts the delay
+        kernel.alarm_set(3, 3, callback);
+        tick(&mut kernel, 2);
+        kernel.alarm_set(3, 3, callback);
+        tick(&mut kernel, 2);
+        assert_eq!(FIRED.load(Ordering::Relaxed), 1);
+        tick(&mut kernel, 1);
+        assert_eq!(FIRED.load(Ordering::Relaxed), 2);
+
+        kernel.alarm_set(3, 1, callback);
+        assert!(kernel.alarm_cancel(3));
+        assert!(!kernel.alarm_cancel(3));
+        tick(&mut kernel, 1);
+        assert_eq!(FIRED.load(Ordering::Relaxed), 2);
+
+        // Cancelling a fired alarm before it is taken drops its callback
+        kernel.alarm_set(3, 1, callback);
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.alarm_cancel(3));
+        assert!(kernel.take_fired_alarm().is_none());
+        assert_eq!(FIRED.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_defer_wakes_server() {
+        let mut kernel = setup();
+
+        fn in_isr() -> bool {
+            true
+        }
+
+        assert!(kernel.work_wait());
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::WorkWait));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+
+        // Deferred from an interrupt handler, waking the server task
+        kernel.set_isr_check(Some(in_isr));
+        assert!(kernel.defer(7));
+        kernel.set_isr_check(None);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert_eq!(kernel.take_work(), Some(7));
+        assert_eq!(kernel.take_work(), None);
+    }
+
+    #[test]
+    fn test_defer_fifo() {
+        let mut kernel = setup();
+
+        assert!(!kernel.defer(3));
+        assert!(!kernel.defer(1));
+
+        // Work is already waiting, so the server does not block
+        assert!(!kernel.work_wait());
+        assert_eq!(kernel.take_work(), Some(3));
+        assert!(!kernel.defer(2));
+        assert_eq!(kernel.take_work(), Some(1));
+        assert_eq!(kernel.take_work(), Some(2));
+        assert_eq!(kernel.take_work(), None);
+    }
+
+    #[test]
+    fn test_u32_tick_wrap() {
+        let mut kernel: Kernel<u32, u32, 3> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+        kernel.tick_counter = u32::MAX - 2;
+
+        // Task 0 wakes up after the wrap, task 1 before it
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 2);
+        assert_eq!(kernel.get_next_wakeup(), Some(u32::MAX - 1));
+
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_next_wakeup(), Some(2));
+
+        // Counter goes through u32::MAX, 0, 1 without waking task 0
+        for _ in 0..3 {
+            assert!(!kernel.tick_update(1));
+        }
+        assert_eq!(kernel.get_current_tick(), 1);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Sleep));
+
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+    }
+
+    #[test]
+    fn test_u64_tick_wrap() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+        kernel.tick_counter = u64::MAX - 1;
+
+        // Task 0 sleeps until tick 1, task 1 waits on the semaphore until tick 2
+        assert!(kernel.sleep(3));
+        let _ = kernel.handle_context_switch(None);
+
+        // There is no idle task to switch to while both tasks wait
+        kernel.set_no_runnable_hook(Some(|| {}));
+        assert!(!kernel.sem_take(0, Some(4)));
+
+        for tick in [u64::MAX, 0] {
+            assert!(!kernel.tick_update(1));
+            assert_eq!(kernel.get_current_tick(), tick);
+            assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Sleep));
+        }
+
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::SemWait));
+
+        assert!(!kernel.tick_update(1));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::NotPending));
+    }
+
+    #[test]
+    fn test_sleep_until_wrap() {
+        let mut kernel = setup();
+        kernel.tick_counter = u64::MAX;
+
+        // A wake up tick just past the wrap is in the future, not the past
+        assert!(kernel.sleep_until(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+        assert!(kernel.tick_update(1));
+    }
+
+    #[test]
+    fn test_sleep_zero() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(0, 1, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.start();
+
+        // Yields to the peer instead of pending
+        assert!(kernel.sleep(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Ready));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::NotPending));
+
+        // Also for a past wake up tick
+        assert!(!kernel.tick_update(5));
+        assert!(kernel.sleep_until(3));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+    }
+
+    #[test]
+    fn test_is_switch_pending() {
+        let mut kernel = setup();
+        assert!(!kernel.is_switch_pending());
+
+        assert!(kernel.sleep(1));
+        assert!(kernel.is_switch_pending());
+        assert_eq!(kernel.next_task_id, Some(1));
+
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.is_switch_pending());
+    }
+
+    #[test]
+    fn test_scheduler_lock() {
+        let mut kernel = setup();
+
+        assert!(kernel.sleep(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+
+        // Task 0 wakes up, but the switch is deferred until fully unlocked
+        kernel.scheduler_lock();
+        kernel.scheduler_lock();
+        assert!(!kernel.tick_update(3));
+        assert_eq!(kernel.next_task_id, None);
+        assert!(!kernel.scheduler_unlock());
+        assert!(kernel.scheduler_unlock());
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_scheduler_unlock_no_deferred_switch() {
+        let mut kernel = setup();
+
+        kernel.scheduler_lock();
+        assert!(!kernel.tick_update(1));
+        assert!(!kernel.scheduler_unlock());
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Scheduler not locked")]
+    fn test_scheduler_unlock_not_locked() {
+        let mut kernel = setup();
+
+        let _ = kernel.scheduler_unlock();
+    }
+
+    #[test]
+    fn test_try_create() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        assert_eq!(kernel.try_create(0, 1, 0), Ok(false));
+        assert_eq!(kernel.try_create(0, 2, 0), Err(CreateError::DuplicateId));
+        assert_eq!(kernel.try_create(1, 2, 0), Ok(false));
+        assert_eq!(
+            kernel.try_create(2, 3, 0),
+            Err(CreateError::CapacityExceeded)
+        );
+
+        let _ = kernel.start();
+        assert_eq!(kernel.try_create(0, 0, 0), Err(CreateError::DuplicateId));
+    }
+
+    #[test]
+    fn test_max_priority() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.set_max_priority(Some(10));
+
+        // The maximum itself is allowed
+        assert_eq!(kernel.try_create(0, 10, 0), Ok(false));
+        assert_eq!(
+            kernel.try_create(1, 11, 0),
+            Err(CreateError::PriorityOutOfRange)
+        );
+        assert_eq!(kernel.get_task_state(1), None);
+        assert!(!kernel.set_priority(0, 0));
+
+        // No check by default
+        kernel.set_max_priority(None);
+        assert_eq!(kernel.try_create(1, 11, 0), Ok(false));
+    }
+
+    #[test]
+    #[should_panic(expected = "Task priority exceeds the maximum priority, see set_max_priority")]
+    fn test_max_priority_set_priority() {
+        let mut kernel = setup();
+        kernel.set_max_priority(Some(100));
+        let _ = kernel.set_priority(0, 101);
+    }
+
+    #[test]
+    #[should_panic(expected = "The maximum priority must be below IDLE_TASK_PRIORITY")]
+    fn test_max_priority_idle() {
+        let mut kernel = setup();
+        kernel.set_max_priority(Some(IDLE_TASK_PRIORITY));
+    }
+
+    #[cfg(feature = "priority-bitmap")]
+    #[test]
+    fn test_priority_bitmap_range() {
+        let mut kernel = setup();
+
+        // Priorities the bitmap has no level for are rejected, not a panic
+        assert_eq!(
+            kernel.try_create(2, crate::bitmap::PRIORITY_LEVELS, 0),
+            Err(CreateError::PriorityOutOfRange)
+        );
+        assert_eq!(kernel.get_task_state(2), None);
+
+        // A ceiling never lowers the owner priority, so any ceiling is allowed
+        kernel.mutex_create_with_ceiling(0, 300);
+        assert!(!kernel.mutex_lock(0, None));
+        assert_eq!(kernel.find_task(0).priority, 99);
+        assert!(!kernel.mutex_unlock(0));
+    }
+
+    #[cfg(feature = "priority-bitmap")]
+    #[test]
+    #[should_panic(expected = "Task priority exceeds the maximum priority, see set_max_priority")]
+    fn test_priority_bitmap_set_priority() {
+        let mut kernel = setup();
+        let _ = kernel.set_priority(0, 300);
+    }
+
+    #[test]
+    fn test_create_from_task() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        let mut parent_stack: [u8; 128] = [0; 128];
+        kernel.create(0, 10, parent_stack.as_mut_ptr() as u32);
+        kernel.start();
+        assert_eq!(kernel.get_current_task(), 0);
+
+        // A lower priority child waits for the parent to block
+        let mut child1_stack: [u8; 128] = [0; 128];
+        assert!(!kernel.create(1, 20, child1_stack.as_mut_ptr() as u32));
+        assert_eq!(kernel.next_task_id, None);
+
+        // A higher priority child preempts the parent right away
+        let mut child2_stack: [u8; 128] = [0; 128];
+        let child2_stack_ptr = child2_stack.as_mut_ptr() as u32;
+        assert!(kernel.create(2, 5, child2_stack_ptr));
+        assert_eq!(kernel.next_task_id, Some(2));
+        assert_eq!(kernel.handle_context_switch(Some(0x1000)), child2_stack_ptr);
+        assert_eq!(kernel.get_current_task(), 2);
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Ready));
+    }
+
+    #[test]
+    #[should_panic(expected = "The task ID is not unique")]
+    fn test_create_duplicate_id() {
+        let mut kernel = setup();
+
+        let _ = kernel.create(0, 1, 0);
+    }
+
+    #[test]
+    fn test_create_reserved_id() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        assert_eq!(
+            kernel.try_create(IDLE_TASK_ID, 0, 0),
+            Err(CreateError::ReservedId)
+        );
+
+        let _ = kernel.create_idle(0);
+        assert_eq!(
+            kernel.try_create(IDLE_TASK_ID, 0, 0),
+            Err(CreateError::ReservedId)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The task ID is reserved for the idle task")]
+    fn test_create_reserved_id_panics() {
+        let mut kernel = setup();
+
+        let _ = kernel.create(IDLE_TASK_ID, IDLE_TASK_PRIORITY, 0);
+    }
+
+    #[test]
+    fn test_try_task_operations_not_found() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.try_delete(Some(2)), Err(TaskError::NotFound));
+        assert_eq!(kernel.try_suspend(Some(2)), Err(TaskError::NotFound));
+        assert_eq!(kernel.try_resume(2), Err(TaskError::NotFound));
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    fn test_try_task_operations() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.try_suspend(None), Ok(true));
+        assert_eq!(kernel.try_resume(0), Ok(false));
+        assert_eq!(kernel.try_delete(Some(1)), Ok(false));
+        assert_eq!(kernel.try_resume(1), Err(TaskError::NotFound));
+    }
+
+    #[test]
+    fn test_dump_state() {
+        let mut kernel = setup();
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+
+        let mut dump = std::string::String::new();
+        kernel
+            .dump_state(&mut dump, |id| if id == 0 { Some(64) } else { None })
+            .unwrap();
+
+        assert_eq!(
+            dump,
+            "task 0 priority 99 state Pending pend Sleep stack 64\n\
+             task 1 priority 100 state Running pend NotPending stack unknown\n"
+        );
+    }
+
+    #[test]
+    fn test_for_each_task() {
+        let mut kernel = setup();
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+
+        let mut tasks = std::vec::Vec::new();
+        kernel.for_each_task(|info| tasks.push(*info));
+
+        assert_eq!(kernel.task_count(), 2);
+        assert_eq!(
+            tasks,
+            [
+                TaskInfo {
+                    id: 0,
+                    name: None,
+                    priority: 99,
+                    state: TaskState::Pending,
+                    pend: PendReason::Sleep,
+                },
+                TaskInfo {
+                    id: 1,
+                    name: None,
+                    priority: 100,
+                    state: TaskState::Running,
+                    pend: PendReason::NotPending,
+                },
+            ]
+        );
+
+        let _ = kernel.delete(Some(0));
+        let mut ids = std::vec::Vec::new();
+        kernel.for_each_task(|info| ids.push(info.id));
+        assert_eq!(kernel.task_count(), 1);
+        assert_eq!(ids, [1]);
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let mut kernel = setup();
+
+        // Task 1 becomes higher priority than Task 0
+        assert!(kernel.set_priority(1, 10));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+
+        // Task 0 becomes the highest priority again
+        assert!(kernel.set_priority(0, 5));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert!(!kernel.set_priority(0, 0));
+    }
+
+    #[test]
+    fn test_assign_rate_monotonic() {
+        let mut kernel: Kernel<u32, u64, 4> = Kernel::new();
+
+        for id in 0..4 {
+            let _ = kernel.create(id, 50, 0);
+        }
+
+        let _ = kernel.assign_rate_monotonic(&[(0, 100), (1, 10), (2, 50), (3, 10)]);
+
+        let mut priorities = std::vec::Vec::new();
+        kernel.for_each_task(|info| priorities.push((info.id, info.priority)));
+        assert_eq!(priorities, [(0, 3), (1, 0), (2, 2), (3, 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The idle task priority cannot be changed")]
+    fn test_set_priority_idle_task() {
+        let mut kernel: Kernel<u32, u64, 1> = Kernel::new();
+
+        let _ = kernel.create_idle(0);
+        let _ = kernel.set_priority(IDLE_TASK_ID, 0);
+    }
+
+    #[test]
+    fn test_cpu_usage() {
+        let mut kernel = setup();
+
+        // Task 0 runs for 3 ticks, then sleeps while Task 1 runs for 2 ticks
+        assert!(!kernel.tick_update(3));
+        assert!(kernel.sleep(2));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+
+        assert_eq!(kernel.cpu_usage(0), Some((3, 5)));
+        assert_eq!(kernel.cpu_usage(1), Some((2, 5)));
+        assert_eq!(kernel.cpu_usage(2), None);
+
+        // Ticks counted in one update, as done by tickless idle
+        assert!(!kernel.tick_update(4));
+        assert_eq!(kernel.cpu_usage(0), Some((7, 9)));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new_with_tie_break(TieBreak::ById);
+        kernel.set_sched_policy(SchedPolicy::Mlfq { levels: 2 });
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create_idle(0);
+        kernel.sem_create(0);
+        kernel.set_tick_hook(Some(|_| {}));
+        assert_eq!(kernel.reset(), Ok(()));
+
+        // Same as a newly initialized kernel
+        assert_eq!(kernel.task_count(), 0);
+        assert!(kernel.semaphore_list.is_empty());
+        assert!(kernel.tick_hook.is_none());
+        assert_eq!(kernel.tie_break, TieBreak::ById);
+        assert_eq!(kernel.sched_policy, SchedPolicy::Priority);
+        assert_eq!(kernel.get_current_tick(), 0);
+
+        // The same IDs can be used again
+        assert!(!kernel.create(0, 0, 0));
+        kernel.sem_create(0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 0);
+        assert_eq!(kernel.reset(), Err(ResetError::Running));
+        assert_eq!(kernel.task_count(), 1);
+    }
+
+    #[test]
+    fn test_idle_task_stats() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+        kernel.create(0, 0, 0);
+        kernel.create_idle(0);
+        kernel.set_stack_region(IDLE_TASK_ID, 0x2000_0000, 256);
+        let _ = kernel.start();
+
+        // Task 0 runs for 1 tick, then the idle task runs for 3 ticks
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.sleep(3));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+
+        assert_eq!(kernel.cpu_usage(IDLE_TASK_ID), Some((3, 4)));
+        assert_eq!(kernel.cpu_usage(0), Some((1, 4)));
+        assert_eq!(
+            kernel.get_stack_region(IDLE_TASK_ID),
+            Some((0x2000_0000, 256))
+        );
+        assert_eq!(kernel.get_task_state(IDLE_TASK_ID), Some(TaskState::Ready));
+
+        let mut idle_found = false;
+        kernel.for_each_task(|t| idle_found |= t.id == IDLE_TASK_ID);
+        assert!(idle_found);
+    }
+
+    #[test]
+    fn test_task_name() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+
+        let _ = kernel.create_named(0, 0, 0, "sensor");
+        let _ = kernel.create(1, 1, 0);
+
+        assert_eq!(kernel.get_task_name(0), Some("sensor"));
+        assert_eq!(kernel.get_task_name(1), None);
+        assert_eq!(kernel.get_task_name(2), None);
+
+        kernel.set_task_name(1, "logger");
+        assert_eq!(kernel.get_task_name(1), Some("logger"));
+    }
+
+    #[test]
+    fn test_stack_region() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.get_stack_region(0), None);
+        kernel.set_stack_region(0, 0x2000_0000, 1024);
+        assert_eq!(kernel.get_stack_region(0), Some((0x2000_0000, 1024)));
+        assert_eq!(kernel.get_stack_region(2), None);
+    }
+
+    #[test]
+    fn test_task_entry() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.get_task_entry(0), None);
+        kernel.set_task_entry(0, 0x0800_0101);
+        assert_eq!(kernel.get_task_entry(0), Some(0x0800_0101));
+        assert_eq!(kernel.get_task_entry(2), None);
+    }
+
+    #[test]
+    fn test_restart() {
+        let mut kernel = setup();
+
+        // Task 0 sleeps, Task 1 runs
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(Some(0x100));
+
+        // Task 0 restarts with a fresh stack and preempts Task 1
+        assert!(kernel.restart(0, 0x200));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::NotPending));
+        assert_eq!(kernel.handle_context_switch(Some(0x300)), 0x200);
+        assert_eq!(kernel.get_current_task(), 0);
+    }
+
+    #[test]
+    fn test_tls() {
+        let mut kernel = setup();
+        let mut value0 = 0u32;
+        let mut value1 = 0u32;
+        let ptr0 = &mut value0 as *mut u32 as *mut ();
+        let ptr1 = &mut value1 as *mut u32 as *mut ();
+
+        // Each task has its own slots
+        assert!(kernel.tls_get(0).is_null());
+        kernel.tls_set(0, ptr0);
+        kernel.tls_set(TLS_SLOTS - 1, ptr0);
+        assert!(kernel.suspend(None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tls_get(0).is_null());
+        kernel.tls_set(0, ptr1);
+        assert_eq!(kernel.tls_get(0), ptr1);
+
+        kernel.make_ready(0);
+        assert!(kernel.reschedule());
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.tls_get(0), ptr0);
+        assert_eq!(kernel.tls_get(TLS_SLOTS - 1), ptr0);
+
+        // Restarting a task clears its slots
+        assert!(kernel.restart(0, 0x200));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tls_get(0).is_null());
+        assert!(kernel.tls_get(TLS_SLOTS - 1).is_null());
+
+        // A task created with the ID of a deleted task starts with clear slots
+        assert!(kernel.suspend(None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.tls_get(0), ptr1);
+        assert!(!kernel.delete(Some(0)));
+        assert!(!kernel.create(0, 101, 0));
+        assert!(kernel.suspend(None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert!(kernel.tls_get(0).is_null());
+    }
+
+    #[test]
+    #[should_panic(expected = "TLS slot index exceeds TLS_SLOTS")]
+    fn test_tls_index_too_big() {
+        let mut kernel = setup();
+
+        kernel.tls_set(TLS_SLOTS, core::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_restart_current_task() {
+        let mut kernel = setup();
+
+        // The context of the current task is discarded, not saved
+        assert!(kernel.restart(0, 0x200));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.handle_context_switch(Some(0x100)), 0x200);
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Running));
+    }
+
+    #[test]
+    #[should_panic(expected = "A task owning a mutex cannot be restarted")]
+    fn test_restart_mutex_owner() {
+        let mut kernel = setup();
+
+        kernel.mutex_create(0);
+        assert!(!kernel.mutex_lock(0, None));
+        let _ = kernel.restart(0, 0);
+    }
+
+    #[test]
+    fn test_task_uses_fpu() {
+        let mut kernel = setup();
+
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        kernel.mark_fpu_used();
+
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.task_uses_fpu(0), Some(false));
+        assert_eq!(kernel.task_uses_fpu(1), Some(true));
+        assert_eq!(kernel.task_uses_fpu(2), None);
+
+        let _ = kernel.delete(None);
+        kernel.mark_fpu_used();
+        assert_eq!(kernel.task_uses_fpu(1), Some(true));
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn test_periodic_jitter() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        let _ = kernel.create_idle(0);
+        kernel.create(0, 0, 0);
+        kernel.create(1, 1, 0);
+        kernel.start();
+        assert_eq!(kernel.periodic_jitter(1), None);
+        assert_eq!(kernel.periodic_jitter(2), None);
+
+        // Task 0 sleeps until tick 8, task 1 runs every 4 ticks
+        assert!(kernel.sleep_until(8));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep_until(4));
+        let _ = kernel.handle_context_switch(None);
+
+        // Task 1 runs on time
+        for _ in 0..3 {
+            assert!(!kernel.tick_update(1));
+        }
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+        assert_eq!(
+            kernel.periodic_jitter(1),
+            Some(Jitter {
+                min: 0,
+                max: 0,
+                last: 0
+            })
+        );
+
+        // Task 0 wakes up with task 1 and runs for 3 ticks first
+        assert!(kernel.sleep_until(8));
+        let _ = kernel.handle_context_switch(None);
+        for _ in 0..3 {
+            assert!(!kernel.tick_update(1));
+        }
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        for _ in 0..3 {
+            assert!(!kernel.tick_update(1));
+        }
+        assert!(kernel.sleep(100));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+        assert_eq!(
+            kernel.periodic_jitter(1),
+            Some(Jitter {
+                min: 0,
+                max: 3,
+                last: 3
+            })
+        );
+
+        // Cleared by a restart
+        let _ = kernel.restart(1, 0);
+        assert_eq!(kernel.periodic_jitter(1), None);
+    }
+
+    #[test]
+    fn test_stack_watch() {
+        let mut kernel = setup();
+        assert!(kernel.is_current_task(0));
+        assert!(!kernel.is_current_task(1));
+        assert_eq!(kernel.task_stack_watch(0), Some(false));
+        assert_eq!(kernel.task_stack_watch(2), None);
+
+        kernel.set_stack_watch(1, true);
+        assert_eq!(kernel.task_stack_watch(0), Some(false));
+        assert_eq!(kernel.task_stack_watch(1), Some(true));
+
+        // Kept across a restart
+        let _ = kernel.restart(1, 0);
+        assert_eq!(kernel.task_stack_watch(1), Some(true));
+
+        kernel.set_stack_watch(1, false);
+        assert_eq!(kernel.task_stack_watch(1), Some(false));
+    }
+
+    #[test]
+    #[should_panic(expected = "Task does not exist")]
+    fn test_set_stack_watch_invalid() {
+        let mut kernel = setup();
+        kernel.set_stack_watch(2, true);
+    }
+
+    #[test]
+    fn test_join() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+
+        // Tasks 0 and 1 both wait for Task 2
+        assert!(kernel.join(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Join));
+        assert!(kernel.join(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 2);
+
+        // Task 2 completes, waking up both joiners
+        assert!(kernel.delete(None));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+    }
+
+    #[test]
+    fn test_join_deleted_task() {
+        let mut kernel = setup();
+
+        assert!(!kernel.delete(Some(1)));
+        assert!(!kernel.join(1));
+        assert_eq!(kernel.next_task_id, None);
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Running));
+    }
+
+    #[test]
+    #[should_panic(expected = "A task cannot join itself")]
+    fn test_join_self() {
+        let mut kernel = setup();
+
+        let _ = kernel.join(0);
+    }
+
+    #[test]
+    fn test_get_task_state() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Running));
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+        assert_eq!(kernel.get_task_state(2), None);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::NotPending));
+        assert_eq!(kernel.get_pend_reason(2), None);
+
+        let _ = kernel.suspend(Some(1));
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Pending));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::Suspended));
+
+        let _ = kernel.resume(1);
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::NotPending));
+
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Pending));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Sleep));
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Running));
+    }
+
+    #[test]
+    fn test_get_next_wakeup() {
+        let mut kernel: Kernel<u32, u64, 4> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.create_idle(0);
+        kernel.sem_create(0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_next_wakeup(), None);
+        assert!(!kernel.is_idle());
+
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_next_wakeup(), Some(10));
+
+        assert!(kernel.sem_take(0, Some(4)));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_next_wakeup(), Some(4));
+
+        // Waiting forever and suspending have no wake up tick
+        assert!(kernel.suspend(None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_next_wakeup(), Some(4));
+        assert!(kernel.is_idle());
+
+        // Wake up early from a give, leaving only the sleeping task
+        assert!(kernel.sem_give(0));
+        assert!(!kernel.is_idle());
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_next_wakeup(), Some(10));
+
+        // Fast-forward the ticks skipped while idle
+        assert!(kernel.suspend(None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(10));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.get_next_wakeup(), None);
+    }
+
+    #[test]
+    fn test_delete_hook() {
+        static DELETE_HOOK_LAST: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+        fn delete_hook(id: usize) {
+            DELETE_HOOK_LAST.store(id, Ordering::Relaxed);
+        }
+
+        let mut kernel = setup();
+        kernel.set_delete_hook(Some(delete_hook));
+
+        // The hook is only called once taken
+        assert!(!kernel.delete(Some(1)));
+        assert_eq!(DELETE_HOOK_LAST.load(Ordering::Relaxed), usize::MAX);
+        assert_eq!(kernel.get_task_state(1), None);
+        let (id, hook) = kernel.take_deleted_task().unwrap();
+        hook(id);
+        assert_eq!(DELETE_HOOK_LAST.load(Ordering::Relaxed), 1);
+        assert!(kernel.take_deleted_task().is_none());
+
+        // The ID is free again once the hook runs
+        let mut task1_stack: [u8; 128] = [0; 128];
+        kernel.create(1, 100, task1_stack.as_mut_ptr() as u32);
+        kernel.set_delete_hook(None);
+        assert!(!kernel.delete(Some(1)));
+        assert!(kernel.take_deleted_task().is_none());
+    }
+
+    #[test]
+    fn test_delete_hook_order() {
+        fn delete_hook(_: usize) {}
+
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+        kernel.set_delete_hook(Some(delete_hook));
+
+        // Taken in the order the tasks were deleted, including the current task
+        assert!(!kernel.delete(Some(2)));
+        assert!(kernel.delete(None));
+        assert_eq!(kernel.take_deleted_task().map(|(id, _)| id), Some(2));
+        assert_eq!(kernel.take_deleted_task().map(|(id, _)| id), Some(0));
+        assert!(kernel.take_deleted_task().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "idle-stack-check")]
+    fn test_idle_stack_check() {
+        static OVERFLOW_HOOK_LAST: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+        fn overflow_hook(id: usize) {
+            OVERFLOW_HOOK_LAST.store(id, Ordering::Relaxed);
+        }
+
+        fn stack_check(bottom: usize, size: usize) -> bool {
+            let stack = unsafe { std::slice::from_raw_parts(bottom as *const u8, size) };
+            crate::stack::stack_canary_check(stack)
+        }
+
+        // The stack is only accessed through its address, like on a target
+        let idle_stack = std::boxed::Box::leak(std::boxed::Box::new([0u8; 64])).as_mut_ptr();
+        crate::stack::stack_canary_write(unsafe { std::slice::from_raw_parts_mut(idle_stack, 64) });
+
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+        let _ = kernel.create_idle(0);
+        kernel.create(0, 0, 0);
+        kernel.set_stack_region(IDLE_TASK_ID, idle_stack as usize, 64);
+        kernel.set_stack_check(Some(stack_check));
+        kernel.set_overflow_hook(Some(overflow_hook));
+        kernel.start();
+
+        // An intact canary does not call the hook
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(OVERFLOW_HOOK_LAST.load(Ordering::Relaxed), usize::MAX);
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+
+        // The overflow is caught on the next switch to the idle task
+        unsafe { *idle_stack = !*idle_stack };
+        assert!(kernel.sleep(1));
+        assert_eq!(OVERFLOW_HOOK_LAST.load(Ordering::Relaxed), usize::MAX);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(OVERFLOW_HOOK_LAST.load(Ordering::Relaxed), IDLE_TASK_ID);
+    }
+
+    #[test]
+    fn test_missed_deadlines() {
+        static DEADLINE_HOOK_LAST: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+        fn deadline_hook(id: usize) {
+            DEADLINE_HOOK_LAST.store(id, Ordering::Relaxed);
+        }
+
+        let mut kernel = setup();
+        kernel.set_deadline_hook(Some(deadline_hook));
+        kernel.set_deadline(0, Some(2));
+
+        // The first period is not checked
+        assert!(!kernel.tick_update(5));
+        assert!(kernel.sleep_until(10));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.missed_deadlines(0), Some(0));
+
+        // Task 0 wakes at tick 10 and sleeps again within its deadline
+        assert!(kernel.tick_update(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.sleep_until(20));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.missed_deadlines(0), Some(0));
+
+        // Task 0 is delayed past its deadline, the next wake up is already due
+        assert!(kernel.tick_update(8));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(3));
+        assert!(!kernel.sleep_until(20));
+        assert_eq!(kernel.missed_deadlines(0), Some(1));
+        assert_eq!(DEADLINE_HOOK_LAST.load(Ordering::Relaxed), 0);
+        assert_eq!(kernel.missed_deadlines(1), Some(0));
+        assert_eq!(kernel.missed_deadlines(2), None);
+    }
+
+    #[test]
+    fn test_watchdog() {
+        static WATCHDOG_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static WATCHDOG_HOOK_LAST: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+        fn watchdog_hook(id: usize) {
+            WATCHDOG_HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+            WATCHDOG_HOOK_LAST.store(id, Ordering::Relaxed);
+        }
+
+        let mut kernel = setup();
+        kernel.set_watchdog_hook(Some(watchdog_hook));
+        kernel.set_watchdog(0, Some(5));
+        kernel.set_watchdog(1, Some(5));
+
+        // Both tasks kick in time, the timeout itself is still in time
+        assert!(!kernel.tick_update(4));
+        kernel.task_kick(0);
+        kernel.task_kick(1);
+        assert!(!kernel.tick_update(5));
+        assert_eq!(WATCHDOG_HOOK_CALLS.load(Ordering::Relaxed), 0);
+
+        // Task 1 misses its kick
+        kernel.task_kick(0);
+        assert!(!kernel.tick_update(1));
+        assert_eq!(WATCHDOG_HOOK_CALLS.load(Ordering::Relaxed), 1);
+        assert_eq!(WATCHDOG_HOOK_LAST.load(Ordering::Relaxed), 1);
+
+        // The watchdog is armed again after firing
+        kernel.task_kick(0);
+        assert!(!kernel.tick_update(5));
+        assert_eq!(WATCHDOG_HOOK_CALLS.load(Ordering::Relaxed), 1);
+        kernel.task_kick(0);
+        assert!(!kernel.tick_update(1));
+        assert_eq!(WATCHDOG_HOOK_CALLS.load(Ordering::Relaxed), 2);
+        assert_eq!(WATCHDOG_HOOK_LAST.load(Ordering::Relaxed), 1);
+
+        // No more faults once the watchdog is stopped
+        kernel.set_watchdog(1, None);
+        kernel.task_kick(0);
+        assert!(!kernel.tick_update(5));
+        assert_eq!(WATCHDOG_HOOK_CALLS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_tick_hook() {
+        static TICK_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static TICK_HOOK_LAST: AtomicU64 = AtomicU64::new(0);
+
+        fn tick_hook(tick: u64) {
+            TICK_HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+            TICK_HOOK_LAST.store(tick, Ordering::Relaxed);
+        }
+
+        let mut kernel = setup();
+        kernel.set_tick_hook(Some(tick_hook));
+
+        for _ in 0..3 {
+            assert!(!kernel.tick_update(1));
+        }
+
+        assert!(!kernel.tick_update(5));
+        assert_eq!(TICK_HOOK_CALLS.load(Ordering::Relaxed), 4);
+        assert_eq!(TICK_HOOK_LAST.load(Ordering::Relaxed), 8);
+
+        kernel.set_tick_hook(None);
+        assert!(!kernel.tick_update(1));
+        assert_eq!(TICK_HOOK_CALLS.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_switch_hook() {
+        static SWITCHES: std::sync::Mutex<std::vec::Vec<(Option<usize>, usize)>> =
+            std::sync::Mutex::new(std::vec::Vec::new());
+
+        fn switch_hook(from: Option<usize>, to: usize) {
+            SWITCHES.lock().unwrap().push((from, to));
+        }
+
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+        kernel.set_switch_hook(Some(switch_hook));
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.start();
+
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.delete(None));
+        let _ = kernel.handle_context_switch(None);
+
+        assert_eq!(
+            *SWITCHES.lock().unwrap(),
+            [(None, 0), (Some(0), 1), (Some(1), 0), (None, 1)]
+        );
+    }
+
+    #[test]
+    fn test_handle_context_switch_traced() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        let _ = kernel.create(0, 0, 0x100);
+        let _ = kernel.create(1, 1, 0x200);
+        let _ = kernel.create(2, 2, 0x300);
+        assert_eq!(kernel.start(), 0x100);
+
+        assert!(kernel.sleep(2));
+        assert_eq!(
+            kernel.handle_context_switch_traced(Some(0x110)),
+            (0x200, Some(0))
+        );
+
+        assert!(kernel.suspend(None));
+        assert_eq!(
+            kernel.handle_context_switch_traced(Some(0x210)),
+            (0x300, Some(1))
+        );
+
+        assert!(kernel.tick_update(2));
+        assert_eq!(
+            kernel.handle_context_switch_traced(Some(0x310)),
+            (0x110, Some(2))
+        );
+
+        // A deleted task is not reported as switched out
+        assert!(kernel.delete(None));
+        assert_eq!(kernel.handle_context_switch_traced(None), (0x310, None));
+        assert_eq!(kernel.get_current_task(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "No runnable task and no idle task")]
+    fn test_no_runnable_task() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.start();
+
+        assert!(!kernel.delete(Some(1)));
+        let _ = kernel.delete(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No tasks created")]
+    fn test_start_no_tasks() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.start();
+    }
+
+    #[test]
+    #[should_panic(expected = "No tasks created other than the idle task")]
+    fn test_start_idle_task_only() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+    }
+
+    #[test]
+    fn test_no_runnable_hook() {
+        static NO_RUNNABLE_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        fn no_runnable_hook() {
+            NO_RUNNABLE_HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+        kernel.set_no_runnable_hook(Some(no_runnable_hook));
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.start();
+        assert_eq!(NO_RUNNABLE_HOOK_CALLS.load(Ordering::Relaxed), 0);
+
+        assert!(!kernel.sleep(1));
+        assert_eq!(kernel.next_task_id, None);
+        assert_eq!(NO_RUNNABLE_HOOK_CALLS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The idle task cannot be deleted")]
+    fn test_delete_idle_task() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        let _ = kernel.delete(Some(IDLE_TASK_ID));
+    }
+
+    #[test]
+    #[should_panic(expected = "The idle task cannot be suspended")]
+    fn test_suspend_idle_task() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        let _ = kernel.suspend(Some(IDLE_TASK_ID));
+    }
+
+    #[test]
+    fn test_try_idle_task_operations() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        assert_eq!(
+            kernel.try_delete(Some(IDLE_TASK_ID)),
+            Err(TaskError::IdleTask)
+        );
+        assert_eq!(
+            kernel.try_suspend(Some(IDLE_TASK_ID)),
+            Err(TaskError::IdleTask)
+        );
+
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.try_delete(None), Err(TaskError::IdleTask));
+        assert_eq!(kernel.try_suspend(None), Err(TaskError::IdleTask));
+        assert_eq!(kernel.get_current_task(), IDLE_TASK_ID);
+    }
+
+    #[test]
+    fn test_suspend_current_task() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.suspend(None), true);
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, Some(1));
+    }
+
+    #[test]
+    fn test_suspend_other_task() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.suspend(Some(1)), false);
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    fn test_resume() {
+        let mut kernel = setup();
+
+        let _ = kernel.suspend(None);
+        let _ = kernel.handle_context_switch(None);
+
+        assert_eq!(kernel.resume(0), true);
+        assert_eq!(kernel.curr_task_id, Some(1));
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_resume_preempts() {
+        let mut kernel = setup();
+
+        let _ = kernel.suspend(None);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+
+        // Resuming a higher priority task switches to it without a tick
+        assert!(kernel.resume(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+        assert_eq!(kernel.get_current_tick(), 0);
+
+        // Resuming a lower priority task does not
+        assert!(!kernel.suspend(Some(1)));
+        assert!(!kernel.resume(1));
+        assert_eq!(kernel.next_task_id, None);
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+    }
+
+    #[test]
+    fn test_sleep_remaining() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.sleep_remaining(0), None);
+        assert_eq!(kernel.sleep_remaining(7), None);
+
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.sleep_remaining(0), Some(10));
+        assert_eq!(kernel.sleep_remaining(1), None);
+
+        let _ = kernel.tick_update(4);
+        assert_eq!(kernel.sleep_remaining(0), Some(6));
+        let _ = kernel.tick_update(5);
+        assert_eq!(kernel.sleep_remaining(0), Some(1));
+
+        // Woken up, no longer sleeping
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.sleep_remaining(0), None);
+    }
+
+    #[test]
+    fn test_wake() {
+        let mut kernel = setup();
+
+        // Not sleeping, nothing to do
+        assert!(!kernel.wake(1));
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+
+        assert!(kernel.sleep(100));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
+        let _ = kernel.tick_update(10);
+
+        // Woken up early, preempting the lower priority task
+        assert!(kernel.wake(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::NotPending));
+        assert_eq!(kernel.sleep_remaining(0), None);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+
+        // The old wake up tick has no effect
+        assert!(!kernel.tick_update(90));
+        assert_eq!(kernel.get_current_task(), 0);
+    }
+
+    #[test]
+    fn test_sleep_forever() {
+        let mut kernel = setup();
+
+        assert!(kernel.sleep_forever());
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Sleep));
+        assert_eq!(kernel.sleep_remaining(0), Some(u64::MAX));
+        assert_eq!(kernel.get_next_wakeup(), None);
+
+        // No amount of ticks wakes the task
+        for _ in 0..4 {
+            assert!(!kernel.tick_update(u64::MAX / 3));
+            assert_eq!(kernel.get_current_task(), 1);
+        }
+
+        assert!(kernel.wake(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+    }
+
+    #[test]
+    fn test_sleep_max_delay() {
+        let mut kernel: Kernel<u32, u16, 2> = Kernel::new();
+        kernel.create(0, 0, 0);
+        kernel.create_idle(0);
+        let _ = kernel.start();
+        let _ = kernel.tick_update(5);
+
+        // The maximum delay sleeps until woken, rather than waking up a tick
+        // early after the tick counter wraps
+        assert!(kernel.sleep(u16::MAX));
+        let _ = kernel.handle_context_switch(None);
+        for _ in 0..(3 * u16::MAX as u32) {
+            assert!(!kernel.tick_update(1));
+        }
+        assert_eq!(kernel.get_current_task(), IDLE_TASK_ID);
+
+        assert!(kernel.wake(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+    }
+
+    #[test]
+    fn test_wake_not_sleeping() {
+        let mut kernel = setup();
+
+        assert!(!kernel.suspend(Some(1)));
+        assert!(!kernel.wake(1));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::Suspended));
+        let _ = kernel.resume(1);
+
+        kernel.sem_create(0);
+        assert!(kernel.sem_take(0, Some(10)));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.wake(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::SemWait));
+    }
+
+    #[test]
+    fn test_ready_count() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+
+        kernel.create(0, 0, 0);
+        kernel.create(1, 1, 0);
+        kernel.create_idle(0);
+        let _ = kernel.start();
+        assert_eq!(kernel.ready_count(), 2);
+
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(Some(0));
+        assert_eq!(kernel.ready_count(), 1);
+
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(Some(0));
+        assert_eq!(kernel.ready_count(), 0);
+
+        assert!(kernel.tick_update(5));
+        let _ = kernel.handle_context_switch(Some(0));
+        assert_eq!(kernel.ready_count(), 1);
+
+        assert!(!kernel.tick_update(5));
+        assert_eq!(kernel.ready_count(), 2);
+    }
+
+    #[test]
+    fn test_suspend_all_except() {
+        let mut kernel: Kernel<u32, u64, 4> = Kernel::new();
+
+        kernel.create(0, 0, 0);
+        kernel.create(1, 1, 0);
+        kernel.create(2, 2, 0);
+        kernel.create_idle(0);
+        let _ = kernel.start();
+
+        // Task 0 sleeps and task 1 waits on a semaphore, leaving task 2 running
+        kernel.sem_create(0);
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(Some(0));
+        assert!(kernel.sem_take(0, None));
+        let _ = kernel.handle_context_switch(Some(0));
+        assert_eq!(kernel.get_current_task(), 2);
+
+        assert!(!kernel.suspend_all_except(2));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Suspended));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::Suspended));
+        assert_eq!(kernel.get_task_state(IDLE_TASK_ID), Some(TaskState::Ready));
+
+        // The sleep of task 0 expires while it is suspended
+        assert!(!kernel.tick_update(20));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Suspended));
+
+        // Only the idle task runs once task 2 blocks
+        assert!(kernel.sleep(100));
+        let _ = kernel.handle_context_switch(Some(0));
+        assert_eq!(kernel.get_current_task(), IDLE_TASK_ID);
+
+        assert!(kernel.resume_all());
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Ready));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::SemWait));
+        assert_eq!(kernel.get_pend_reason(2), Some(PendReason::Sleep));
+    }
+
+    #[test]
+    fn test_suspend_sem_wait() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        // Task 0 waits again once resumed, and takes a later give
+        assert!(kernel.sem_take(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+        assert!(!kernel.resume(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::SemWait));
+        assert!(kernel.sem_give(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+
+        // A give while Task 0 is suspended is taken once it is resumed
+        assert!(kernel.sem_take(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+        assert!(!kernel.sem_give(0));
+        assert_eq!(kernel.sem_get_count(0), 1);
+        assert!(kernel.resume(0));
+        assert_eq!(kernel.sem_get_count(0), 0);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+    }
+
+    #[test]
+    fn test_suspend_timeout_expired() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        // The timeout expires while Task 0 is suspended
+        assert!(kernel.sem_take(0, Some(3)));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+        assert!(!kernel.tick_update(5));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Suspended));
+
+        // The wait ends once Task 0 is resumed
+        assert!(kernel.resume(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::TimedOut);
+    }
+
+    #[test]
+    fn test_suspend_mutex_wait() {
+        let mut kernel = setup();
+        kernel.mutex_create(0);
+
+        // Task 1 locks the mutex, Task 0 waits on it
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(kernel.tick_update(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.mutex_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.find_task(1).priority, 99);
+
+        // The suspended task stops boosting the owner and is not handed the
+        // mutex, it takes the mutex once resumed
+        assert!(!kernel.suspend(Some(0)));
+        assert_eq!(kernel.find_task(1).priority, 100);
+        assert!(!kernel.mutex_unlock(0));
+        assert_eq!(kernel.find_mutex(0).owner, None);
+        assert!(kernel.resume(0));
+        assert_eq!(kernel.find_mutex(0).owner, Some(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+        assert!(!kernel.mutex_unlock(0));
+    }
+
+    #[test]
+    fn test_suspend_rwlock_wait() {
+        let mut kernel = setup_rwlock();
+
+        // Task 2 writes, Task 0 waits to read and Task 1 waits to write
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.rwlock_write_lock(0, None));
+        assert!(kernel.tick_update(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_read_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_write_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+
+        // Neither suspended task is handed the lock
+        assert!(!kernel.suspend(Some(0)));
+        assert!(!kernel.suspend(Some(1)));
+        assert!(!kernel.rwlock_write_unlock(0));
+        assert_eq!(kernel.find_rwlock(0).writer, None);
+
+        // Each takes the lock once resumed
+        assert!(kernel.resume(1));
+        assert_eq!(kernel.find_rwlock(0).writer, Some(1));
+        let _ = kernel.resume(0);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::ReadWait));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_write_unlock(0));
+        assert_eq!(kernel.find_rwlock(0).readers, 1);
+    }
+
+    #[test]
+    fn test_suspend_mailbox_wait() {
+        let mut kernel = setup();
+        kernel.mailbox_create(0);
+
+        assert!(kernel.mailbox_fetch(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+
+        // The item posted while Task 0 is suspended is fetched once resumed
+        assert!(!kernel.mailbox_post(0, 7));
+        assert!(kernel.resume(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_message(), Some(7));
+    }
+
+    #[test]
+    fn test_suspend_event_wait() {
+        let mut kernel = setup();
+        kernel.event_create(0);
+
+        assert!(kernel.event_wait(0, 0b1, true, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+        assert!(!kernel.resume(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::EventWait));
+
+        // Flags set while Task 0 is suspended satisfy the wait once resumed
+        assert!(!kernel.suspend(Some(0)));
+        assert!(!kernel.event_set(0, 0b1));
+        assert!(kernel.resume(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+    }
+
+    #[test]
+    fn test_suspend_barrier_wait() {
+        let mut kernel = setup_rwlock();
+        kernel.barrier_create(0, 3);
+
+        // Tasks 0 and 1 arrive, Task 0 is suspended and no longer counts
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), IDLE_TASK_ID);
+
+        // Task 0 arrives again once resumed, releasing the barrier
+        assert!(kernel.resume(0));
+        assert_eq!(kernel.next_task_id, Some(0));
+        for id in 0..3 {
+            assert_ne!(kernel.get_task_state(id), Some(TaskState::Pending));
+        }
+    }
+
+    #[test]
+    fn test_suspend_notify_wait() {
+        let mut kernel = setup();
+
+        assert!(kernel.notify_wait(None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.suspend(Some(0)));
+
+        // A notification while Task 0 is suspended does not wake it
+        assert!(!kernel.notify(0, 5));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::Suspended));
+        assert!(kernel.resume(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_notification(), Some(5));
+    }
+
+    #[test]
+    fn test_resume_not_suspended() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        // Resuming a task that is only blocked does not end its wait
+        assert!(kernel.sem_take(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.resume(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::SemWait));
+    }
+
+    #[test]
+    fn test_resume_all_after_resume() {
+        let mut kernel = setup();
+
+        let _ = kernel.suspend(Some(1));
+        assert!(!kernel.suspend_all_except(0));
+        assert!(!kernel.resume(1));
+
+        // Task 1 was resumed explicitly, its earlier suspension is not restored
+        assert!(!kernel.resume_all());
+        assert_eq!(kernel.get_task_state(1), Some(TaskState::Ready));
+    }
+
+    #[test]
+    fn test_reschedule() {
+        let mut kernel = setup();
+
+        let _ = kernel.suspend(None);
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.reschedule());
+
+        kernel.make_ready(0);
+
+        assert!(kernel.reschedule());
+        assert_eq!(kernel.curr_task_id, Some(1));
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_take_switch_decision() {
+        let mut kernel = setup();
+        assert_eq!(kernel.take_switch_decision(), None);
+
+        let _ = kernel.sleep(2);
+        assert_eq!(kernel.take_switch_decision(), Some(1));
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, None);
+
+        let mut other_kernel = setup();
+        let _ = other_kernel.sleep(2);
+        let _ = other_kernel.handle_context_switch(None);
+        assert_eq!(other_kernel.curr_task_id, Some(1));
+    }
+
+    #[test]
+    fn test_delete_current_task() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.delete(None), true);
+        assert_eq!(kernel.curr_task_id, None);
+        assert_eq!(kernel.next_task_id, Some(1));
+    }
+
+    #[test]
+    fn test_delete_current_task_by_id() {
+        let mut kernel = setup();
+
+        assert_eq!(kernel.delete(Some(0)), true);
+        assert_eq!(kernel.curr_task_id, None);
+        assert_eq!(kernel.next_task_id, Some(1));
+    }
+
+    #[test]
+    fn test_delete_other_task() {
+        let mut kernel = setup();
+
+        let _ = kernel.suspend(None);
+        let _ = kernel.handle_context_switch(None);
+
+        assert_eq!(kernel.delete(Some(0)), false);
+        assert_eq!(kernel.curr_task_id, Some(1));
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    fn test_serialize_state() {
+        let mut kernel = setup();
+
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        let _ = kernel.tick_update(1);
+
+        let mut buf = [0; 128];
+        let len = kernel.serialize_state(&mut buf);
+        assert_eq!(len, STATE_HEADER_SIZE + 2 * STATE_TASK_SIZE);
+
+        let expected = StateSnapshot {
+            version: STATE_FORMAT_VERSION,
+            is_running: true,
+            tick: 1,
+            curr_task_id: Some(1),
+            next_task_id: None,
+            tasks: std::vec![
+                TaskSnapshot {
+                    id: 0,
+                    priority: 99,
+                    state: 0,
+                    pend: 2,
+                    pend_data: 2,
+                },
+                TaskSnapshot {
+                    id: 1,
+                    priority: 100,
+                    state: 2,
+                    pend: 0,
+                    pend_data: 0,
+                },
+            ],
+        };
+
+        assert_eq!(decode_state(&buf[..len]), expected);
+    }
+
+    #[test]
+    fn test_serialize_state_small_buffer() {
+        let kernel = setup();
+
+        let mut buf = [0; STATE_HEADER_SIZE];
+        assert_eq!(kernel.serialize_state(&mut buf), 0);
+    }
+
+    #[test]
+    fn test_tie_break_by_creation_order() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(5, 1, 0);
+        let _ = kernel.create(3, 1, 0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 5);
+    }
+
+    #[test]
+    fn test_tie_break_by_id() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new_with_tie_break(TieBreak::ById);
+
+        let _ = kernel.create(5, 1, 0);
+        let _ = kernel.create(3, 1, 0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 3);
+    }
+
+    #[test]
+    fn test_tie_break_by_least_recently_run() {
+        let mut kernel: Kernel<u32, u64, 4> =
+            Kernel::new_with_tie_break(TieBreak::ByLeastRecentlyRun);
+
+        let _ = kernel.create(0, 1, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 1, 0);
+        let _ = kernel.create(3, 0, 0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 3);
+
+        // Task 3 blocks repeatedly, each time handing over to an equal priority task
+        for id in [0, 1, 2, 0] {
+            assert!(kernel.sleep(1));
+            assert_eq!(kernel.next_task_id, Some(id));
+            let _ = kernel.handle_context_switch(None);
+
+            assert!(kernel.tick_update(1));
+            assert_eq!(kernel.next_task_id, Some(3));
+            let _ = kernel.handle_context_switch(None);
+        }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+
+        let _ = kernel.create_with_time_slice(0, 1, 0, Some(1));
+        let _ = kernel.create_with_time_slice(1, 1, 0, Some(1));
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 0);
+
+        for id in [1, 0, 1, 0] {
+            assert!(kernel.tick_update(1));
+            assert_eq!(kernel.next_task_id, Some(id));
+            let _ = kernel.handle_context_switch(None);
+        }
+    }
+
+    #[test]
+    fn test_yield_task() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+
+        let _ = kernel.create(0, 1, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 0);
+
+        for id in [1, 0] {
+            assert!(kernel.yield_task());
+            assert_eq!(kernel.next_task_id, Some(id));
+            let _ = kernel.handle_context_switch(None);
+            assert_eq!(kernel.get_current_task(), id);
+        }
+    }
+
+    #[test]
+    fn test_mlfq_demotion() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.set_sched_policy(SchedPolicy::Mlfq { levels: 2 });
+
+        let _ = kernel.create_with_time_slice(0, 1, 0, Some(2));
+        let _ = kernel.create(1, 2, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        // Task 1 waits for work, Task 0 is CPU-bound
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep_forever());
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+
+        // Using up a time slice demotes Task 0 to the priority of Task 1
+        assert!(!kernel.tick_update(1));
+        assert!(!kernel.tick_update(1));
+        assert_eq!(kernel.find_task(0).priority, 2);
+        assert_eq!(kernel.find_task(0).base_priority, 1);
+
+        // Task 1 has work again, and now preempts Task 0 once it is demoted
+        // further
+        assert!(!kernel.wake(1));
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.find_task(0).priority, 3);
+        assert_eq!(kernel.next_task_id, Some(1));
+        let _ = kernel.handle_context_switch(None);
+
+        // Demoted by at most `levels`
+        assert!(kernel.sleep_forever());
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(2));
+        assert_eq!(kernel.find_task(0).priority, 3);
+    }
+
+    #[test]
+    fn test_mlfq_promotion() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+        kernel.set_sched_policy(SchedPolicy::Mlfq { levels: 2 });
+
+        let _ = kernel.create_with_time_slice(0, 1, 0, Some(1));
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        assert!(!kernel.tick_update(1));
+        assert!(!kernel.tick_update(1));
+        assert_eq!(kernel.find_task(0).priority, 3);
+
+        // Each time the task blocks it is promoted, up to its own priority
+        for priority in [2, 1, 1] {
+            assert!(kernel.sleep(1));
+            assert_eq!(kernel.find_task(0).priority, priority);
+            let _ = kernel.handle_context_switch(None);
+            assert!(kernel.tick_update(1));
+            let _ = kernel.handle_context_switch(None);
+        }
+
+        // Being suspended is not blocking
+        assert!(!kernel.tick_update(1));
+        assert_eq!(kernel.find_task(0).priority, 2);
+        assert!(kernel.suspend(None));
+        assert_eq!(kernel.find_task(0).priority, 2);
+    }
+
+    #[test]
+    fn test_mlfq_default_priority() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create_with_time_slice(0, 1, 0, Some(1));
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        // Strict priority scheduling never demotes
+        assert!(!kernel.tick_update(5));
+        assert_eq!(kernel.find_task(0).priority, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Scheduling policy must be set before the kernel starts")]
+    fn test_mlfq_set_after_start() {
+        let mut kernel = setup();
+        kernel.set_sched_policy(SchedPolicy::Mlfq { levels: 2 });
+    }
+
+    #[test]
+    fn test_yield_task_no_peers() {
+        let mut kernel = setup();
+
+        assert!(!kernel.yield_task());
+        assert_eq!(kernel.next_task_id, None);
+        assert_eq!(kernel.get_current_task(), 0);
+    }
+
+    #[test]
+    fn test_time_slice() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create_with_time_slice(0, 1, 0, Some(5));
+        let _ = kernel.create_with_time_slice(1, 1, 0, Some(5));
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 0);
+
+        for _ in 0..2 {
+            for _ in 0..4 {
+                assert!(!kernel.tick_update(1));
+            }
+
+            let curr_task_id = kernel.get_current_task();
+            assert!(kernel.tick_update(1));
+            assert_eq!(kernel.next_task_id, Some(1 - curr_task_id));
+            let _ = kernel.handle_context_switch(None);
+        }
+    }
+
+    #[test]
+    fn test_set_time_slice() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create_with_time_slice(0, 1, 0, Some(2));
+        let _ = kernel.create_with_time_slice(1, 1, 0, Some(2));
+        let _ = kernel.start();
+
+        // Lengthened from the next time slice, the current one still runs out
+        assert!(!kernel.tick_update(1));
+        kernel.set_time_slice(0, Some(4), false);
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        for _ in 0..3 {
+            assert!(!kernel.tick_update(1));
+        }
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+
+        // Lengthened right away, the running task runs for the new length
+        assert!(!kernel.tick_update(1));
+        kernel.set_time_slice(1, Some(5), true);
+        for _ in 0..4 {
+            assert!(!kernel.tick_update(1));
+        }
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_no_time_slice() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create(0, 1, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.start();
+
+        for _ in 0..10 {
+            assert!(!kernel.tick_update(1));
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "priority-bitmap"))]
+    fn test_round_robin_excludes_idle_priority() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new();
+
+        let _ = kernel.create_with_time_slice(0, IDLE_TASK_PRIORITY, 0, Some(1));
+        let _ = kernel.create_with_time_slice(1, IDLE_TASK_PRIORITY, 0, Some(1));
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 0);
+
+        assert!(!kernel.tick_update(1));
+    }
+
+    #[test]
+    #[cfg(feature = "priority-bitmap")]
+    fn test_priority_bitmap_selection() {
+        let mut kernel: Kernel<u32, u64, 8> = Kernel::new();
+        kernel.mutex_create(0);
+
+        for id in 0..7 {
+            let _ = kernel.create(id, 10 + id % 3, 0);
+        }
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        let check = |kernel: &Kernel<u32, u64, 8>| {
+            for round_robin in [false, true] {
+                let ready_tasks = kernel.ready_list.iter().map(|idx| &kernel.task_list[idx]);
+                let all_tasks = kernel.task_list.iter().filter(|t| t.is_runnable());
+                let level = kernel
+                    .ready_list
+                    .highest_level()
+                    .or(Some(IDLE_TASK_PRIORITY));
+                assert_eq!(
+                    kernel.select_runnable_task(ready_tasks, round_robin, level),
+                    kernel.select_runnable_task(all_tasks, round_robin, None)
+                );
+            }
+        };
+
+        // Block, wake, re-prioritize, and delete tasks across priority levels
+        check(&kernel);
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(kernel.sleep(3));
+        let _ = kernel.handle_context_switch(None);
+        check(&kernel);
+        assert!(kernel.mutex_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        check(&kernel);
+        let _ = kernel.set_priority(5, 2);
+        let _ = kernel.handle_context_switch(None);
+        check(&kernel);
+        let _ = kernel.suspend(Some(2));
+        let _ = kernel.delete(Some(4));
+        check(&kernel);
+        assert!(!kernel.tick_update(3));
+        check(&kernel);
+        assert!(kernel.set_priority(5, 20));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        let _ = kernel.mutex_unlock(0);
+        let _ = kernel.resume(2);
+        check(&kernel);
+        assert_eq!(kernel.ready_count(), 6);
+    }
+
+    #[test]
+    fn test_equal_priority_does_not_preempt() {
+        let mut kernel: Kernel<u32, u64, 2> = Kernel::new_with_tie_break(TieBreak::ById);
+
+        let _ = kernel.create(5, 1, 0);
+        let _ = kernel.start();
+        assert_eq!(kernel.get_current_task(), 5);
+
+        assert!(!kernel.create(3, 1, 0));
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    fn test_sem_take_available() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        assert!(!kernel.sem_give(0));
+        assert!(!kernel.sem_take(0, None));
+        assert_eq!(kernel.curr_task_id, Some(0));
+        assert_eq!(kernel.next_task_id, None);
+    }
+
+    #[test]
+    fn test_sem_producer_consumer() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        // Consumer (task 0) blocks on the semaphore
+        assert!(kernel.sem_take(0, None));
+        assert_eq!(kernel.next_task_id, Some(1));
+        let _ = kernel.handle_context_switch(None);
+
+        assert!(!kernel.tick_update(100));
+
+        // Producer (task 1) gives the semaphore, waking the consumer
+        assert!(kernel.sem_give(0));
+        assert_eq!(kernel.next_task_id, Some(0));
+        let _ = kernel.handle_context_switch(None);
+
+        // The semaphore was handed to the consumer
+        assert!(kernel.sem_take(0, None));
+    }
+
+    #[test]
+    fn test_sem_take_timeout() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        assert!(kernel.sem_take(0, Some(2)));
+        let _ = kernel.handle_context_switch(None);
+
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_sem_take_wait_result() {
+        let mut kernel = setup();
+        kernel.sem_create(0);
+
+        // Timed out
+        assert!(kernel.sem_take(0, Some(2)));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::TimedOut);
+
+        // Fixed once the task resumed, even if the timeout state changes later
+        kernel.find_task(0).timed_out = false;
+        assert_eq!(kernel.get_wait_result(), WaitResult::TimedOut);
+
+        // Given before the timeout
+        assert!(kernel.sem_take(0, Some(2)));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sem_give(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+
+        // Taken without blocking, after an earlier timeout
+        assert!(kernel.sem_take(0, Some(2)));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::TimedOut);
+        assert!(!kernel.sem_give(0));
+        assert!(!kernel.sem_take(0, Some(2)));
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+    }
+
+    #[test]
+    fn test_sem_give_highest_priority_waiter() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.sem_create(0);
+
+        let _ = kernel.create(0, 2, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 0, 0);
+        let _ = kernel.start();
+
+        // Tasks 2 and 1 block on the semaphore, in priority order
+        for id in [1, 0] {
+            assert!(kernel.sem_take(0, None));
+            assert_eq!(kernel.next_task_id, Some(id));
+            let _ = kernel.handle_context_switch(None);
+        }
+
+        // Task 0 gives the semaphore twice
+        assert!(kernel.sem_give(0));
+        assert_eq!(kernel.next_task_id, Some(2));
+        assert!(kernel.sem_give(0));
+        assert_eq!(kernel.next_task_id, Some(2));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.find_task(1).state, TaskState::Ready);
+    }
+
+    fn sem_wake_sequence(wake_order: WakeOrder) -> [usize; 3] {
+        let mut kernel: Kernel<u32, u64, 4> = Kernel::new();
+        kernel.sem_create_with_order(0, 0, 1, wake_order);
+
+        for id in 0..4 {
+            let _ = kernel.create(id, id, 0);
+        }
+        let _ = kernel.start();
+
+        // Tasks 0 and 1 sleep so the tasks block on the semaphore in the order
+        // 2, 1, 0, with Task 3 running in between
+        for delay in [2, 1] {
+            assert!(kernel.sleep(delay));
+            let _ = kernel.handle_context_switch(None);
+        }
+        for _ in 0..3 {
+            assert!(kernel.sem_take(0, None));
+            let _ = kernel.handle_context_switch(None);
+            assert_eq!(kernel.get_current_task(), 3);
+            if kernel.tick_update(1) {
+                let _ = kernel.handle_context_switch(None);
+            }
+        }
+
+        // Task 3 gives the semaphore, and each woken task leaves it to Task 3
+        let mut sequence = [0; 3];
+        for woken in sequence.iter_mut() {
+            assert!(kernel.sem_give(0));
+            let _ = kernel.handle_context_switch(None);
+            *woken = kernel.get_current_task();
+            assert!(kernel.sleep_forever());
+            let _ = kernel.handle_context_switch(None);
+        }
+
+        sequence
+    }
+
+    #[test]
+    fn test_sem_wake_order() {
+        assert_eq!(sem_wake_sequence(WakeOrder::Priority), [0, 1, 2]);
+        assert_eq!(sem_wake_sequence(WakeOrder::Fifo), [2, 1, 0]);
+    }
+
+    #[test]
+    fn test_sem_counting() {
+        let mut kernel = setup();
+        kernel.sem_create_counting(0, 2, 3);
+        assert_eq!(kernel.sem_get_count(0), 2);
+
+        assert!(!kernel.sem_take(0, None));
+        assert!(!kernel.sem_take(0, None));
+        assert_eq!(kernel.sem_get_count(0), 0);
+
+        assert!(kernel.sem_take(0, None));
+        assert_eq!(kernel.next_task_id, Some(1));
+    }
+
+    #[test]
+    fn test_sem_give_saturates() {
+        let mut kernel = setup();
+        kernel.sem_create_counting(0, 0, 2);
+
+        for _ in 0..5 {
+            assert!(!kernel.sem_give(0));
+        }
+        assert_eq!(kernel.sem_get_count(0), 2);
+
+        kernel.sem_create(1);
+        let _ = kernel.sem_give(1);
+        let _ = kernel.sem_give(1);
+        assert_eq!(kernel.sem_get_count(1), 1);
+    }
+
+    #[test]
+    fn test_sem_give_unbounded_max() {
+        let mut kernel = setup();
+        kernel.sem_create_counting(0, usize::MAX, usize::MAX);
+
+        // The count stays at the largest value instead of overflowing
+        assert!(!kernel.sem_give(0));
+        assert_eq!(kernel.sem_get_count(0), usize::MAX);
+    }
+
+    #[test]
+    fn test_sem_give_one_waiter_per_count() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.sem_create_counting(0, 0, 5);
+
+        let _ = kernel.create(0, 2, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 0, 0);
+        let _ = kernel.start();
+
+        // Tasks 2 and 1 block on the semaphore
+        for _ in 0..2 {
+            let _ = kernel.sem_take(0, None);
+            let _ = kernel.handle_context_switch(None);
+        }
+
+        // Each give releases exactly one waiter, the count only grows once
+        // there are no waiters left
+        let _ = kernel.sem_give(0);
+        assert_eq!(kernel.find_task(2).state, TaskState::Ready);
+        assert_eq!(kernel.find_task(1).state, TaskState::Pending);
+        assert_eq!(kernel.sem_get_count(0), 0);
+
+        let _ = kernel.sem_give(0);
+        assert_eq!(kernel.find_task(1).state, TaskState::Ready);
+        assert_eq!(kernel.sem_get_count(0), 0);
+
+        let _ = kernel.sem_give(0);
+        assert_eq!(kernel.sem_get_count(0), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Semaphore count exceeds max_count")]
+    fn test_sem_create_counting_invalid() {
+        let mut kernel = setup();
+        kernel.sem_create_counting(0, 3, 2);
+    }
+
+    fn setup_priority_inversion() -> Kernel<u32, u64, 3> {
+        let mut kernel = Kernel::new();
+        kernel.mutex_create(0);
+
+        // High (0), medium (1), and low (2) priority tasks
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+
+        // High and medium priority tasks sleep, low priority task locks the mutex
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+
+        // High and medium priority tasks wake up, high priority task runs
+        assert!(kernel.tick_update(2));
+        assert_eq!(kernel.next_task_id, Some(0));
+        let _ = kernel.handle_context_switch(None);
+
+        kernel
+    }
+
+    #[test]
+    fn test_mutex_priority_inheritance() {
+        let mut kernel = setup_priority_inversion();
+
+        // High priority task blocks, low priority task inherits its priority
+        // and runs instead of the medium priority task
+        assert!(kernel.mutex_lock(0, None));
+        assert_eq!(kernel.next_task_id, Some(2));
+        assert_eq!(kernel.find_task(2).priority, 0);
+        let _ = kernel.handle_context_switch(None);
+
+        assert!(!kernel.tick_update(1));
+
+        // Unlocking hands the mutex to the high priority task
+        assert!(kernel.mutex_unlock(0));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.find_task(2).priority, 2);
+        assert_eq!(kernel.find_mutex(0).owner, Some(0));
+    }
+
+    #[test]
+    fn test_mutex_owner_deleted() {
+        let mut kernel = setup_priority_inversion();
+
+        // High priority task blocks on the mutex held by the low priority task
+        assert!(kernel.mutex_lock(0, Some(3)));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 2);
+
+        // Deleting the owner hands the mutex to the waiting task
+        assert!(kernel.delete(None));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.find_mutex(0).owner, Some(0));
+        assert_eq!(kernel.find_mutex(0).lock_count, 1);
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+        assert!(!kernel.tick_update(3));
+
+        // A new task reusing the ID does not hold the mutex
+        assert!(!kernel.mutex_unlock(0));
+        let _ = kernel.create(2, 2, 0);
+        assert_eq!(kernel.find_mutex(0).owner, None);
+        assert!(!kernel.mutex_lock(0, None));
+        assert_eq!(kernel.find_mutex(0).owner, Some(0));
+    }
+
+    #[test]
+    fn test_mutex_priority_ceiling() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.mutex_create_with_ceiling(0, 0);
+
+        // High (0), medium (1), and low (2) priority tasks
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+
+        // High and medium priority tasks sleep, low priority task locks the
+        // mutex and is raised to the ceiling right away
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+        assert_eq!(kernel.find_task(2).priority, 0);
+
+        // Neither task that wakes up preempts the owner while it holds the mutex
+        assert!(!kernel.tick_update(2));
+        assert_eq!(kernel.get_current_task(), 2);
+
+        // Recursive locking keeps the ceiling until the last unlock
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(!kernel.mutex_unlock(0));
+        assert_eq!(kernel.find_task(2).priority, 0);
+
+        assert!(kernel.mutex_unlock(0));
+        assert_eq!(kernel.find_task(2).priority, 2);
+        assert_eq!(kernel.next_task_id, Some(0));
+    }
+
+    #[test]
+    fn test_mutex_ceiling_handoff() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.mutex_create_with_ceiling(0, 0);
+
+        let _ = kernel.create(0, 1, 0);
+        let _ = kernel.create(1, 2, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
+
+        // Task 0 locks the mutex and sleeps, task 1 then waits on it
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.mutex_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), IDLE_TASK_ID);
+
+        // Task 0 wakes up and hands the mutex over, along with the ceiling
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.mutex_unlock(0));
+        assert_eq!(kernel.find_task(0).priority, 1);
+        assert_eq!(kernel.find_task(1).priority, 0);
+        assert_eq!(kernel.find_mutex(0).owner, Some(1));
+        assert_eq!(kernel.next_task_id, Some(1));
+    }
+
+    #[test]
+    fn test_mutex_lock_timeout() {
+        let mut kernel = setup_priority_inversion();
+
+        assert!(kernel.mutex_lock(0, Some(3)));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.find_task(2).priority, 0);
+
+        // High priority task gives up, low priority task loses the boost
+        assert!(kernel.tick_update(3));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.find_task(2).priority, 2);
+        assert_eq!(kernel.find_mutex(0).owner, Some(2));
+    }
+
+    #[test]
+    fn test_mutex_lock_wait_result() {
+        let mut kernel = setup_priority_inversion();
+
+        // Timed out
+        assert!(kernel.mutex_lock(0, Some(3)));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.tick_update(3));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::TimedOut);
+
+        // Unlocked before the timeout
+        assert!(kernel.mutex_lock(0, Some(3)));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 2);
+        assert!(kernel.mutex_unlock(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::Acquired);
+        assert_eq!(kernel.find_mutex(0).owner, Some(0));
+    }
+
+    #[test]
+    fn test_mutex_chained_inheritance() {
+        let mut kernel: Kernel<u32, u64, 3> = Kernel::new();
+        kernel.mutex_create(0);
+        kernel.mutex_create(1);
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+
+        // High and medium priority tasks sleep, low priority task locks mutex 1
+        let _ = kernel.sleep(3);
+        let _ = kernel.handle_context_switch(None);
+        let _ = kernel.sleep(2);
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(1, None));
+
+        // Medium priority task locks mutex 0, then blocks on mutex 1
+        assert!(kernel.tick_update(2));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(kernel.mutex_lock(1, None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.find_task(2).priority, 1);
+
+        // High priority task blocks on mutex 0, boosting the whole chain
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.mutex_lock(0, None));
+        assert_eq!(kernel.find_task(1).priority, 0);
+        assert_eq!(kernel.find_task(2).priority, 0);
+        assert_eq!(kernel.next_task_id, Some(2));
+    }
+
+    #[test]
+    fn test_mutex_recursive() {
+        let mut kernel = setup_priority_inversion();
+
+        // High priority task blocks, low priority task locks the mutex again
+        assert!(kernel.mutex_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(!kernel.mutex_lock(0, None));
+        assert_eq!(kernel.find_mutex(0).lock_count, 3);
+
+        assert!(!kernel.mutex_unlock(0));
+        assert!(!kernel.mutex_unlock(0));
+        assert_eq!(kernel.find_mutex(0).owner, Some(2));
+
+        // The last unlock releases the mutex to the high priority task
+        assert!(kernel.mutex_unlock(0));
+        assert_eq!(kernel.find_mutex(0).owner, Some(0));
+        assert_eq!(kernel.find_mutex(0).lock_count, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mutex not locked by the current task")]
+    fn test_mutex_unlock_too_many_times() {
+        let mut kernel = setup();
+        kernel.mutex_create(0);
+
+        let _ = kernel.mutex_lock(0, None);
+        let _ = kernel.mutex_lock(0, None);
+        let _ = kernel.mutex_unlock(0);
+        let _ = kernel.mutex_unlock(0);
+        let _ = kernel.mutex_unlock(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mutex not locked by the current task")]
+    fn test_mutex_unlock_not_owner() {
+        let mut kernel = setup_priority_inversion();
+        let _ = kernel.mutex_unlock(0);
+    }
+
+    #[cfg(feature = "deadlock-detection")]
+    fn setup_deadlock() -> Kernel<u32, u64, 3> {
+        let mut kernel = Kernel::new();
+        kernel.mutex_create(0);
+        kernel.mutex_create(1);
+
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create_idle(0);
+        let _ = kernel.start();
 
-        // Update next task
-        let next_task = self.find_task(next_task_id);
-        next_task.state = TaskState::Running;
+        // Task 0 locks mutex 0 and sleeps, task 1 locks mutex 1 and waits on
+        // mutex 0
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(kernel.sleep(1));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(1, None));
+        assert!(kernel.mutex_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
 
-        // Return the next task stack pointer
-        next_task.stack_ptr
+        // Task 0 wakes up
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+
+        kernel
     }
 
-    fn scheduler(&mut self) -> bool {
-        if !self.is_running {
-            return false;
-        }
+    #[test]
+    #[cfg(feature = "deadlock-detection")]
+    fn test_try_mutex_lock_deadlock() {
+        let mut kernel = setup_deadlock();
 
-        // Update pending tasks, as they might be ready to run now
-        self.update_pending_tasks();
+        assert_eq!(kernel.try_mutex_lock(1, None), Err(MutexError::Deadlock));
+        assert_eq!(kernel.get_task_state(0), Some(TaskState::Running));
 
-        // Update next task to run
-        match self.find_highest_priority_runnable_task() {
-            Some(next_task_id) => {
-                match self.curr_task_id {
-                    Some(curr_task_id) => {
-                        // Case 1: Current task should continue running
-                        if curr_task_id == next_task_id {
-                            self.next_task_id = None;
-                        // Case 2: Current task should be switched out
-                        } else {
-                            self.next_task_id = Some(next_task_id);
-                        }
-                    }
-                    // Case 3: There is no current task (starting the kernel)
-                    None => self.next_task_id = Some(next_task_id),
-                }
-            }
-            // All tasks pending, nothing to do
-            None => self.next_task_id = None,
-        }
+        // Locking a mutex held by the current task is not a deadlock
+        assert_eq!(kernel.try_mutex_lock(0, None), Ok(false));
+    }
 
-        !(self.next_task_id == None)
+    #[test]
+    #[cfg(feature = "deadlock-detection")]
+    #[should_panic(expected = "Mutex lock would deadlock")]
+    fn test_mutex_lock_deadlock() {
+        let mut kernel = setup_deadlock();
+        let _ = kernel.mutex_lock(1, None);
     }
 
-    fn update_pending_tasks(&mut self) {
-        for task in self.task_list.iter_mut() {
-            match task.pend {
-                TaskPendReason::Sleep(timeout) => {
-                    if self.tick_counter >= timeout {
-                        task.state = TaskState::Ready;
-                        task.pend = TaskPendReason::NotPending;
-                    }
-                }
-                _ => (),
-            }
-        }
+    #[test]
+    fn test_event_wait_all() {
+        let mut kernel = setup();
+        kernel.event_create(0);
+
+        assert!(kernel.event_wait(0, 0b101, true, None));
+        let _ = kernel.handle_context_switch(None);
+
+        assert!(!kernel.event_set(0, 0b001));
+        assert!(kernel.event_set(0, 0b110));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.event_get(0), 0b111);
     }
 
-    // TODO: Assumes only one task per priority level, no round-robin scheduling
-    fn find_highest_priority_runnable_task(&self) -> Option<usize> {
-        let mut highest_prio_runnable_task: Option<&Task<SP, TICK>> = None;
-        for task in self.task_list.iter() {
-            if task.is_runnable() {
-                highest_prio_runnable_task = match highest_prio_runnable_task {
-                    Some(other) => {
-                        if task < other {
-                            Some(task)
-                        } else {
-                            Some(other)
-                        }
-                    }
-                    None => Some(task),
-                };
-            }
-        }
+    #[test]
+    fn test_event_wait_any() {
+        let mut kernel = setup();
+        kernel.event_create(0);
 
-        match highest_prio_runnable_task {
-            Some(task) => Some(task.id),
-            None => None,
-        }
+        assert!(kernel.event_wait(0, 0b101, false, None));
+        let _ = kernel.handle_context_switch(None);
+
+        assert!(!kernel.event_set(0, 0b010));
+        assert!(kernel.event_set(0, 0b100));
+        assert_eq!(kernel.next_task_id, Some(0));
     }
 
-    fn find_task(&mut self, id: usize) -> &mut Task<SP, TICK> {
-        self.task_list
-            .iter_mut()
-            .find(|t| t.id == id)
-            .expect("Task does not exist")
+    #[test]
+    fn test_event_already_set() {
+        let mut kernel = setup();
+        kernel.event_create(0);
+
+        let _ = kernel.event_set(0, 0b11);
+        assert!(!kernel.event_wait(0, 0b11, true, None));
+
+        kernel.event_clear(0, 0b01);
+        assert_eq!(kernel.event_get(0), 0b10);
+        assert!(kernel.event_wait(0, 0b11, true, None));
     }
 
-    fn find_task_idx(&self, id: usize) -> usize {
-        self.task_list
-            .iter()
-            .position(|t| t.id == id)
-            .expect("Task does not exist")
+    #[test]
+    fn test_event_wait_timeout() {
+        let mut kernel = setup();
+        kernel.event_create(0);
+
+        assert!(kernel.event_wait(0, 0b1, true, Some(5)));
+        let _ = kernel.handle_context_switch(None);
+
+        assert!(!kernel.tick_update(4));
+        assert!(kernel.tick_update(1));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.event_get(0), 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_mailbox_overwrite() {
+        let mut kernel = setup();
+        kernel.mailbox_create(0);
 
-    fn setup() -> Kernel<u32, u64, 2> {
-        let mut kernel = Kernel::new();
+        // Only the latest item is kept
+        assert!(!kernel.mailbox_post(0, 1));
+        assert!(!kernel.mailbox_post(0, 2));
+        assert!(!kernel.mailbox_fetch(0, None));
+        assert_eq!(kernel.take_message(), Some(2));
+        assert_eq!(kernel.take_message(), None);
 
-        let mut task0_stack: [u8; 128] = [0; 128];
-        kernel.create(0, 99, task0_stack.as_mut_ptr() as u32);
+        // The item was consumed
+        assert!(kernel.mailbox_fetch(0, None));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::MailboxWait));
+    }
 
-        let mut task1_stack: [u8; 128] = [0; 128];
-        kernel.create(1, 100, task1_stack.as_mut_ptr() as u32);
+    #[test]
+    fn test_mailbox_fetch_blocked() {
+        let mut kernel = setup();
+        kernel.mailbox_create(0);
 
-        kernel.start();
-        assert_eq!(kernel.curr_task_id, Some(0));
-        assert_eq!(kernel.next_task_id, None);
-        assert_eq!(kernel.get_current_task(), 0);
+        // Task 0 blocks on the empty mailbox, Task 1 runs
+        assert!(kernel.mailbox_fetch(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 1);
 
-        kernel
+        // The post is handed to Task 0, which preempts Task 1
+        assert!(kernel.mailbox_post(0, 7));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_message(), Some(7));
+        assert!(kernel.mailbox_fetch(0, None));
     }
 
     #[test]
-    fn test_sleep() {
+    fn test_mailbox_fetch_timeout() {
         let mut kernel = setup();
+        kernel.mailbox_create(0);
 
-        assert_eq!(kernel.sleep(2), true);
-        assert_eq!(kernel.curr_task_id, Some(0));
-        assert_eq!(kernel.next_task_id, Some(1));
+        assert!(kernel.mailbox_fetch(0, Some(3)));
+        let _ = kernel.handle_context_switch(None);
 
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.tick_update(1));
         let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_message(), None);
+    }
 
-        assert_eq!(kernel.curr_task_id, Some(1));
-        assert_eq!(kernel.next_task_id, None);
+    #[test]
+    fn test_notify_wait_blocked() {
+        let mut kernel = setup();
+
+        // Task 0 blocks waiting for a notification, Task 1 runs
+        assert!(kernel.notify_wait(None));
+        let _ = kernel.handle_context_switch(None);
         assert_eq!(kernel.get_current_task(), 1);
 
-        assert_eq!(kernel.tick_update(3), true);
-        assert_eq!(kernel.get_current_tick(), 3);
-        assert_eq!(kernel.curr_task_id, Some(1));
-        assert_eq!(kernel.next_task_id, Some(0));
+        // The notification wakes Task 0, which preempts Task 1
+        assert!(kernel.notify(0, 7));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_notification(), Some(7));
+        assert!(kernel.notify_wait(None));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::NotifyWait));
     }
 
     #[test]
-    fn test_suspend_current_task() {
+    fn test_notify_before_wait() {
         let mut kernel = setup();
 
-        assert_eq!(kernel.suspend(None), true);
-        assert_eq!(kernel.curr_task_id, Some(0));
-        assert_eq!(kernel.next_task_id, Some(1));
+        // Only the latest value is kept by default
+        assert!(!kernel.notify(0, 0b01));
+        assert!(!kernel.notify(0, 0b10));
+        assert!(!kernel.notify_wait(None));
+        assert_eq!(kernel.take_notification(), Some(0b10));
+        assert_eq!(kernel.take_notification(), None);
+
+        // Values are combined once the action is changed
+        kernel.set_notify_action(0, NotifyAction::SetBits);
+        assert!(!kernel.notify(0, 0b01));
+        assert!(!kernel.notify(0, 0b10));
+        assert!(!kernel.notify_wait(Some(1)));
+        assert_eq!(kernel.take_notification(), Some(0b11));
     }
 
     #[test]
-    fn test_suspend_other_task() {
+    fn test_wait_until_or_signal() {
         let mut kernel = setup();
 
-        assert_eq!(kernel.suspend(Some(1)), false);
-        assert_eq!(kernel.curr_task_id, Some(0));
-        assert_eq!(kernel.next_task_id, None);
+        // The wait ends at the absolute tick, not a delay from each call
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.wait_until_or_signal(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_tick(), 5);
+        assert_eq!(kernel.take_wake_reason(u32::MAX), WakeReason::Deadline);
+
+        // A tick that has been reached does not block
+        assert!(!kernel.wait_until_or_signal(5));
+        assert_eq!(kernel.take_wake_reason(u32::MAX), WakeReason::Deadline);
+
+        // Signalled before the tick
+        assert!(kernel.wait_until_or_signal(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.tick_update(1));
+        assert!(kernel.notify(0, 4));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_wake_reason(u32::MAX), WakeReason::Signalled(4));
+        assert_eq!(kernel.take_notification(), None);
+
+        // A pending notification does not block either, and is capped
+        assert!(!kernel.notify(0, 9));
+        assert!(!kernel.wait_until_or_signal(10));
+        assert_eq!(kernel.take_wake_reason(3), WakeReason::Signalled(3));
     }
 
     #[test]
-    fn test_resume() {
+    fn test_notify_wait_timeout() {
         let mut kernel = setup();
 
-        let _ = kernel.suspend(None);
+        assert!(kernel.notify_wait(Some(3)));
         let _ = kernel.handle_context_switch(None);
 
-        assert_eq!(kernel.resume(0), true);
-        assert_eq!(kernel.curr_task_id, Some(1));
+        assert!(!kernel.tick_update(2));
+        assert!(kernel.tick_update(1));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.take_notification(), None);
+        assert_eq!(kernel.get_wait_result(), WaitResult::TimedOut);
+    }
+
+    fn setup_rwlock() -> Kernel<u32, u64, 4> {
+        let mut kernel = Kernel::new();
+        kernel.rwlock_create(0);
+
+        let _ = kernel.create_idle(0);
+        let _ = kernel.create(0, 0, 0);
+        let _ = kernel.create(1, 1, 0);
+        let _ = kernel.create(2, 2, 0);
+        let _ = kernel.start();
+
+        kernel
+    }
+
+    #[test]
+    fn test_rwlock_readers() {
+        let mut kernel = setup_rwlock();
+
+        // Tasks 0 and 1 both read, then block on something else
+        assert!(!kernel.rwlock_read_lock(0, None));
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.rwlock_read_lock(0, None));
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+
+        // Task 2 cannot write while they read
+        assert_eq!(kernel.get_current_task(), 2);
+        assert!(kernel.rwlock_write_lock(0, None));
+        assert_eq!(kernel.get_pend_reason(2), Some(PendReason::WriteWait));
+    }
+
+    #[test]
+    fn test_rwlock_writer_exclusion() {
+        let mut kernel = setup_rwlock();
+
+        // Task 2 writes
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.rwlock_write_lock(0, None));
+
+        // Tasks 0 and 1 wake up and block on the lock
+        assert!(kernel.tick_update(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_read_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_write_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 2);
+
+        // The highest priority waiter, a reader, gets the lock
+        assert!(kernel.rwlock_write_unlock(0));
         assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::WriteWait));
+
+        // The writer gets the lock once the reader is done
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.rwlock_read_unlock(0));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::NotPending));
     }
 
     #[test]
-    fn test_delete_current_task() {
-        let mut kernel = setup();
+    fn test_rwlock_holder_deleted() {
+        let mut kernel = setup_rwlock();
 
-        assert_eq!(kernel.delete(None), true);
-        assert_eq!(kernel.curr_task_id, None);
+        // Task 2 writes, Task 0 waits to read and Task 1 waits to write
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.rwlock_write_lock(0, None));
+        assert!(kernel.tick_update(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_read_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_write_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 2);
+
+        // Deleting the writer hands the lock to the reader
+        assert!(kernel.delete(None));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.find_rwlock(0).writer, None);
+        assert_eq!(kernel.find_rwlock(0).readers, 1);
+        let _ = kernel.handle_context_switch(None);
+
+        // Deleting the reader hands the lock to the writer
+        assert!(kernel.delete(None));
         assert_eq!(kernel.next_task_id, Some(1));
+        assert_eq!(kernel.find_rwlock(0).readers, 0);
+        assert_eq!(kernel.find_rwlock(0).writer, Some(1));
     }
 
     #[test]
-    fn test_delete_current_task_by_id() {
-        let mut kernel = setup();
+    #[should_panic(expected = "Read-write lock not locked for reading by the current task")]
+    fn test_rwlock_read_unlock_not_reader() {
+        let mut kernel = setup_rwlock();
+        assert!(!kernel.rwlock_read_lock(0, None));
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        let _ = kernel.rwlock_read_unlock(0);
+    }
 
-        assert_eq!(kernel.delete(Some(0)), true);
-        assert_eq!(kernel.curr_task_id, None);
+    #[test]
+    fn test_rwlock_writer_preference() {
+        let mut kernel = setup_rwlock();
+
+        // Task 2 reads
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.sleep(10));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.rwlock_read_lock(0, None));
+
+        // Task 0 waits to write
+        assert!(kernel.tick_update(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_write_lock(0, None));
+        let _ = kernel.handle_context_switch(None);
+
+        // Task 1 cannot read ahead of the waiting writer
+        assert!(kernel.tick_update(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.rwlock_read_lock(0, None));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::ReadWait));
+        let _ = kernel.handle_context_switch(None);
+
+        // Task 0 writes once Task 2 is done, then Task 1 reads
+        assert!(kernel.rwlock_read_unlock(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert!(!kernel.rwlock_write_unlock(0));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::NotPending));
+    }
+
+    #[test]
+    fn test_barrier() {
+        let mut kernel = setup_rwlock();
+        kernel.barrier_create(0, 3);
+
+        // Tasks 0 and 1 arrive and block
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::BarrierWait));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::BarrierWait));
+
+        // Task 2 arrives last and releases everyone
+        assert!(kernel.barrier_wait(0));
+        assert_eq!(kernel.next_task_id, Some(0));
+        for id in 0..3 {
+            assert_ne!(kernel.get_task_state(id), Some(TaskState::Pending));
+        }
+
+        // The barrier resets for the next round
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.barrier_wait(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::BarrierWait));
+    }
+
+    #[test]
+    fn test_barrier_waiter_deleted() {
+        let mut kernel = setup_rwlock();
+        kernel.barrier_create(0, 3);
+
+        // Task 0 arrives, then is deleted
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.delete(Some(0)));
+
+        // The barrier now releases with two tasks
+        assert!(kernel.barrier_wait(0));
+        let _ = kernel.handle_context_switch(None);
+        assert!(kernel.barrier_wait(0));
         assert_eq!(kernel.next_task_id, Some(1));
     }
 
+    fn setup_cond() -> Kernel<u32, u64, 4> {
+        let mut kernel = setup_rwlock();
+        kernel.mutex_create(0);
+        kernel.cond_create(0);
+
+        kernel
+    }
+
     #[test]
-    fn test_delete_other_task() {
-        let mut kernel = setup();
+    fn test_cond_wait_then_notify() {
+        let mut kernel = setup_cond();
 
-        let _ = kernel.suspend(None);
+        // Task 0 waits, unlocking the mutex
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(kernel.cond_wait(0, 0));
         let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::CondWait));
 
-        assert_eq!(kernel.delete(Some(0)), false);
-        assert_eq!(kernel.curr_task_id, Some(1));
-        assert_eq!(kernel.next_task_id, None);
+        // Task 1 locks the mutex and notifies, Task 0 now waits on the mutex
+        assert!(!kernel.mutex_lock(0, None));
+        assert!(!kernel.cond_notify_one(0));
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::MutexWait));
+
+        // Task 0 runs once it has the mutex again
+        assert!(kernel.mutex_unlock(0));
+        let _ = kernel.handle_context_switch(None);
+        assert_eq!(kernel.get_current_task(), 0);
+        assert!(!kernel.mutex_unlock(0));
+    }
+
+    #[test]
+    fn test_cond_notify_before_wait() {
+        let mut kernel = setup_cond();
+        let mut flag = false;
+
+        // Task 0 is busy, Task 1 sets the flag and notifies with nobody waiting
+        assert!(kernel.sleep(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+        flag = !flag;
+        assert!(!kernel.cond_notify_one(0));
+        assert!(!kernel.mutex_unlock(0));
+
+        // Task 0 checks the flag before waiting, so the signal is not lost
+        assert!(kernel.tick_update(5));
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_lock(0, None));
+        if !flag {
+            let _ = kernel.cond_wait(0, 0);
+        }
+        assert_eq!(kernel.get_pend_reason(0), Some(PendReason::NotPending));
+        assert!(!kernel.mutex_unlock(0));
+    }
+
+    #[test]
+    fn test_cond_notify_all() {
+        let mut kernel = setup_cond();
+
+        // Tasks 0 and 1 wait
+        for _ in 0..2 {
+            assert!(!kernel.mutex_lock(0, None));
+            assert!(kernel.cond_wait(0, 0));
+            let _ = kernel.handle_context_switch(None);
+        }
+
+        // Task 0 gets the mutex first, Task 1 waits for it
+        assert!(kernel.cond_notify_all(0));
+        assert_eq!(kernel.next_task_id, Some(0));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::MutexWait));
+
+        let _ = kernel.handle_context_switch(None);
+        assert!(!kernel.mutex_unlock(0));
+        assert_eq!(kernel.get_pend_reason(1), Some(PendReason::NotPending));
     }
 }
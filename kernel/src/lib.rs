@@ -2,7 +2,52 @@
 
 #![cfg_attr(not(test), no_std)]
 
+mod alarm;
+mod atomic_tick;
+mod barrier;
+mod bitmap;
+mod condvar;
+mod cycles;
+mod error;
+mod event;
+mod irq;
 pub mod kernel;
+mod mailbox;
+mod mutex;
+mod pool;
+mod queue;
+mod ready;
+mod retry;
+mod rwlock;
+mod semaphore;
+mod spsc;
+mod stack;
 mod task;
+mod tick;
+mod time;
 
-pub use kernel::Kernel;
+pub use atomic_tick::AtomicTick;
+pub use bitmap::{PriorityBitmap, PRIORITY_LEVELS};
+pub use cycles::{CycleCounter, CycleStats};
+pub use error::{CreateError, MutexError, ResetError, TaskError};
+pub use irq::{
+    irq_priority, irq_priority_allowed, irq_priority_bits, irq_priority_implemented,
+    irq_priority_lowest,
+};
+pub use kernel::{
+    Kernel, SchedPolicy, TieBreak, IDLE_TASK_ID, IDLE_TASK_PRIORITY, MAX_READ_LOCKS, TLS_SLOTS,
+};
+pub use pool::MemPool;
+pub use queue::PriorityQueue;
+pub use retry::retry_with_backoff;
+pub use semaphore::WakeOrder;
+pub use spsc::SpscRing;
+pub use stack::{
+    stack_canary_check, stack_canary_write, stack_fill, stack_fits, stack_high_water,
+    stack_min_size, stack_top, STACK_CANARY, STACK_FILL,
+};
+#[cfg(feature = "stats")]
+pub use task::Jitter;
+pub use task::{NotifyAction, PendReason, TaskInfo, TaskState, WaitResult, WakeReason};
+pub use tick::Tick;
+pub use time::{ms_to_ticks, ticks_to_ms, ticks_to_us, us_to_ticks, TickResync};
@@ -0,0 +1,10 @@
+//! RuCOS Mailbox
+
+/// Mailbox control block
+#[derive(Debug)]
+pub struct Mailbox {
+    /// Mailbox ID
+    pub id: usize,
+    /// Latest item posted and not yet fetched, or `None` if empty
+    pub item: Option<usize>,
+}
@@ -0,0 +1,15 @@
+//! RuCOS Mutex
+
+/// Mutex control block
+#[derive(Debug)]
+pub struct Mutex {
+    /// Mutex ID
+    pub id: usize,
+    /// ID of the task holding the mutex, or `None` if unlocked
+    pub owner: Option<usize>,
+    /// Number of times the owner has locked the mutex
+    pub lock_count: usize,
+    /// Priority the owner is raised to while it holds the mutex, or `None` if
+    /// the owner inherits the priority of the tasks waiting on it instead
+    pub ceiling: Option<usize>,
+}
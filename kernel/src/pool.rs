@@ -0,0 +1,180 @@
+//! RuCOS Memory Pool
+
+/// Pool of fixed size memory blocks
+///
+/// # Generics
+///
+/// * `BLOCK`: Size of each block in bytes
+/// * `N`: Number of blocks in the pool
+///
+/// # Note
+///
+/// Blocks are identified by their index in the pool. Allocating and freeing
+/// are O(1), using a list of free blocks threaded through `next`.
+#[derive(Debug)]
+pub struct MemPool<const BLOCK: usize, const N: usize> {
+    /// Block memory
+    blocks: [[u8; BLOCK]; N],
+    /// Index of the free block after each free block, `N` ends the list
+    next: [usize; N],
+    /// Index of the first free block, or `N` if the pool is exhausted
+    head: usize,
+    /// The block has been allocated and not freed
+    allocated: [bool; N],
+}
+
+impl<const BLOCK: usize, const N: usize> MemPool<BLOCK, N> {
+    /// Create a pool with all blocks free
+    pub const fn new() -> Self {
+        let mut next = [0; N];
+        let mut idx = 0;
+        while idx < N {
+            next[idx] = idx + 1;
+            idx += 1;
+        }
+
+        Self {
+            blocks: [[0; BLOCK]; N],
+            next,
+            head: 0,
+            allocated: [false; N],
+        }
+    }
+
+    /// Allocate a block
+    ///
+    /// # Returns
+    ///
+    /// Index of the allocated block, or `None` if all blocks are allocated
+    pub fn alloc(&mut self) -> Option<usize> {
+        if self.head == N {
+            return None;
+        }
+
+        let idx = self.head;
+        self.head = self.next[idx];
+        self.allocated[idx] = true;
+        Some(idx)
+    }
+
+    /// Free a block
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: Index of the block to free
+    ///
+    /// # Panics
+    ///
+    /// * The `idx` provided is out of range
+    /// * The block is not allocated, e.g. it was already freed
+    pub fn free(&mut self, idx: usize) {
+        assert!(self.allocated[idx], "Block is not allocated");
+
+        self.allocated[idx] = false;
+        self.next[idx] = self.head;
+        self.head = idx;
+    }
+
+    /// Get the memory of an allocated block
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: Index of the block
+    ///
+    /// # Returns
+    ///
+    /// Memory of the block
+    ///
+    /// # Panics
+    ///
+    /// * The `idx` provided is out of range
+    /// * The block is not allocated
+    pub fn block_mut(&mut self, idx: usize) -> &mut [u8; BLOCK] {
+        assert!(self.allocated[idx], "Block is not allocated");
+        &mut self.blocks[idx]
+    }
+
+    /// Get the index of a block from its address
+    ///
+    /// # Arguments
+    ///
+    /// * `addr`: Address of the start of a block
+    ///
+    /// # Returns
+    ///
+    /// Index of the block, or `None` if `addr` is not the start of a block in
+    /// the pool
+    pub fn block_index(&self, addr: usize) -> Option<usize> {
+        let offset = addr.checked_sub(self.blocks.as_ptr() as usize)?;
+        if BLOCK == 0 || offset % BLOCK != 0 || offset / BLOCK >= N {
+            return None;
+        }
+
+        Some(offset / BLOCK)
+    }
+
+    /// Get the number of free blocks
+    ///
+    /// # Returns
+    ///
+    /// Number of blocks that can be allocated
+    pub fn available(&self) -> usize {
+        self.allocated
+            .iter()
+            .filter(|&&allocated| !allocated)
+            .count()
+    }
+}
+
+impl<const BLOCK: usize, const N: usize> Default for MemPool<BLOCK, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exhaustion() {
+        let mut pool: MemPool<16, 2> = MemPool::new();
+
+        assert_eq!(pool.alloc(), Some(0));
+        assert_eq!(pool.alloc(), Some(1));
+        assert_eq!(pool.alloc(), None);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[test]
+    fn test_reuse() {
+        let mut pool: MemPool<16, 3> = MemPool::new();
+
+        let a = pool.alloc().unwrap();
+        let b = pool.alloc().unwrap();
+        pool.block_mut(b)[0] = 0xAB;
+        pool.free(b);
+        pool.free(a);
+        assert_eq!(pool.available(), 3);
+
+        // The most recently freed block is reused first
+        assert_eq!(pool.alloc(), Some(a));
+        assert_eq!(pool.alloc(), Some(b));
+        assert_eq!(pool.block_mut(b)[0], 0xAB);
+
+        let addr = pool.block_mut(b).as_ptr() as usize;
+        assert_eq!(pool.block_index(addr), Some(b));
+        assert_eq!(pool.block_index(addr + 1), None);
+        assert_eq!(pool.block_index(addr + 32), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Block is not allocated")]
+    fn test_double_free() {
+        let mut pool: MemPool<16, 2> = MemPool::new();
+
+        let idx = pool.alloc().unwrap();
+        pool.free(idx);
+        pool.free(idx);
+    }
+}
@@ -0,0 +1,132 @@
+//! RuCOS Queue
+
+use heapless::Vec;
+
+/// Message queue delivering the highest priority message first
+///
+/// # Generics
+///
+/// * `T`: The message type
+/// * `CAP`: Upper bound on the number of messages in the queue
+///
+/// # Note
+///
+/// The queue does not block. To wait for messages, pair it with a counting
+/// semaphore given after each `send` and taken before each `recv`, and protect
+/// the queue itself with a mutex or critical section.
+#[derive(Debug)]
+pub struct PriorityQueue<T, const CAP: usize> {
+    /// Messages with their priority, highest priority at the back
+    messages: Vec<(usize, T), CAP>,
+}
+
+impl<T, const CAP: usize> PriorityQueue<T, CAP> {
+    /// Create an empty queue
+    pub const fn new() -> Self {
+        Self {
+            messages: Vec::new(),
+        }
+    }
+
+    /// Send a message
+    ///
+    /// # Arguments
+    ///
+    /// * `item`: Message to send
+    /// * `priority`: Message priority, with a lower number meaning higher
+    ///   priority
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the message was queued, or `Err(item)` if the queue is full
+    pub fn send(&mut self, item: T, priority: usize) -> Result<(), T> {
+        if self.messages.is_full() {
+            return Err(item);
+        }
+
+        // Keep messages sorted with the next one to receive at the back, behind
+        // messages of the same priority that were sent earlier
+        let idx = self
+            .messages
+            .iter()
+            .position(|&(other, _)| other <= priority)
+            .unwrap_or(self.messages.len());
+
+        self.messages
+            .insert(idx, (priority, item))
+            .map_err(|(_, item)| item)
+    }
+
+    /// Receive the highest priority message
+    ///
+    /// # Returns
+    ///
+    /// Highest priority message, the one sent first if several have the same
+    /// priority, or `None` if the queue is empty
+    pub fn recv(&mut self) -> Option<T> {
+        self.messages.pop().map(|(_, item)| item)
+    }
+
+    /// Get the number of messages in the queue
+    ///
+    /// # Returns
+    ///
+    /// Number of messages in the queue
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Check if the queue is empty
+    ///
+    /// # Returns
+    ///
+    /// `true` if the queue is empty, `false` if not
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+impl<T, const CAP: usize> Default for PriorityQueue<T, CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_order() {
+        let mut queue: PriorityQueue<char, 4> = PriorityQueue::new();
+
+        assert_eq!(queue.send('a', 3), Ok(()));
+        assert_eq!(queue.send('b', 1), Ok(()));
+        assert_eq!(queue.send('c', 2), Ok(()));
+        assert_eq!(queue.send('d', 1), Ok(()));
+        assert_eq!(queue.send('e', 0), Err('e'));
+        assert_eq!(queue.len(), 4);
+
+        assert_eq!(queue.recv(), Some('b'));
+        assert_eq!(queue.recv(), Some('d'));
+        assert_eq!(queue.recv(), Some('c'));
+        assert_eq!(queue.recv(), Some('a'));
+        assert_eq!(queue.recv(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let mut queue: PriorityQueue<u32, 3> = PriorityQueue::default();
+
+        assert_eq!(queue.send(10, 5), Ok(()));
+        assert_eq!(queue.send(20, 5), Ok(()));
+        assert_eq!(queue.recv(), Some(10));
+
+        assert_eq!(queue.send(30, 0), Ok(()));
+        assert_eq!(queue.send(40, 5), Ok(()));
+        assert_eq!(queue.recv(), Some(30));
+        assert_eq!(queue.recv(), Some(20));
+        assert_eq!(queue.recv(), Some(40));
+    }
+}
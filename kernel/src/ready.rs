@@ -0,0 +1,243 @@
+//! RuCOS ready list
+
+#[cfg(not(feature = "priority-bitmap"))]
+use crate::bitmap::NoBitmap as ReadyLevels;
+#[cfg(feature = "priority-bitmap")]
+use crate::bitmap::PriorityBitmap as ReadyLevels;
+use crate::kernel::IDLE_TASK_ID;
+use crate::task::{Task, TaskPendReason, TaskState};
+use heapless::Vec;
+
+/// Runnable tasks of the kernel, kept in step with its task list
+///
+/// Every change of task state or priority goes through this list, so the
+/// scheduler only looks at the tasks that can run. Tasks are referred to by
+/// their index in the task list, in ascending order, so tasks of the same
+/// priority are visited in creation order.
+///
+/// # Generics
+///
+/// * `MAX_NUM_TASKS`: Upper bound on the number of tasks for the kernel
+#[derive(Debug)]
+pub struct ReadyList<const MAX_NUM_TASKS: usize> {
+    /// Task list indices of the runnable tasks
+    indices: Vec<usize, MAX_NUM_TASKS>,
+    /// Priority levels with a runnable task other than the idle task, see the
+    /// `priority-bitmap` feature
+    levels: ReadyLevels,
+}
+
+impl<const MAX_NUM_TASKS: usize> ReadyList<MAX_NUM_TASKS> {
+    /// Create an empty ready list
+    pub const fn new() -> Self {
+        Self {
+            indices: Vec::new(),
+            levels: ReadyLevels::new(),
+        }
+    }
+
+    /// Iterate over the task list indices of the runnable tasks
+    pub fn iter(&self) -> impl Iterator<Item = usize> + Clone + '_ {
+        self.indices.iter().copied()
+    }
+
+    /// Get the highest priority level with a runnable task
+    ///
+    /// # Returns
+    ///
+    /// Highest priority of a runnable task other than the idle task, or `None`
+    /// if there is none
+    #[cfg(feature = "priority-bitmap")]
+    pub fn highest_level(&self) -> Option<usize> {
+        self.levels.highest()
+    }
+
+    /// Add a task appended to the task list, which is created ready
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: Index of the task in the task list
+    /// * `task`: The task
+    pub fn push<SP, TICK>(&mut self, idx: usize, task: &Task<SP, TICK>) {
+        self.insert(idx, task);
+    }
+
+    /// Forget a task removed from the task list
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: Index the task had in the task list
+    /// * `task`: The task
+    ///
+    /// # Note
+    ///
+    /// The tasks after it in the task list move down by one index
+    pub fn remove<SP, TICK>(&mut self, idx: usize, task: &Task<SP, TICK>) {
+        if task.is_runnable() {
+            self.delete(idx, task);
+        }
+
+        for other in self.indices.iter_mut() {
+            if *other > idx {
+                *other -= 1;
+            }
+        }
+    }
+
+    /// Change the state and pend reason of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: Index of the task in the task list
+    /// * `task`: The task
+    /// * `state`: New task state
+    /// * `pend`: New task pend reason
+    pub fn set_state<SP, TICK>(
+        &mut self,
+        idx: usize,
+        task: &mut Task<SP, TICK>,
+        state: TaskState,
+        pend: TaskPendReason<TICK>,
+    ) {
+        let was_runnable = task.is_runnable();
+        task.state = state;
+        task.pend = pend;
+
+        match (was_runnable, task.is_runnable()) {
+            (false, true) => self.insert(idx, task),
+            (true, false) => self.delete(idx, task),
+            _ => (),
+        }
+    }
+
+    /// Make a task ready to run
+    ///
+    /// # Arguments
+    ///
+    /// * `idx`: Index of the task in the task list
+    /// * `task`: The task
+    pub fn set_ready<SP, TICK>(&mut self, idx: usize, task: &mut Task<SP, TICK>) {
+        self.set_state(idx, task, TaskState::Ready, TaskPendReason::NotPending);
+    }
+
+    /// Change the priority of a task
+    ///
+    /// # Arguments
+    ///
+    /// * `task`: The task
+    /// * `priority`: New task priority
+    pub fn set_priority<SP, TICK>(&mut self, task: &mut Task<SP, TICK>, priority: usize) {
+        if task.id != IDLE_TASK_ID && task.is_runnable() {
+            self.levels.remove(task.priority);
+            self.levels.insert(priority);
+        }
+
+        task.priority = priority;
+    }
+
+    fn insert<SP, TICK>(&mut self, idx: usize, task: &Task<SP, TICK>) {
+        // Capacity matches the task list, so there is always room
+        let pos = self
+            .indices
+            .binary_search(&idx)
+            .expect_err("Task already in the ready list");
+        let _ = self.indices.insert(pos, idx);
+
+        if task.id != IDLE_TASK_ID {
+            self.levels.insert(task.priority);
+        }
+    }
+
+    fn delete<SP, TICK>(&mut self, idx: usize, task: &Task<SP, TICK>) {
+        let pos = self
+            .indices
+            .binary_search(&idx)
+            .expect("Task not in the ready list");
+        self.indices.remove(pos);
+
+        if task.id != IDLE_TASK_ID {
+            self.levels.remove(task.priority);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::WaitResult;
+
+    fn task(id: usize, state: TaskState) -> Task<u32, u64> {
+        Task {
+            id,
+            name: None,
+            priority: id,
+            base_priority: id,
+            stack_ptr: 0,
+            state,
+            pend: TaskPendReason::NotPending,
+            last_run: 0,
+            time_slice: None,
+            slice_end: 0,
+            timed_out: false,
+            wait_result: WaitResult::Acquired,
+            pend_seq: 0,
+            mlfq_level: 0,
+            stack_region: None,
+            entry: None,
+            uses_fpu: false,
+            stack_watch: false,
+            run_ticks: 0,
+            deadline: None,
+            release: None,
+            #[cfg(feature = "stats")]
+            jitter_release: None,
+            #[cfg(feature = "stats")]
+            jitter: None,
+            missed_deadlines: 0,
+            watchdog: None,
+            last_kick: 0,
+            message: None,
+            notification: None,
+            notify_action: crate::task::NotifyAction::Overwrite,
+            pre_suspend: None,
+            suspended_wait: None,
+            tls: [core::ptr::null_mut(); crate::kernel::TLS_SLOTS],
+            read_locks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_ready_list_order() {
+        let mut ready: ReadyList<4> = ReadyList::new();
+        let mut tasks = [
+            task(0, TaskState::Ready),
+            task(1, TaskState::Ready),
+            task(2, TaskState::Ready),
+        ];
+
+        for (idx, task) in tasks.iter().enumerate() {
+            ready.push(idx, task);
+        }
+
+        // Indices stay in task list order as tasks block and wake
+        ready.set_state(
+            0,
+            &mut tasks[0],
+            TaskState::Pending,
+            TaskPendReason::Suspended,
+        );
+        ready.set_state(
+            1,
+            &mut tasks[1],
+            TaskState::Pending,
+            TaskPendReason::Suspended,
+        );
+        ready.set_ready(1, &mut tasks[1]);
+        ready.set_ready(0, &mut tasks[0]);
+        assert!(ready.iter().eq([0, 1, 2]));
+
+        // Removing a task moves the tasks after it down
+        ready.remove(1, &tasks[1]);
+        assert!(ready.iter().eq([0, 1]));
+    }
+}
@@ -0,0 +1,111 @@
+//! RuCOS retry helpers
+
+use core::cmp::PartialOrd;
+use core::marker::Copy;
+use core::ops::Add;
+
+/// Retry an operation with exponential backoff
+///
+/// # Generics
+///
+/// * `TICK`: The kernel time data type, usually a numeric type
+///
+/// # Arguments
+///
+/// * `try_fn`: Operation to attempt, returning `true` on success
+/// * `sleep_fn`: Function to sleep the current task for a number of ticks
+/// * `base_ticks`: Number of ticks to sleep after the first failed attempt
+/// * `max_ticks`: Upper bound on the number of ticks to sleep between attempts
+/// * `max_attempts`: Upper bound on the number of times `try_fn` is called
+///
+/// # Returns
+///
+/// `true` if `try_fn` eventually succeeded, `false` if all attempts failed
+///
+/// # Note
+///
+/// The sleep doubles after every failed attempt, up to `max_ticks`. Sleeping
+/// releases the CPU between attempts, so lower priority tasks (e.g. the one
+/// holding the contended resource) can run. There is no sleep after the last
+/// failed attempt.
+pub fn retry_with_backoff<TICK>(
+    mut try_fn: impl FnMut() -> bool,
+    mut sleep_fn: impl FnMut(TICK),
+    base_ticks: TICK,
+    max_ticks: TICK,
+    max_attempts: usize,
+) -> bool
+where
+    TICK: Add<Output = TICK> + Copy + PartialOrd,
+{
+    let mut delay = if base_ticks > max_ticks {
+        max_ticks
+    } else {
+        base_ticks
+    };
+
+    for attempt in 0..max_attempts {
+        if try_fn() {
+            return true;
+        }
+
+        if attempt + 1 < max_attempts {
+            sleep_fn(delay);
+
+            delay = if delay + delay > max_ticks {
+                max_ticks
+            } else {
+                delay + delay
+            };
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_succeeds() {
+        let mut attempts = 0;
+        let mut sleeps = Vec::new();
+
+        let result = retry_with_backoff(
+            || {
+                attempts += 1;
+                attempts == 5
+            },
+            |delay: u64| sleeps.push(delay),
+            2,
+            10,
+            8,
+        );
+
+        assert!(result);
+        assert_eq!(attempts, 5);
+        assert_eq!(sleeps, [2, 4, 8, 10]);
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut attempts = 0;
+        let mut sleeps = Vec::new();
+
+        let result = retry_with_backoff(
+            || {
+                attempts += 1;
+                false
+            },
+            |delay: u64| sleeps.push(delay),
+            1,
+            100,
+            3,
+        );
+
+        assert!(!result);
+        assert_eq!(attempts, 3);
+        assert_eq!(sleeps, [1, 2]);
+    }
+}
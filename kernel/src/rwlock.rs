@@ -0,0 +1,12 @@
+//! RuCOS Read-Write Lock
+
+/// Read-write lock control block
+#[derive(Debug)]
+pub struct RwLock {
+    /// Read-write lock ID
+    pub id: usize,
+    /// Number of tasks holding the lock for reading
+    pub readers: usize,
+    /// ID of the task holding the lock for writing, or `None` if not write locked
+    pub writer: Option<usize>,
+}
@@ -0,0 +1,25 @@
+//! RuCOS Semaphore
+
+/// Order in which tasks waiting on a semaphore are woken up
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WakeOrder {
+    /// Wake up the highest priority waiter
+    #[default]
+    Priority,
+    /// Wake up the longest waiting waiter regardless of priority, so no waiter
+    /// starves
+    Fifo,
+}
+
+/// Semaphore control block
+#[derive(Debug)]
+pub struct Semaphore {
+    /// Semaphore ID
+    pub id: usize,
+    /// Number of times the semaphore can be taken without blocking
+    pub count: usize,
+    /// Upper bound on `count`
+    pub max_count: usize,
+    /// Order in which waiters are woken up
+    pub wake_order: WakeOrder,
+}
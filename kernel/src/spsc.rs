@@ -0,0 +1,252 @@
+//! RuCOS single-producer single-consumer ring buffer
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Lock-free ring buffer for passing data from one producer to one consumer
+///
+/// # Generics
+///
+/// * `T`: Item type
+/// * `CAP`: Maximum number of items in the ring
+///
+/// # Note
+///
+/// Intended to be placed in a `static` to pass data from an interrupt handler
+/// to a task (or the other way around) without a critical section. Only loads
+/// and stores of `AtomicUsize` are used, so it works on cores without
+/// compare-and-swap. At any time there may be at most one context calling
+/// `try_push` and at most one context calling `try_pop`. The ring cannot check
+/// this, and two producers or two consumers racing with each other corrupt it.
+pub struct SpscRing<T, const CAP: usize> {
+    /// Item memory, a slot is initialized between its push and its pop
+    slots: UnsafeCell<[MaybeUninit<T>; CAP]>,
+    /// Position of the next item to pop, written only by the consumer
+    head: AtomicUsize,
+    /// Position of the next item to push, written only by the producer
+    tail: AtomicUsize,
+}
+
+// The producer and consumer each only touch the slots the other has handed over
+unsafe impl<T: Send, const CAP: usize> Sync for SpscRing<T, CAP> {}
+
+impl<T, const CAP: usize> SpscRing<T, CAP> {
+    /// Create an empty ring
+    ///
+    /// # Panics
+    ///
+    /// The `CAP` is `0`
+    pub const fn new() -> Self {
+        assert!(CAP > 0, "Ring capacity must not be zero");
+
+        Self {
+            // An array of `MaybeUninit` needs no initialization
+            slots: UnsafeCell::new(unsafe { MaybeUninit::uninit().assume_init() }),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Add an item to the back of the ring, only called by the producer
+    ///
+    /// # Arguments
+    ///
+    /// * `item`: Item to add
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or `Err(item)` if the ring is full
+    pub fn try_push(&self, item: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        if Self::distance(head, tail) == CAP {
+            return Err(item);
+        }
+
+        // The consumer does not read the slot until the new tail is published
+        unsafe { (*self.slots.get())[tail % CAP].write(item) };
+        self.tail.store(Self::advance(tail), Ordering::Release);
+        Ok(())
+    }
+
+    /// Remove the item at the front of the ring, only called by the consumer
+    ///
+    /// # Returns
+    ///
+    /// Item at the front, or `None` if the ring is empty
+    pub fn try_pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        // The producer does not reuse the slot until the new head is published
+        let item = unsafe { (*self.slots.get())[head % CAP].assume_init_read() };
+        self.head.store(Self::advance(head), Ordering::Release);
+        Some(item)
+    }
+
+    /// Get the number of items in the ring
+    ///
+    /// # Returns
+    ///
+    /// Number of items, which may already be out of date if the producer or the
+    /// consumer runs concurrently
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        Self::distance(head, tail)
+    }
+
+    /// Check if the ring is empty, see `len`
+    ///
+    /// # Returns
+    ///
+    /// `true` if there are no items, `false` if there are
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the maximum number of items in the ring
+    ///
+    /// # Returns
+    ///
+    /// Capacity of the ring
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    // Positions count up to `2 * CAP`, so a full ring is told apart from an
+    // empty one without wasting a slot
+    const fn advance(pos: usize) -> usize {
+        if pos + 1 == 2 * CAP {
+            0
+        } else {
+            pos + 1
+        }
+    }
+
+    const fn distance(head: usize, tail: usize) -> usize {
+        if tail >= head {
+            tail - head
+        } else {
+            tail + 2 * CAP - head
+        }
+    }
+}
+
+impl<T, const CAP: usize> Default for SpscRing<T, CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const CAP: usize> Drop for SpscRing<T, CAP> {
+    fn drop(&mut self) {
+        while self.try_pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_spsc_full_empty() {
+        let ring: SpscRing<u32, 3> = SpscRing::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.try_pop(), None);
+
+        // Wrap around the slots a few times
+        for round in 0..4 {
+            assert_eq!(ring.try_push(round), Ok(()));
+            assert_eq!(ring.try_push(round + 1), Ok(()));
+            assert_eq!(ring.try_push(round + 2), Ok(()));
+            assert_eq!(ring.len(), 3);
+            assert_eq!(ring.try_push(99), Err(99));
+
+            assert_eq!(ring.try_pop(), Some(round));
+            assert_eq!(ring.try_pop(), Some(round + 1));
+            assert_eq!(ring.try_pop(), Some(round + 2));
+            assert_eq!(ring.try_pop(), None);
+            assert!(ring.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_spsc_drop() {
+        let item = Rc::new(());
+        let ring: SpscRing<Rc<()>, 4> = SpscRing::new();
+        assert!(ring.try_push(item.clone()).is_ok());
+        assert!(ring.try_push(item.clone()).is_ok());
+        assert_eq!(Rc::strong_count(&item), 3);
+
+        // Items still in the ring are dropped with it
+        drop(ring);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_spsc_model() {
+        // Random sequences of pushes and pops give the same results as a queue
+        for seed in 0..200u32 {
+            let ring: SpscRing<u32, 5> = SpscRing::new();
+            let mut model = VecDeque::new();
+            let mut state = seed;
+
+            for value in 0..500 {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                if (state >> 16) % 2 == 0 {
+                    let expected = if model.len() < 5 {
+                        model.push_back(value);
+                        Ok(())
+                    } else {
+                        Err(value)
+                    };
+                    assert_eq!(ring.try_push(value), expected);
+                } else {
+                    assert_eq!(ring.try_pop(), model.pop_front());
+                }
+                assert_eq!(ring.len(), model.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_spsc_threads() {
+        const COUNT: u32 = 100_000;
+        let ring: Arc<SpscRing<u32, 8>> = Arc::new(SpscRing::new());
+
+        let producer = {
+            let ring = ring.clone();
+            thread::spawn(move || {
+                for value in 0..COUNT {
+                    while ring.try_push(value).is_err() {
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        // Every item arrives exactly once and in order
+        let mut expected = 0;
+        while expected < COUNT {
+            match ring.try_pop() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        producer.join().unwrap();
+        assert!(ring.is_empty());
+    }
+}
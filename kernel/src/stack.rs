@@ -0,0 +1,231 @@
+//! RuCOS stack helpers
+
+/// Pattern written to the lowest word of a task stack to detect overflow
+pub const STACK_CANARY: u32 = 0xDEAD_BEEF;
+
+/// Size of the canary in bytes
+const STACK_CANARY_SIZE: usize = core::mem::size_of::<u32>();
+
+/// Pattern written to unused task stack memory to measure peak usage
+pub const STACK_FILL: u32 = 0xA5A5_A5A5;
+
+/// Fill a task stack with the fill pattern
+///
+/// # Arguments
+///
+/// * `stack`: Task stack memory, with the lowest address first
+///
+/// # Note
+///
+/// Must be called before the initial task context and the canary are written
+pub fn stack_fill(stack: &mut [u8]) {
+    for word in stack.chunks_mut(4) {
+        word.copy_from_slice(&STACK_FILL.to_ne_bytes()[..word.len()]);
+    }
+}
+
+/// Measure the peak usage of a task stack
+///
+/// # Arguments
+///
+/// * `stack`: Task stack memory, with the lowest address first, filled using
+///   `stack_fill` before the task was created
+///
+/// # Returns
+///
+/// Maximum number of bytes of the stack ever used, not including the canary
+///
+/// # Note
+///
+/// The stack is scanned from the bottom for the first word not matching the
+/// fill pattern, so a task that writes the pattern itself is under-measured
+pub fn stack_high_water(stack: &[u8]) -> usize {
+    let fill = STACK_FILL.to_ne_bytes();
+    let start = stack.len().min(4);
+
+    let unused = stack[start..]
+        .chunks(4)
+        .take_while(|word| *word == &fill[..word.len()])
+        .map(|word| word.len())
+        .sum::<usize>();
+
+    stack.len() - start - unused
+}
+
+/// Write the canary to the bottom of a task stack
+///
+/// # Arguments
+///
+/// * `stack`: Task stack memory, with the lowest address first
+///
+/// # Panics
+///
+/// If the stack is smaller than the canary
+pub fn stack_canary_write(stack: &mut [u8]) {
+    stack[..4].copy_from_slice(&STACK_CANARY.to_ne_bytes());
+}
+
+/// Check the canary at the bottom of a task stack
+///
+/// # Arguments
+///
+/// * `stack`: Task stack memory, with the lowest address first
+///
+/// # Returns
+///
+/// `true` if the canary is intact, `false` if it has been overwritten
+///
+/// # Note
+///
+/// Only detects an overflow that reaches the bottom word of the stack. A task
+/// that skips over it (e.g. with a large local array that is never written in
+/// full) corrupts memory below the stack without being detected.
+pub fn stack_canary_check(stack: &[u8]) -> bool {
+    stack.len() >= 4 && stack[..4] == STACK_CANARY.to_ne_bytes()
+}
+
+/// Get the aligned top of a task stack, where the initial context ends
+///
+/// # Arguments
+///
+/// * `bottom`: Lowest address of the task stack
+/// * `size`: Size of the task stack in bytes
+/// * `align`: Alignment of the top of the stack in bytes
+///
+/// # Returns
+///
+/// End of the stack rounded down to `align`, or `None` if the stack ends
+/// past the address space, so the top cannot be represented
+///
+/// # Panics
+///
+/// The `align` is not a power of two
+///
+/// # Note
+///
+/// The end may still lie below `bottom` after rounding down, see `stack_fits`
+pub fn stack_top(bottom: usize, size: usize, align: usize) -> Option<usize> {
+    assert!(
+        align.is_power_of_two(),
+        "Stack alignment must be a power of two"
+    );
+
+    bottom.checked_add(size).map(|end| end & !(align - 1))
+}
+
+/// Check if a task stack has room for its initial context
+///
+/// # Arguments
+///
+/// * `bottom`: Lowest address of the task stack
+/// * `size`: Size of the task stack in bytes
+/// * `context_size`: Size of the initial context in bytes
+/// * `align`: Alignment of the top of the stack in bytes, a power of two
+///
+/// # Returns
+///
+/// `true` if the initial context fits between the aligned top of the stack and
+/// the canary, `false` if it would be written below the stack or the top
+/// cannot be represented, see `stack_top`
+pub fn stack_fits(bottom: usize, size: usize, context_size: usize, align: usize) -> bool {
+    let Some(top) = stack_top(bottom, size, align) else {
+        return false;
+    };
+
+    bottom
+        .checked_add(STACK_CANARY_SIZE + context_size)
+        .is_some_and(|context_end| top >= context_end)
+}
+
+/// Get the smallest task stack size that always has room for the initial
+/// context, see `stack_fits`
+///
+/// # Arguments
+///
+/// * `context_size`: Size of the initial context in bytes
+/// * `align`: Alignment of the top of the stack in bytes, a power of two
+///
+/// # Returns
+///
+/// Minimum stack size in bytes, including the canary and the up to `align - 1`
+/// bytes lost to aligning the top of the stack
+pub const fn stack_min_size(context_size: usize, align: usize) -> usize {
+    STACK_CANARY_SIZE + context_size + align - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_canary() {
+        let mut stack = [0u8; 64];
+        assert!(!stack_canary_check(&stack));
+
+        stack_canary_write(&mut stack);
+        assert!(stack_canary_check(&stack));
+
+        // Simulate an overflow reaching the bottom of the stack
+        stack[2] = 0;
+        assert!(!stack_canary_check(&stack));
+    }
+
+    #[test]
+    fn test_stack_high_water() {
+        let mut stack = [0u8; 64];
+        stack_fill(&mut stack);
+        stack_canary_write(&mut stack);
+        assert_eq!(stack_high_water(&stack), 0);
+
+        // Simulate the task using the top 24 bytes, with a gap to skip over
+        stack[40..].fill(0);
+        stack[44..48].copy_from_slice(&STACK_FILL.to_ne_bytes());
+        assert_eq!(stack_high_water(&stack), 24);
+
+        stack[4] = 0;
+        assert_eq!(stack_high_water(&stack), 60);
+        assert!(stack_canary_check(&stack));
+    }
+
+    #[test]
+    fn test_stack_fits() {
+        // The canary and a 68 byte context, with up to 7 bytes lost to alignment
+        assert_eq!(stack_min_size(68, 8), 79);
+        for bottom in 0x2000_0000..0x2000_0008 {
+            assert!(stack_fits(bottom, 79, 68, 8));
+        }
+
+        // Smaller stacks only fit if they happen to end on an aligned address
+        assert!(stack_fits(0x2000_0000, 72, 68, 8));
+        assert!(!stack_fits(0x2000_0001, 72, 68, 8));
+        assert!(!stack_fits(0x2000_0000, 71, 68, 8));
+        assert!(!stack_fits(0x2000_0004, 0, 68, 8));
+        assert!(!stack_fits(0x2000_0001, 4, 0, 8));
+    }
+
+    #[test]
+    fn test_stack_fits_awkward_base() {
+        // Near the bottom of the address space, aligning down must not wrap
+        assert_eq!(stack_top(1, 78, 8), Some(72));
+        assert!(!stack_fits(1, 78, 68, 8));
+        assert!(stack_fits(1, 79, 68, 8));
+
+        // A stack ending at the end of the address space has no representable
+        // top, and one ending just before it is aligned down
+        assert_eq!(stack_top(usize::MAX - 99, 100, 8), None);
+        assert!(!stack_fits(usize::MAX - 99, 100, 68, 8));
+        assert_eq!(stack_top(usize::MAX - 100, 100, 8), Some(usize::MAX - 7));
+        assert!(stack_fits(usize::MAX - 100, 100, 68, 8));
+
+        // The aligned top is a multiple of the alignment
+        for bottom in 0x2000_0000..0x2000_0010 {
+            assert_eq!(stack_top(bottom, 100, 8).unwrap() % 8, 0);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Stack alignment must be a power of two")]
+    fn test_stack_top_bad_alignment() {
+        let _ = stack_top(0x2000_0000, 100, 6);
+    }
+}
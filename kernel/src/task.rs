@@ -18,14 +18,190 @@ pub enum TaskState {
 /// # Generics
 ///
 /// * `TICK`: The kernel time data type, usually a numeric type
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum TaskPendReason<TICK> {
     /// The task is not pending
     NotPending,
     /// The task is suspended
     Suspended,
-    /// The task is sleeping until some tick count in the future
-    Sleep(TICK),
+    /// The task is sleeping until some tick count in the future, or until it
+    /// is woken up if there is none
+    Sleep(Option<TICK>),
+    /// The task is waiting on a semaphore, with an optional tick count in the
+    /// future to give up waiting
+    SemWait(usize, Option<TICK>),
+    /// The task is waiting on a mutex, with an optional tick count in the
+    /// future to give up waiting
+    MutexWait(usize, Option<TICK>),
+    /// The task is waiting on a combination of flags in an event group, with
+    /// an optional tick count in the future to give up waiting
+    EventWait {
+        /// Event group ID
+        id: usize,
+        /// Event flags to wait on
+        mask: u32,
+        /// `true` to wait on all flags in `mask`, `false` to wait on any
+        wait_all: bool,
+        /// Tick count to give up waiting
+        timeout: Option<TICK>,
+    },
+    /// The task is waiting for another task to be deleted
+    Join(usize),
+    /// The task is waiting on a mailbox, with an optional tick count in the
+    /// future to give up waiting
+    MailboxWait(usize, Option<TICK>),
+    /// The task is waiting to read lock a read-write lock, with an optional
+    /// tick count in the future to give up waiting
+    ReadWait(usize, Option<TICK>),
+    /// The task is waiting to write lock a read-write lock, with an optional
+    /// tick count in the future to give up waiting
+    WriteWait(usize, Option<TICK>),
+    /// The task is waiting at a barrier
+    BarrierWait(usize),
+    /// The task is waiting on a condition variable, with the mutex to lock
+    /// once notified
+    CondWait(usize, usize),
+    /// The task is waiting for deferred work
+    WorkWait,
+    /// The task is waiting for a notification, with an optional tick count in
+    /// the future to give up waiting
+    NotifyWait(Option<TICK>),
+}
+
+/// Outcome of a blocking wait with a timeout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaitResult {
+    /// The wait ended before the timeout, e.g. the semaphore was taken
+    Acquired,
+    /// The timeout expired
+    TimedOut,
+}
+
+/// Condition that ended a wait for an absolute tick or a notification
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WakeReason {
+    /// The wake up tick was reached without a notification
+    Deadline,
+    /// A notification arrived, with its value
+    Signalled(u32),
+}
+
+/// Deviation of a periodic task from its wake up ticks, in ticks
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jitter<TICK> {
+    /// Smallest deviation recorded
+    pub min: TICK,
+    /// Largest deviation recorded
+    pub max: TICK,
+    /// Deviation of the latest wake up
+    pub last: TICK,
+}
+
+/// How a notification is combined with one the task has not taken yet
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NotifyAction {
+    /// The new value replaces the pending one
+    #[default]
+    Overwrite,
+    /// The new value is bitwise ORed into the pending one
+    SetBits,
+}
+
+/// Task pend reasons without their associated pend data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PendReason {
+    /// The task is not pending
+    NotPending,
+    /// The task is suspended
+    Suspended,
+    /// The task is sleeping
+    Sleep,
+    /// The task is waiting on a semaphore
+    SemWait,
+    /// The task is waiting on a mutex
+    MutexWait,
+    /// The task is waiting on an event group
+    EventWait,
+    /// The task is waiting for another task to be deleted
+    Join,
+    /// The task is waiting on a mailbox
+    MailboxWait,
+    /// The task is waiting to read lock a read-write lock
+    ReadWait,
+    /// The task is waiting to write lock a read-write lock
+    WriteWait,
+    /// The task is waiting at a barrier
+    BarrierWait,
+    /// The task is waiting on a condition variable
+    CondWait,
+    /// The task is waiting for deferred work
+    WorkWait,
+    /// The task is waiting for a notification
+    NotifyWait,
+}
+
+impl<TICK: Copy> TaskPendReason<TICK> {
+    /// Get the tick count at which the task stops pending on its own
+    ///
+    /// # Returns
+    ///
+    /// Tick count to wake up or give up waiting, or `None` if the task pends
+    /// until another task or interrupt handler makes it ready
+    pub fn timeout(&self) -> Option<TICK> {
+        match *self {
+            TaskPendReason::Sleep(timeout) => timeout,
+            TaskPendReason::SemWait(_, timeout) => timeout,
+            TaskPendReason::MutexWait(_, timeout) => timeout,
+            TaskPendReason::EventWait { timeout, .. } => timeout,
+            TaskPendReason::MailboxWait(_, timeout) => timeout,
+            TaskPendReason::ReadWait(_, timeout) => timeout,
+            TaskPendReason::WriteWait(_, timeout) => timeout,
+            TaskPendReason::NotifyWait(timeout) => timeout,
+            _ => None,
+        }
+    }
+}
+
+impl<TICK> TaskPendReason<TICK> {
+    /// Get the pend reason without its pend data
+    ///
+    /// # Returns
+    ///
+    /// Pend reason
+    pub fn reason(&self) -> PendReason {
+        match self {
+            TaskPendReason::NotPending => PendReason::NotPending,
+            TaskPendReason::Suspended => PendReason::Suspended,
+            TaskPendReason::Sleep(_) => PendReason::Sleep,
+            TaskPendReason::SemWait(..) => PendReason::SemWait,
+            TaskPendReason::MutexWait(..) => PendReason::MutexWait,
+            TaskPendReason::EventWait { .. } => PendReason::EventWait,
+            TaskPendReason::Join(_) => PendReason::Join,
+            TaskPendReason::MailboxWait(..) => PendReason::MailboxWait,
+            TaskPendReason::ReadWait(..) => PendReason::ReadWait,
+            TaskPendReason::WriteWait(..) => PendReason::WriteWait,
+            TaskPendReason::BarrierWait(_) => PendReason::BarrierWait,
+            TaskPendReason::CondWait(..) => PendReason::CondWait,
+            TaskPendReason::WorkWait => PendReason::WorkWait,
+            TaskPendReason::NotifyWait(_) => PendReason::NotifyWait,
+        }
+    }
+}
+
+/// Snapshot of a task, for reporting
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TaskInfo {
+    /// Task ID
+    pub id: usize,
+    /// Task name, if any
+    pub name: Option<&'static str>,
+    /// Task priority, including any priority inherited through a mutex
+    pub priority: usize,
+    /// Task state
+    pub state: TaskState,
+    /// Task pend reason
+    pub pend: PendReason,
 }
 
 /// Task control block
@@ -38,14 +214,79 @@ pub enum TaskPendReason<TICK> {
 pub struct Task<SP, TICK> {
     /// Task ID
     pub id: usize,
-    /// Task priority
+    /// Task name for debugging, if any
+    pub name: Option<&'static str>,
+    /// Task priority, including any priority inherited through a mutex
     pub priority: usize,
+    /// Task priority assigned at creation
+    pub base_priority: usize,
     /// Task stack pointer
     pub stack_ptr: SP,
     /// Task state
     pub state: TaskState,
     /// Task pend reason
     pub pend: TaskPendReason<TICK>,
+    /// Value of the kernel context switch counter when the task last started
+    /// running, or `0` if it has never run
+    pub last_run: u64,
+    /// Task time slice, or `None` if the task runs until blocked
+    pub time_slice: Option<TICK>,
+    /// Tick at which the current time slice of the task expires
+    pub slice_end: TICK,
+    /// The last blocking call of the task ended because its timeout expired
+    pub timed_out: bool,
+    /// Outcome of the last blocking call, fixed when the task is switched in
+    pub wait_result: WaitResult,
+    /// Value of the kernel pend counter when the task last started pending,
+    /// used to wake up waiters in arrival order
+    pub pend_seq: u64,
+    /// Number of priority levels the task has been demoted by, see
+    /// `SchedPolicy::Mlfq`
+    pub mlfq_level: usize,
+    /// Lowest address and size in bytes of the task stack, if known
+    pub stack_region: Option<(usize, usize)>,
+    /// Address of the task entry function, if known
+    pub entry: Option<usize>,
+    /// The task has used the floating point unit
+    pub uses_fpu: bool,
+    /// The port watches the task stack limit while the task is running
+    pub stack_watch: bool,
+    /// Number of ticks that elapsed while the task was running
+    pub run_ticks: TICK,
+    /// Number of ticks after each periodic wake up by which the task must sleep
+    /// again, or `None` if the task has no deadline
+    pub deadline: Option<TICK>,
+    /// Tick the task last asked to wake up at, if it has slept
+    pub release: Option<TICK>,
+    /// Tick the task is sleeping until, cleared once it runs again
+    #[cfg(feature = "stats")]
+    pub jitter_release: Option<TICK>,
+    /// Deviation of the task from its wake up ticks, if it has been measured
+    #[cfg(feature = "stats")]
+    pub jitter: Option<Jitter<TICK>>,
+    /// Number of times the task slept again after its deadline
+    pub missed_deadlines: u64,
+    /// Number of ticks within which the task must call `Kernel::task_kick`, or
+    /// `None` if the task has no watchdog
+    pub watchdog: Option<TICK>,
+    /// Tick the task last kicked its watchdog at, or the watchdog was armed at
+    pub last_kick: TICK,
+    /// Item received by the task and not yet taken, e.g. from a mailbox
+    pub message: Option<usize>,
+    /// Notification value received by the task and not yet taken
+    pub notification: Option<u32>,
+    /// How a new notification is combined with the pending one
+    pub notify_action: NotifyAction,
+    /// State and pend reason of the task before it was suspended by
+    /// `suspend_all_except`, restored by `resume_all`
+    pub pre_suspend: Option<(TaskState, TaskPendReason<TICK>)>,
+    /// Pend reason of the task when it was suspended by `Kernel::suspend` while
+    /// blocked, continued by `Kernel::resume`
+    pub suspended_wait: Option<TaskPendReason<TICK>>,
+    /// Task-local storage slots, see `Kernel::tls_set`
+    pub tls: [*mut (); crate::kernel::TLS_SLOTS],
+    /// Read-write locks held by the task for reading, once per lock taken
+    pub read_locks: heapless::Vec<usize, { crate::kernel::MAX_READ_LOCKS }>,
 }
 
 /// Allow comparison of tasks using priority level
@@ -71,4 +312,19 @@ impl<SP, TICK> Task<SP, TICK> {
     pub fn is_runnable(&self) -> bool {
         self.state == TaskState::Ready || self.state == TaskState::Running
     }
+
+    /// Get a snapshot of the task
+    ///
+    /// # Returns
+    ///
+    /// Copy of the reportable task fields
+    pub fn info(&self) -> TaskInfo {
+        TaskInfo {
+            id: self.id,
+            name: self.name,
+            priority: self.priority,
+            state: self.state,
+            pend: self.pend.reason(),
+        }
+    }
 }
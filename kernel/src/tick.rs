@@ -0,0 +1,106 @@
+//! RuCOS tick arithmetic
+
+use core::cmp::PartialOrd;
+use core::default::Default;
+use core::fmt::Debug;
+use core::marker::Copy;
+
+/// Kernel time data types
+///
+/// Implemented for `u16`, `u32`, and `u64`. Tick arithmetic wraps around to
+/// zero once the maximum value is reached, e.g. after about 49.7 days for a
+/// `u32` tick at 1 kHz.
+pub trait Tick: Copy + Debug + Default + PartialOrd {
+    /// Largest tick value, passed to `Kernel::sleep` to sleep until woken
+    const MAX: Self;
+
+    /// Add a number of ticks, wrapping around at the maximum value
+    ///
+    /// # Arguments
+    ///
+    /// * `rhs`: Number of ticks to add
+    ///
+    /// # Returns
+    ///
+    /// Sum of the ticks, modulo the tick range
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// Subtract a number of ticks, wrapping around at zero
+    ///
+    /// # Arguments
+    ///
+    /// * `rhs`: Number of ticks to subtract
+    ///
+    /// # Returns
+    ///
+    /// Difference of the ticks, modulo the tick range
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    /// Compare two ticks, allowing for the tick counter to have wrapped
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: Tick to compare against
+    ///
+    /// # Returns
+    ///
+    /// `true` if `self` is at or after `other`, `false` if not
+    ///
+    /// # Note
+    ///
+    /// The ticks must be less than half the tick range apart, so the wrapping
+    /// difference between them can be treated as signed
+    fn wrapping_ge(self, other: Self) -> bool;
+}
+
+macro_rules! impl_tick {
+    ($($tick:ty, $signed:ty);*) => {
+        $(
+            impl Tick for $tick {
+                const MAX: Self = <$tick>::MAX;
+
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    <$tick>::wrapping_add(self, rhs)
+                }
+
+                fn wrapping_sub(self, rhs: Self) -> Self {
+                    <$tick>::wrapping_sub(self, rhs)
+                }
+
+                fn wrapping_ge(self, other: Self) -> bool {
+                    self.wrapping_sub(other) as $signed >= 0
+                }
+            }
+        )*
+    };
+}
+
+impl_tick!(u16, i16; u32, i32; u64, i64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrapping_add() {
+        assert_eq!(Tick::wrapping_add(u32::MAX - 1, 3), 1);
+        assert_eq!(Tick::wrapping_add(u64::MAX, 1), 0);
+    }
+
+    #[test]
+    fn test_wrapping_sub() {
+        assert_eq!(Tick::wrapping_sub(5u32, 3), 2);
+        assert_eq!(Tick::wrapping_sub(1u32, 3), u32::MAX - 1);
+    }
+
+    #[test]
+    fn test_wrapping_ge() {
+        assert!(5u32.wrapping_ge(5));
+        assert!(6u32.wrapping_ge(5));
+        assert!(!5u32.wrapping_ge(6));
+
+        // Across the wrap boundary
+        assert!(2u32.wrapping_ge(u32::MAX - 2));
+        assert!(!(u32::MAX - 2).wrapping_ge(2));
+    }
+}
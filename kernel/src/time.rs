@@ -0,0 +1,192 @@
+//! RuCOS time conversions
+
+const MS_PER_SECOND: u64 = 1_000;
+const US_PER_SECOND: u64 = 1_000_000;
+
+/// Convert a duration to a number of ticks, rounding up
+fn to_ticks(duration: u64, units_per_second: u64, tick_rate_hz: u64) -> u64 {
+    let ticks = (duration as u128 * tick_rate_hz as u128).div_ceil(units_per_second as u128);
+    u64::try_from(ticks).unwrap_or(u64::MAX)
+}
+
+/// Convert a number of ticks to a duration, rounding down
+fn from_ticks(ticks: u64, units_per_second: u64, tick_rate_hz: u64) -> u64 {
+    let duration = ticks as u128 * units_per_second as u128 / tick_rate_hz as u128;
+    u64::try_from(duration).unwrap_or(u64::MAX)
+}
+
+/// Convert milliseconds to ticks
+///
+/// # Arguments
+///
+/// * `ms`: Duration in milliseconds
+/// * `tick_rate_hz`: Kernel tick rate in hertz
+///
+/// # Returns
+///
+/// Number of ticks, rounded up so that a non-zero duration is at least one tick
+pub fn ms_to_ticks(ms: u64, tick_rate_hz: u64) -> u64 {
+    to_ticks(ms, MS_PER_SECOND, tick_rate_hz)
+}
+
+/// Convert microseconds to ticks
+///
+/// # Arguments
+///
+/// * `us`: Duration in microseconds
+/// * `tick_rate_hz`: Kernel tick rate in hertz
+///
+/// # Returns
+///
+/// Number of ticks, rounded up so that a non-zero duration is at least one tick
+pub fn us_to_ticks(us: u64, tick_rate_hz: u64) -> u64 {
+    to_ticks(us, US_PER_SECOND, tick_rate_hz)
+}
+
+/// Convert ticks to milliseconds
+///
+/// # Arguments
+///
+/// * `ticks`: Number of ticks
+/// * `tick_rate_hz`: Kernel tick rate in hertz
+///
+/// # Returns
+///
+/// Duration in milliseconds, rounded down
+pub fn ticks_to_ms(ticks: u64, tick_rate_hz: u64) -> u64 {
+    from_ticks(ticks, MS_PER_SECOND, tick_rate_hz)
+}
+
+/// Convert ticks to microseconds
+///
+/// # Arguments
+///
+/// * `ticks`: Number of ticks
+/// * `tick_rate_hz`: Kernel tick rate in hertz
+///
+/// # Returns
+///
+/// Duration in microseconds, rounded down
+pub fn ticks_to_us(ticks: u64, tick_rate_hz: u64) -> u64 {
+    from_ticks(ticks, US_PER_SECOND, tick_rate_hz)
+}
+
+/// Converter from the counts of a timer that keeps running in deep sleep to
+/// kernel ticks
+///
+/// # Note
+///
+/// Intended to resynchronize the tick counter after the tick interrupt was
+/// stopped, e.g. in a low power mode clocked by a 32768 Hz oscillator. The
+/// fraction of a tick left over by each conversion is carried into the next
+/// one, so repeated sleeps do not drift.
+#[derive(Clone, Copy, Debug)]
+pub struct TickResync {
+    counter_hz: u64,
+    tick_rate_hz: u64,
+    carry: u64,
+}
+
+impl TickResync {
+    /// Create a converter
+    ///
+    /// # Arguments
+    ///
+    /// * `counter_hz`: Count rate of the timer in hertz
+    /// * `tick_rate_hz`: Kernel tick rate in hertz
+    ///
+    /// # Panics
+    ///
+    /// The `counter_hz` or the `tick_rate_hz` is `0`
+    pub const fn new(counter_hz: u64, tick_rate_hz: u64) -> Self {
+        assert!(counter_hz > 0 && tick_rate_hz > 0, "Rates must not be zero");
+
+        Self {
+            counter_hz,
+            tick_rate_hz,
+            carry: 0,
+        }
+    }
+
+    /// Convert the counts that elapsed during a sleep to ticks
+    ///
+    /// # Arguments
+    ///
+    /// * `counts`: Number of timer counts that elapsed
+    ///
+    /// # Returns
+    ///
+    /// Number of whole ticks that elapsed, including the fractions carried over
+    /// from earlier conversions
+    pub fn ticks(&mut self, counts: u64) -> u64 {
+        let total = counts as u128 * self.tick_rate_hz as u128 + self.carry as u128;
+        self.carry = (total % self.counter_hz as u128) as u64;
+        u64::try_from(total / self.counter_hz as u128).unwrap_or(u64::MAX)
+    }
+
+    /// Convert a sleep duration to timer counts, e.g. to program a wake up timer
+    ///
+    /// # Arguments
+    ///
+    /// * `ticks`: Number of ticks to sleep
+    ///
+    /// # Returns
+    ///
+    /// Number of timer counts, rounded down so the sleep does not overshoot
+    pub fn counts(&self, ticks: u64) -> u64 {
+        from_ticks(ticks, self.counter_hz, self.tick_rate_hz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ms_to_ticks() {
+        assert_eq!(ms_to_ticks(500, 1000), 500);
+        assert_eq!(ms_to_ticks(0, 100), 0);
+        assert_eq!(ms_to_ticks(1, 100), 1);
+        assert_eq!(ms_to_ticks(10, 100), 1);
+        assert_eq!(ms_to_ticks(11, 100), 2);
+        assert_eq!(ms_to_ticks(u64::MAX, 1000), u64::MAX);
+        assert_eq!(ms_to_ticks(u64::MAX, 10_000), u64::MAX);
+    }
+
+    #[test]
+    fn test_us_to_ticks() {
+        assert_eq!(us_to_ticks(1, 1000), 1);
+        assert_eq!(us_to_ticks(1000, 1000), 1);
+        assert_eq!(us_to_ticks(1001, 1000), 2);
+        assert_eq!(us_to_ticks(0, 1000), 0);
+    }
+
+    #[test]
+    fn test_ticks_to_ms_and_us() {
+        assert_eq!(ticks_to_ms(500, 1000), 500);
+        assert_eq!(ticks_to_ms(3, 100), 30);
+        assert_eq!(ticks_to_ms(1, 3000), 0);
+        assert_eq!(ticks_to_us(1, 1000), 1000);
+        assert_eq!(ticks_to_us(1, 3000), 333);
+        assert_eq!(ticks_to_us(u64::MAX, 1000), u64::MAX);
+    }
+
+    #[test]
+    fn test_tick_resync() {
+        let mut resync = TickResync::new(32_768, 1000);
+        assert_eq!(resync.counts(1000), 32_768);
+        assert_eq!(resync.counts(1), 32);
+        assert_eq!(resync.ticks(32_768), 1000);
+
+        // Fractions of a tick add up over many short sleeps
+        let mut ticks = 0;
+        for _ in 0..1000 {
+            ticks += resync.ticks(33);
+        }
+        assert_eq!(ticks, 33_000 * 1000 / 32_768);
+
+        // The carried fraction counts toward the next sleep
+        assert_eq!(ticks + resync.ticks(1000), 34_000 * 1000 / 32_768);
+        assert_eq!(resync.ticks(0), 0);
+    }
+}